@@ -2,8 +2,6 @@ use std::io;
 use std::io::prelude::*;
 
 pub trait ByteParsable {
-    fn parse_u1_as_bytes(&mut self) -> io::Result<[u8; 1]>;
-    fn parse_u2_as_bytes(&mut self) -> io::Result<[u8; 2]>;
     fn parse_u4_as_bytes(&mut self) -> io::Result<[u8; 4]>;
 
     fn parse_n_bytes(&mut self, n: usize) -> io::Result<Vec<u8>>;
@@ -12,6 +10,10 @@ pub trait ByteParsable {
     fn parse_u2(&mut self) -> io::Result<u16>;
     fn parse_u4(&mut self) -> io::Result<u32>;
 
+    fn parse_i1(&mut self) -> io::Result<i8>;
+    fn parse_i2(&mut self) -> io::Result<i16>;
+    fn parse_i4(&mut self) -> io::Result<i32>;
+
     fn parse_u4_as_f32(&mut self) -> io::Result<f32>;
     fn parse_u4_as_i32(&mut self) -> io::Result<i32>;
 
@@ -23,94 +25,232 @@ pub trait ByteParsable {
 
 impl<T> ByteParsable for T
 where
-    T: Read
+    T: Read,
 {
-    fn parse_u1_as_bytes(&mut self) -> io::Result<[u8; 1]> {
-        let mut buf = [0; 1];
-        self.read(&mut buf)?;
-        Ok(buf)
-    }
-
-    fn parse_u2_as_bytes(&mut self) -> io::Result<[u8; 2]> {
-        let mut buf = [0; 2];
-        self.read(&mut buf)?;
-        Ok(buf)
-    }
-
     fn parse_u4_as_bytes(&mut self) -> io::Result<[u8; 4]> {
         let mut buf = [0; 4];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(buf)
     }
 
     fn parse_n_bytes(&mut self, n: usize) -> io::Result<Vec<u8>> {
         let mut bytes = vec![0; n];
-        
-        if self.read(&mut bytes)? < n {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Expected more bytes than were read",
-            ));
-        }
+        self.read_exact(&mut bytes)?;
 
         Ok(bytes)
     }
 
     fn parse_u1(&mut self) -> io::Result<u8> {
         let mut buf = [0; 1];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(u8::from_be_bytes(buf))
     }
 
     fn parse_u2(&mut self) -> io::Result<u16> {
         let mut buf = [0; 2];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(u16::from_be_bytes(buf))
     }
 
     fn parse_u4(&mut self) -> io::Result<u32> {
         let mut buf = [0; 4];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(u32::from_be_bytes(buf))
     }
 
+    fn parse_i1(&mut self) -> io::Result<i8> {
+        let mut buf = [0; 1];
+        self.read_exact(&mut buf)?;
+
+        Ok(i8::from_be_bytes(buf))
+    }
+
+    fn parse_i2(&mut self) -> io::Result<i16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+
+        Ok(i16::from_be_bytes(buf))
+    }
+
+    fn parse_i4(&mut self) -> io::Result<i32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+
+        Ok(i32::from_be_bytes(buf))
+    }
+
     fn parse_u4_as_f32(&mut self) -> io::Result<f32> {
         let mut buf = [0; 4];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(f32::from_be_bytes(buf))
     }
 
     fn parse_u4_as_i32(&mut self) -> io::Result<i32> {
         let mut buf = [0; 4];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(i32::from_be_bytes(buf))
     }
 
     fn parse_u8_as_f64(&mut self) -> io::Result<f64> {
         let mut buf = [0; 8];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(f64::from_be_bytes(buf))
     }
 
     fn parse_u8_as_i64(&mut self) -> io::Result<i64> {
         let mut buf = [0; 8];
-        self.read(&mut buf)?;
+        self.read_exact(&mut buf)?;
 
         Ok(i64::from_be_bytes(buf))
     }
 
     fn parse_utf8(&mut self, len: u16) -> io::Result<String> {
         let mut buf = vec![0; len as usize];
-        self.read(&mut buf)?;
 
-        let str = String::from_utf8(buf).expect("Could not parse Utf-8");
+        self.read_exact(&mut buf)?;
+
+        decode_modified_utf8(&buf)
+    }
+}
+
+/**
+ * Decodes the "modified UTF-8" the class file format uses for strings. It differs from standard
+ * UTF-8 in that `\0` is encoded in two bytes (`C0 80`) and characters outside the BMP are
+ * encoded as a surrogate pair with three bytes per surrogate instead of four bytes in total.
+ * Java strings can hold unpaired surrogates, which a Rust `String` can't, so those decode to
+ * U+FFFD. Only byte sequences that are not modified UTF-8 at all are errors.
+ */
+pub fn decode_modified_utf8(bytes: &[u8]) -> io::Result<String> {
+    let invalid = |position: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid modified UTF-8 byte at position {position}"),
+        )
+    };
+
+    // Without a `\0`, a surrogate pair or a four byte sequence (which modified UTF-8 doesn't
+    // have) the two encodings are the same, which is the case for almost every string
+    if !bytes.iter().any(|&byte| byte == 0 || byte >= 0xF0) {
+        if let Ok(value) = std::str::from_utf8(bytes) {
+            return Ok(value.to_string());
+        }
+    }
+
+    let continuation = |position: usize| match bytes.get(position) {
+        Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u16),
+        _ => Err(invalid(position)),
+    };
+
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        match byte {
+            0x01..=0x7F => {
+                units.push(byte as u16);
+                i += 1;
+            }
+            0xC0..=0xDF => {
+                units.push(((byte & 0x1F) as u16) << 6 | continuation(i + 1)?);
+                i += 2;
+            }
+            0xE0..=0xEF => {
+                units.push(((byte & 0x0F) as u16) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?);
+                i += 3;
+            }
+            _ => return Err(invalid(i)),
+        }
+    }
+
+    Ok(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_reads_are_twos_complement() {
+        // A backward branch offset
+        assert_eq!((&[0xFF, 0xFB][..]).parse_i2().unwrap(), -5);
+        assert_eq!((&[0xFF, 0xFB][..]).parse_u2().unwrap(), 0xFFFB);
+
+        assert_eq!((&[0xFF][..]).parse_i1().unwrap(), -1);
+        assert_eq!((&[0x80, 0x00, 0x00, 0x00][..]).parse_i4().unwrap(), i32::MIN);
+        assert_eq!((&[0x7F, 0xFF][..]).parse_i2().unwrap(), i16::MAX);
+    }
+
+    #[test]
+    fn truncated_utf8_is_an_error() {
+        let err = (&b"Hel"[..]).parse_utf8(5).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // A two byte sequence cut off after its first byte
+        let err = (&[b'a', 0xC3][..]).parse_utf8(2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        assert_eq!((&b"Hello"[..]).parse_utf8(5).unwrap(), "Hello");
+    }
+
+    /**
+     * Hands out one byte per `read` call, like a slow pipe
+     */
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = buf.len().min(self.0.len()).min(1);
+
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn short_reads_are_completed_or_fail() {
+        assert_eq!(OneByteReader(&[0xCA, 0xFE, 0xBA, 0xBE]).parse_u4().unwrap(), 0xCAFEBABE);
+        assert_eq!(OneByteReader(&[0x12, 0x34]).parse_u2().unwrap(), 0x1234);
+
+        // Truncated input is an error instead of zero padded data
+        for result in [
+            (&[0x12][..]).parse_u2().map(|_| ()),
+            (&[0x12, 0x34, 0x56][..]).parse_u4().map(|_| ()),
+            (&[0; 7][..]).parse_u8_as_i64().map(|_| ()),
+            OneByteReader(&[0x12, 0x34]).parse_n_bytes(3).map(|_| ()),
+        ] {
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn decodes_modified_utf8() {
+        // `\0` is two bytes, so a class file string never contains a zero byte
+        assert_eq!(decode_modified_utf8(&[b'a', 0xC0, 0x80, b'b']).unwrap(), "a\0b");
+        assert!(decode_modified_utf8(&[b'a', 0x00]).is_err());
+
+        // U+1F600 is the surrogate pair D83D DE00, three bytes each
+        assert_eq!(
+            decode_modified_utf8(&[0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]).unwrap(),
+            "\u{1F600}"
+        );
+        // Standard UTF-8's four byte form doesn't exist in modified UTF-8
+        assert!(decode_modified_utf8("\u{1F600}".as_bytes()).is_err());
+
+        // A lone surrogate is a valid Java string, but not a valid Rust one
+        assert_eq!(decode_modified_utf8(&[b'x', 0xED, 0xA0, 0xBD]).unwrap(), "x\u{FFFD}");
 
-        Ok(str)
+        // Truncated sequences and stray continuation bytes
+        assert!(decode_modified_utf8(&[0xE2, 0x82]).is_err());
+        assert!(decode_modified_utf8(&[0x80]).is_err());
     }
 }
@@ -1,17 +1,339 @@
-use crate::bytes::ByteParsable;
+use crate::{
+    bytes::{decode_modified_utf8, ByteParsable},
+    descriptor::{descriptors_equal, parse_field_descriptor, parse_method_descriptor, FieldType},
+    instruction::{instruction_length, LDC, LDC2_W, LDC_W},
+};
 use std::{
-    fs::File,
+    cell::{Cell, OnceCell},
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
     io::{self, Cursor, Read},
     path::PathBuf,
+    rc::Rc,
 };
 
+use log::warn;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-pub fn parse_class_file(path: &PathBuf) -> io::Result<ClassFile> {
+/** The lowest class file version this parser understands (JDK 1.0.2) */
+pub const MIN_SUPPORTED_MAJOR_VERSION: u16 = 45;
+
+/** The highest class file version this parser understands (Java 21) */
+pub const MAX_SUPPORTED_MAJOR_VERSION: u16 = 65;
+
+/**
+ * Why a class file could not be parsed
+ */
+#[derive(Debug)]
+pub enum ClassParseError {
+    /** The file could not be read */
+    Io(io::Error),
+    /** The first four bytes are not `0xCAFEBABE` */
+    BadMagic([u8; 4]),
+    /** The class file ended before a structure was complete */
+    TruncatedInput,
+    /** The major version is outside `MIN_SUPPORTED_MAJOR_VERSION..=MAX_SUPPORTED_MAJOR_VERSION` */
+    UnsupportedVersion(u16),
+    /** A constant pool entry starts with a tag that is not defined by the JVM spec */
+    UnexpectedConstantTag(u8),
+    /** A constant pool index that should point to a `Utf8` entry does not */
+    InvalidUtf8Index(u16),
+    /** A constant pool index that should point to a `Class` entry does not */
+    InvalidClassIndex(u16),
+    /** Any other structural problem, such as a length that disagrees with its contents */
+    Malformed(String),
+}
+
+impl fmt::Display for ClassParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassParseError::Io(err) => write!(f, "{err}"),
+            ClassParseError::BadMagic(magic) => {
+                write!(f, "Expected class file magic but got {magic:02X?}")
+            }
+            ClassParseError::TruncatedInput => write!(f, "Class file ended unexpectedly"),
+            ClassParseError::UnsupportedVersion(major_version) => write!(
+                f,
+                "Unsupported class file version {major_version} (expected {MIN_SUPPORTED_MAJOR_VERSION} to {MAX_SUPPORTED_MAJOR_VERSION})"
+            ),
+            ClassParseError::UnexpectedConstantTag(tag) => {
+                write!(f, "Unexpected constant pool tag {tag}")
+            }
+            ClassParseError::InvalidUtf8Index(index) => {
+                write!(f, "Expected constant pool index {index} to be utf-8")
+            }
+            ClassParseError::InvalidClassIndex(index) => {
+                write!(f, "Expected constant pool index {index} to be a class")
+            }
+            ClassParseError::Malformed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ClassParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClassParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClassParseError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => ClassParseError::TruncatedInput,
+            io::ErrorKind::InvalidData => ClassParseError::Malformed(err.to_string()),
+            _ => ClassParseError::Io(err),
+        }
+    }
+}
+
+impl From<ClassParseError> for io::Error {
+    fn from(err: ClassParseError) -> Self {
+        match err {
+            ClassParseError::Io(err) => err,
+            ClassParseError::TruncatedInput => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, err.to_string())
+            }
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+pub fn parse_class_file(path: &PathBuf) -> Result<ClassFile, ClassParseError> {
+    let bytes = fs::read(path)?;
+
+    parse_class_bytes(&bytes)
+}
+
+/**
+ * Parses a class file that is already in memory (ex. read out of a jar)
+ */
+pub fn parse_class_bytes(bytes: &[u8]) -> Result<ClassFile, ClassParseError> {
+    let mut context = ParseContext {
+        length: Some(bytes.len() as u64),
+        ..ParseContext::default()
+    };
+
+    let class = ClassFile::parse(&mut context, &mut Cursor::new(bytes))?;
+
+    for warning in &context.warnings {
+        warn!("{warning}");
+    }
+
+    for attribute_name in &context.unknown_attributes {
+        warn!("Got unexpected attribute kind with name: {attribute_name}");
+    }
+
+    Ok(class)
+}
+
+pub fn parse_class_file_with_context(
+    path: &PathBuf,
+    context: &mut ParseContext,
+) -> Result<ClassFile, ClassParseError> {
     let mut f = File::open(path)?;
 
-    ClassFile::parse(&mut f)
+    context.length = Some(f.metadata()?.len());
+
+    let class = ClassFile::parse(context, &mut f);
+
+    context.length = None;
+
+    class
+}
+
+/**
+ * Parses a class file that is already in memory. With `ParseContext::lazy_code` set, the
+ * bytecode of each method is sliced out of `source` when it is needed instead of being copied,
+ * and with `ParseContext::lazy_utf8` set the same goes for the `Utf8` constants.
+ */
+pub fn parse_class_source(
+    source: Rc<[u8]>,
+    context: &mut ParseContext,
+) -> Result<ClassFile, ClassParseError> {
+    context.source = Some(source.clone());
+    context.length = Some(source.len() as u64);
+
+    let class = ClassFile::parse(context, &mut Cursor::new(&source[..]));
+
+    context.source = None;
+    context.length = None;
+
+    class
+}
+
+/**
+ * The names that place a class in the type hierarchy
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassIdentity {
+    pub this_name: String,
+    /** `None` only for `java/lang/Object` */
+    pub super_name: Option<String>,
+    pub interface_names: Vec<String>,
+}
+
+/**
+ * Reads a class file only up to the end of its `interfaces` table, which is enough to know its
+ * name, superclass and interfaces without parsing any fields, methods or attributes
+ */
+pub fn parse_class_identity(mut r: &mut dyn Read) -> Result<ClassIdentity, ClassParseError> {
+    let _magic = parse_magic(r)?;
+    let _minor_version = r.parse_u2()?;
+    let _major_version = parse_major_version(r)?;
+
+    let constant_pool = parse_constant_pool(&ParseContext::default(), r)?;
+
+    let _access_flags = r.parse_u2()?;
+
+    let class_name = |index: u16| {
+        constant_pool
+            .get_class_name_from_index(index)
+            .cloned()
+            .map_err(|_| ClassParseError::InvalidClassIndex(index))
+    };
+
+    let this_name = class_name(r.parse_u2()?)?;
+
+    let super_name = match r.parse_u2()? {
+        0 => None,
+        super_class => Some(class_name(super_class)?),
+    };
+
+    let interfaces_count = r.parse_u2()?;
+
+    let mut interface_names = Vec::with_capacity(interfaces_count as usize);
+
+    for _ in 0..interfaces_count {
+        interface_names.push(class_name(r.parse_u2()?)?);
+    }
+
+    Ok(ClassIdentity {
+        this_name,
+        super_name,
+        interface_names,
+    })
+}
+
+fn parse_magic(mut f: &mut dyn Read) -> Result<[u8; 4], ClassParseError> {
+    let magic = f.parse_u4_as_bytes()?;
+
+    if &magic != b"\xCA\xFE\xBA\xBE" {
+        return Err(ClassParseError::BadMagic(magic));
+    }
+
+    Ok(magic)
+}
+
+fn parse_major_version(mut f: &mut dyn Read) -> Result<u16, ClassParseError> {
+    let major_version = f.parse_u2()?;
+
+    if !(MIN_SUPPORTED_MAJOR_VERSION..=MAX_SUPPORTED_MAJOR_VERSION).contains(&major_version) {
+        return Err(ClassParseError::UnsupportedVersion(major_version));
+    }
+
+    Ok(major_version)
+}
+
+fn parse_constant_pool(
+    context: &ParseContext,
+    mut f: &mut dyn Read,
+) -> Result<Vec<ConstantPoolInfo>, ClassParseError> {
+    let constant_pool_count = f.parse_u2()?;
+
+    let mut constant_pool: Vec<ConstantPoolInfo> =
+        Vec::with_capacity(constant_pool_count as usize);
+
+    while constant_pool.len() + 1 < constant_pool_count as usize {
+        let entry = ConstantPoolInfo::parse(context, f)?;
+        let takes_two_slots = matches!(
+            entry,
+            ConstantPoolInfo::Long { .. } | ConstantPoolInfo::Double { .. }
+        );
+
+        constant_pool.push(entry);
+
+        if takes_two_slots {
+            constant_pool.push(ConstantPoolInfo::Unusable);
+        }
+    }
+
+    Ok(constant_pool)
+}
+
+/**
+ * Options and diagnostics shared by everything parsed out of a single class file
+ */
+#[derive(Debug, Default)]
+pub struct ParseContext {
+    /**
+     * Turns recoverable problems (ex. an attribute that is longer than its contents) into errors
+     */
+    pub strict: bool,
+    /**
+     * Keep the location of each method's bytecode instead of copying it (only applies to
+     * `parse_class_source`, which has the whole class file in memory)
+     */
+    pub lazy_code: bool,
+    /**
+     * Keep the location of each `Utf8` constant and decode it on first lookup instead of
+     * allocating every string up front (only applies to `parse_class_source`). Strings that are
+     * not valid modified UTF-8 are then only found when they are looked up, which fails with
+     * `InvalidConstant`, while an eager parse rejects the whole class. That is the price of not
+     * decoding strings nobody reads, so a lazily parsed class is not validated as thoroughly.
+     */
+    pub lazy_utf8: bool,
+    /**
+     * Record where each method and attribute is in the class file (see `MethodInfo::span`)
+     */
+    pub record_spans: bool,
+    /**
+     * Recoverable problems that were found while parsing
+     */
+    pub warnings: Vec<String>,
+    /**
+     * Names of the attributes that were kept as `AttributeKind::Other` because the parser does
+     * not model them, each listed once in the order they were first seen
+     */
+    pub unknown_attributes: Vec<String>,
+    source: Option<Rc<[u8]>>,
+    /** Number of bytes read from the class file so far */
+    offset: Rc<Cell<u64>>,
+    /** How many attributes deep the parser currently is */
+    attribute_depth: usize,
+    /** Total size of the class file, if known up front */
+    length: Option<u64>,
+}
+
+impl ParseContext {
+    /**
+     * Number of bytes left in the class file, if its size is known
+     */
+    fn remaining(&self) -> Option<u64> {
+        self.length
+            .map(|length| length.saturating_sub(self.offset.get()))
+    }
+}
+
+/**
+ * Keeps track of how many bytes have been read from the class file
+ */
+struct CountingReader<'a> {
+    inner: &'a mut dyn Read,
+    offset: Rc<Cell<u64>>,
+}
+
+impl Read for CountingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.offset.set(self.offset.get() + count as u64);
+
+        Ok(count)
+    }
 }
 
 #[allow(dead_code)]
@@ -24,10 +346,43 @@ pub struct ClassFile {
     pub access_flags: Vec<ClassAccessFlags>,
     pub this_class: u16,
     pub super_class: u16,
+    /** Constant pool indices of the `Class` entries of the directly implemented interfaces */
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<FieldInfo>,
     pub methods: Vec<MethodInfo>,
     pub attributes: Vec<AttributeInfo>,
 }
 
+/**
+ * Serialized field by field, with the names of `this_class`, `super_class` and `interfaces`
+ * resolved next to their indices so consumers don't have to walk the constant pool
+ */
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClassFile {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut class = serializer.serialize_struct("ClassFile", 14)?;
+
+        class.serialize_field("magic", &self.magic)?;
+        class.serialize_field("minor_version", &self.minor_version)?;
+        class.serialize_field("major_version", &self.major_version)?;
+        class.serialize_field("constant_pool", &self.constant_pool)?;
+        class.serialize_field("access_flags", &self.access_flags)?;
+        class.serialize_field("this_class", &self.this_class)?;
+        class.serialize_field("this_class_name", self.get_this_class_name())?;
+        class.serialize_field("super_class", &self.super_class)?;
+        class.serialize_field("super_class_name", &self.get_super_class_name())?;
+        class.serialize_field("interfaces", &self.interfaces)?;
+        class.serialize_field("interface_names", &self.get_interface_names())?;
+        class.serialize_field("fields", &self.fields)?;
+        class.serialize_field("methods", &self.methods)?;
+        class.serialize_field("attributes", &self.attributes)?;
+
+        class.end()
+    }
+}
+
 #[allow(non_snake_case, non_upper_case_globals)]
 mod ConstantPoolType {
     pub const Class: u8 = 7;
@@ -47,7 +402,8 @@ mod ConstantPoolType {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ConstantPoolInfo {
     Class {
         name_index: u16,
@@ -97,9 +453,78 @@ pub enum ConstantPoolInfo {
         bootstrap_method_attr_index: u16,
         name_and_type_index: u16,
     },
+    /**
+     * Stands in for the slot after a `Long` or `Double`, which take up two constant pool
+     * indices. It is never a valid index to refer to.
+     */
+    Unusable,
+    /**
+     * A `Utf8` constant that is decoded the first time it is looked up (see
+     * `ParseContext::lazy_utf8`)
+     */
+    LazyUtf8 {
+        value: LazyString,
+    },
+}
+
+/**
+ * The location of a `Utf8` constant's bytes in the class file, decoded and cached on first use
+ */
+#[derive(Clone)]
+pub struct LazyString {
+    source: Rc<[u8]>,
+    offset: usize,
+    length: usize,
+    decoded: OnceCell<String>,
+}
+
+impl LazyString {
+    pub fn bytes(&self) -> &[u8] {
+        &self.source[self.offset..self.offset + self.length]
+    }
+
+    /**
+     * Returns the decoded string, or `None` if the bytes are not valid modified UTF-8
+     */
+    pub fn get(&self) -> Option<&String> {
+        if let Some(value) = self.decoded.get() {
+            return Some(value);
+        }
+
+        let value = decode_modified_utf8(self.bytes()).ok()?;
+
+        Some(self.decoded.get_or_init(|| value))
+    }
+}
+
+impl fmt::Debug for LazyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(value) => write!(f, "{value:?}"),
+            None => write!(f, "{:02X?}", self.bytes()),
+        }
+    }
+}
+
+impl PartialEq for LazyString {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes() == other.bytes()
+    }
+}
+
+/** Serialized as the decoded string, or the raw bytes if they are not valid modified UTF-8 */
+#[cfg(feature = "serde")]
+impl serde::Serialize for LazyString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.get() {
+            Some(value) => serializer.serialize_str(value),
+            None => self.bytes().serialize(serializer),
+        }
+    }
 }
 
-#[derive(Debug, EnumIter, Clone, Copy)]
+#[derive(Debug, EnumIter, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ClassAccessFlags {
     Public = 0x0001,
     Final = 0x0010,
@@ -112,6 +537,7 @@ pub enum ClassAccessFlags {
 }
 
 #[derive(Debug, EnumIter, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MethodAccessFlags {
     Public = 0x0001,
     Private = 0x0002,
@@ -127,7 +553,57 @@ pub enum MethodAccessFlags {
     Synthetic = 0x1000,
 }
 
+/**
+ * The flags of an `InnerClasses` entry. Unlike the flags of a top level class these can
+ * include the member modifiers `private`, `protected` and `static`.
+ */
+#[derive(Debug, EnumIter, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum InnerClassAccessFlags {
+    Public = 0x0001,
+    Private = 0x0002,
+    Protected = 0x0004,
+    Static = 0x0008,
+    Final = 0x0010,
+    Interface = 0x0200,
+    Abstract = 0x0400,
+    Synthetic = 0x1000,
+    Annotation = 0x2000,
+    Enum = 0x4000,
+}
+
+#[derive(Debug, EnumIter, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FieldAccessFlags {
+    Public = 0x0001,
+    Private = 0x0002,
+    Protected = 0x0004,
+    Static = 0x0008,
+    Final = 0x0010,
+    Volatile = 0x0040,
+    Transient = 0x0080,
+    Synthetic = 0x1000,
+    Enum = 0x4000,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldInfo {
+    pub access_flags: Vec<FieldAccessFlags>,
+    pub name_index: u16,
+    pub name: String,
+    pub descriptor_index: u16,
+    pub descriptor: String,
+    pub attributes: Vec<AttributeInfo>,
+    /**
+     * The `(start, end)` byte offsets of the field in the class file, end exclusive.
+     * Only recorded when `ParseContext::record_spans` is set.
+     */
+    pub span: Option<(u64, u64)>,
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MethodInfo {
     pub access_flags: Vec<MethodAccessFlags>,
     pub name_index: u16,
@@ -135,16 +611,170 @@ pub struct MethodInfo {
     pub descriptor_index: u16,
     pub descriptor: String,
     pub attributes: Vec<AttributeInfo>,
+    /**
+     * The `(start, end)` byte offsets of the method in the class file, end exclusive.
+     * Only recorded when `ParseContext::record_spans` is set.
+     */
+    pub span: Option<(u64, u64)>,
+}
+
+/**
+ * A reference to a `Class` constant, along with the name it resolves to
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassRef {
+    pub index: u16,
+    pub name: String,
+}
+
+/**
+ * An `ldc`, `ldc_w` or `ldc2_w` whose operand does not point at a constant it can load
+ */
+#[derive(Debug, PartialEq)]
+pub struct ConstantLoadError {
+    pub method_name: String,
+    pub method_descriptor: String,
+    pub pc: usize,
+    pub opcode: u8,
+    pub index: u16,
+}
+
+impl fmt::Display for ConstantLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Instruction 0x{:02x} at {}{}:{} loads constant #{} which is not a loadable constant",
+            self.opcode, self.method_name, self.method_descriptor, self.pc, self.index
+        )
+    }
+}
+
+/**
+ * Why a class can't be run with `Interpreter::run_main`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotRunnable {
+    NoMain,
+    /**
+     * Only instance `main` methods exist, and the class can't be instantiated to call them
+     * (it is abstract or has no non-private no-argument constructor)
+     */
+    MainNotStatic,
+    /** There are `main` methods, but none are non-private with a `([Ljava/lang/String;)V` or `()V` descriptor */
+    MainWrongDescriptor,
+    MainHasNoCode,
+}
+
+impl fmt::Display for NotRunnable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotRunnable::NoMain => write!(f, "No main method found"),
+            NotRunnable::MainNotStatic => write!(
+                f,
+                "The main method is not static and the class has no non-private no-argument constructor"
+            ),
+            NotRunnable::MainWrongDescriptor => write!(
+                f,
+                "No non-private main method with the descriptor ([Ljava/lang/String;)V or ()V"
+            ),
+            NotRunnable::MainHasNoCode => write!(f, "The main method has no Code attribute"),
+        }
+    }
+}
+
+impl std::error::Error for NotRunnable {}
+
+#[derive(Debug, PartialEq)]
+pub enum MethodValidationError {
+    /** A method that is neither `native` nor `abstract` has no `Code` attribute */
+    MissingCode { name: String, descriptor: String },
+    /** A `native` or `abstract` method has a `Code` attribute */
+    UnexpectedCode { name: String, descriptor: String },
+    /** A method has more than one `Code` attribute */
+    DuplicateCode { name: String, descriptor: String },
+}
+
+/**
+ * A structural difference between two versions of a class, as reported by `ClassFile::diff`.
+ * Fields and methods are matched by name and descriptor, so changing a descriptor shows up as
+ * one member being removed and another being added.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassDiff {
+    AccessFlagsChanged {
+        old: Vec<ClassAccessFlags>,
+        new: Vec<ClassAccessFlags>,
+    },
+    SuperClassChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    InterfaceAdded { name: String },
+    InterfaceRemoved { name: String },
+    FieldAdded { name: String, descriptor: String },
+    FieldRemoved { name: String, descriptor: String },
+    FieldAccessFlagsChanged {
+        name: String,
+        descriptor: String,
+        old: Vec<FieldAccessFlags>,
+        new: Vec<FieldAccessFlags>,
+    },
+    MethodAdded { name: String, descriptor: String },
+    MethodRemoved { name: String, descriptor: String },
+    MethodAccessFlagsChanged {
+        name: String,
+        descriptor: String,
+        old: Vec<MethodAccessFlags>,
+        new: Vec<MethodAccessFlags>,
+    },
+}
+
+impl fmt::Display for ClassDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassDiff::AccessFlagsChanged { old, new } => {
+                write!(f, "~ class access flags {old:?} -> {new:?}")
+            }
+            ClassDiff::SuperClassChanged { old, new } => {
+                write!(f, "~ super class {old:?} -> {new:?}")
+            }
+            ClassDiff::InterfaceAdded { name } => write!(f, "+ interface {name}"),
+            ClassDiff::InterfaceRemoved { name } => write!(f, "- interface {name}"),
+            ClassDiff::FieldAdded { name, descriptor } => write!(f, "+ field {name} {descriptor}"),
+            ClassDiff::FieldRemoved { name, descriptor } => write!(f, "- field {name} {descriptor}"),
+            ClassDiff::FieldAccessFlagsChanged {
+                name,
+                descriptor,
+                old,
+                new,
+            } => write!(f, "~ field {name} {descriptor} access flags {old:?} -> {new:?}"),
+            ClassDiff::MethodAdded { name, descriptor } => write!(f, "+ method {name}{descriptor}"),
+            ClassDiff::MethodRemoved { name, descriptor } => write!(f, "- method {name}{descriptor}"),
+            ClassDiff::MethodAccessFlagsChanged {
+                name,
+                descriptor,
+                old,
+                new,
+            } => write!(f, "~ method {name}{descriptor} access flags {old:?} -> {new:?}"),
+        }
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeInfo {
     pub attribute_name_index: u16,
     pub attribute_name: String,
     pub attribute: AttributeKind,
+    /**
+     * The `(start, end)` byte offsets of the attribute in the class file including its 6 byte
+     * header, end exclusive. Only recorded when `ParseContext::record_spans` is set.
+     */
+    pub span: Option<(u64, u64)>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AttributeKind {
     ConstantValue {
         constant_value_index: u16,
@@ -152,59 +782,295 @@ pub enum AttributeKind {
     Code {
         max_stack: u16,
         max_locals: u16,
-        code: Vec<u8>,
+        code: CodeBytes,
         exception_table: Vec<Exception>,
         attributes: Vec<AttributeInfo>,
+        /**
+         * Bytes left over after the nested attributes that are still within `attribute_length`.
+         * Empty for well-formed class files.
+         */
+        trailing: Vec<u8>,
+    },
+    StackMapTable {
+        entries: Vec<StackMapFrame>,
     },
-    StackMapTable,
     Exceptions,
-    InnerClasses,
-    EnclosingMethod,
+    InnerClasses {
+        classes: Vec<InnerClass>,
+    },
+    EnclosingMethod {
+        class_index: u16,
+        /** Index of the enclosing method's NameAndType, or 0 if the class is not enclosed by a method */
+        method_index: u16,
+    },
     Synthetic,
     Signature,
     SourceFile {
         source_file_index: u16,
         source_file_value: String,
     },
-    SourceDebugExtension,
+    SourceDebugExtension {
+        /** Usually an SMAP mapping lines of a JSP or Kotlin file to the generated class */
+        value: String,
+    },
     LineNumberTable {
         line_number_table: Vec<LineNumber>,
     },
     LocalVariableTable,
     LocalVariableTypeTable,
     Deprecated,
-    RuntimeVisibleAnnotations,
-    RuntimeInvisibleAnnotations,
-    RuntimeVisibleParameterAnnotations,
-    RuntimeInvisibleParameterAnnotations,
+    RuntimeVisibleAnnotations {
+        annotations: Vec<Annotation>,
+    },
+    RuntimeInvisibleAnnotations {
+        annotations: Vec<Annotation>,
+    },
+    /**
+     * The annotations of each parameter, in declaration order. `num_parameters` can be lower
+     * than the number of parameters in the descriptor (ex. for the implicit parameters of
+     * inner class constructors).
+     */
+    RuntimeVisibleParameterAnnotations {
+        parameter_annotations: Vec<Vec<Annotation>>,
+    },
+    RuntimeInvisibleParameterAnnotations {
+        parameter_annotations: Vec<Vec<Annotation>>,
+    },
     AnnotationDefault,
-    BootstrapMethods,
+    BootstrapMethods {
+        bootstrap_methods: Vec<BootstrapMethod>,
+    },
     Other {
         bytes: Vec<u8>,
     },
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub struct Exception {
-    start_pc: u16,
-    end_pc: u16,
+/**
+ * The bytecode of a `Code` attribute. When parsing with `ParseContext::lazy_code` the bytes are
+ * not copied out of the class file, only their location in it is kept.
+ */
+#[derive(Debug, Clone)]
+pub enum CodeBytes {
+    Owned(Vec<u8>),
+    Lazy {
+        source: Rc<[u8]>,
+        offset: usize,
+        length: usize,
+    },
+}
+
+impl CodeBytes {
+    pub fn code_bytes(&self) -> &[u8] {
+        match self {
+            CodeBytes::Owned(bytes) => bytes,
+            CodeBytes::Lazy {
+                source,
+                offset,
+                length,
+            } => &source[*offset..*offset + *length],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            CodeBytes::Owned(bytes) => bytes.len(),
+            CodeBytes::Lazy { length, .. } => *length,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/** Serialized as the bytes, the same whether they are owned or lazy */
+#[cfg(feature = "serde")]
+impl serde::Serialize for CodeBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.code_bytes().serialize(serializer)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Exception {
+    start_pc: u16,
+    end_pc: u16,
     handler_pc: u16,
     catch_type: u16,
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LineNumber {
     start_pc: u16,
     line_number: u16,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Annotation {
+    pub type_index: u16,
+    /** Field descriptor of the annotation type (ex. `Ljava/lang/Deprecated;`) */
+    pub type_name: String,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub element_name: String,
+    pub value: ElementValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ElementValue {
+    /** A primitive or `String` constant, `tag` is one of `B C D F I J S Z s` */
+    Const {
+        tag: char,
+        const_value_index: u16,
+        value: ConstValue,
+    },
+    Enum {
+        type_name_index: u16,
+        /** Field descriptor of the enum type (ex. `Ljava/lang/annotation/RetentionPolicy;`) */
+        type_name: String,
+        const_name_index: u16,
+        const_name: String,
+    },
+    Class {
+        class_info_index: u16,
+        /** A return descriptor, so `void.class` is `V` (ex. `Ljava/lang/String;`, `[I`) */
+        return_descriptor: String,
+    },
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+/**
+ * The value of a constant element, typed by its tag
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ConstValue {
+    Byte(i8),
+    /** A UTF-16 code unit, like a Java `char` */
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(String),
+}
+
+/**
+ * Where in a class file an attribute was found
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeLocation {
+    Class,
+    Field { name: String, descriptor: String },
+    Method { name: String, descriptor: String },
+    /** An attribute nested in the `Code` attribute of a method */
+    Code { name: String, descriptor: String },
+}
+
+/**
+ * An entry of the `BootstrapMethods` attribute, which `InvokeDynamic` constants refer to by position
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BootstrapMethod {
+    /** Index of the `MethodHandle` constant of the bootstrap method */
+    pub bootstrap_method_ref: u16,
+    /** Indices of the loadable constants passed as the static arguments */
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+/**
+ * A bootstrap method with its method handle and static arguments resolved through the constant pool
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedBootstrap {
+    pub method_handle: ResolvedMethodHandle,
+    pub arguments: Vec<LoadableConstant>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InnerClass {
+    pub inner_class_info_index: u16,
+    /** 0 for top level, local and anonymous classes */
+    pub outer_class_info_index: u16,
+    /** 0 for anonymous classes */
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: Vec<InnerClassAccessFlags>,
+}
+
+/**
+ * The type of a local variable or operand stack slot in a `StackMapTable` frame
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /** An instance of the `Class` at `cpool_index` */
+    Object { cpool_index: u16 },
+    /** An object created by the `new` instruction at `offset` that is not initialized yet */
+    Uninitialized { offset: u16 },
+}
+
+/**
+ * One entry of a `StackMapTable`. The pc each frame applies to is the previous frame's pc plus
+ * `offset_delta + 1` (or just `offset_delta` for the first frame).
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum StackMapFrame {
+    /** Frame types 0-63, same locals as the previous frame and an empty stack */
+    SameFrame { offset_delta: u16 },
+    /** Frame types 64-127, same locals and a single stack item */
+    SameLocals1StackItemFrame {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    /** Frame type 247 */
+    SameLocals1StackItemFrameExtended {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    /** Frame types 248-250, the last `chopped` locals are gone and the stack is empty */
+    ChopFrame { offset_delta: u16, chopped: u8 },
+    /** Frame type 251 */
+    SameFrameExtended { offset_delta: u16 },
+    /** Frame types 252-254, 1-3 locals were added and the stack is empty */
+    AppendFrame {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+    },
+    /** Frame type 255 */
+    FullFrame {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+
 /**
  * Represents a structure that can be parsed from a file reader
  */
 trait Parsable {
-    fn parse(f: &mut dyn Read) -> io::Result<Self>
+    fn parse(f: &mut dyn Read) -> Result<Self, ClassParseError>
     where
         Self: Sized;
 }
@@ -213,50 +1079,67 @@ trait Parsable {
  * Represents a structure that can be parsed from a file reader and a class constant poll context
  */
 trait ClassParsable {
-    fn parse(constant_pool: &dyn ConstantPool, f: &mut dyn Read) -> io::Result<Self>
+    fn parse(
+        context: &mut ParseContext,
+        constant_pool: &dyn ConstantPool,
+        f: &mut dyn Read,
+    ) -> Result<Self, ClassParseError>
     where
         Self: Sized;
 }
 
-impl Parsable for ClassFile {
-    fn parse(mut f: &mut dyn Read) -> io::Result<ClassFile> {
-        let magic = f.parse_u4_as_bytes()?;
-        let minor_version = f.parse_u2()?;
-        let major_version = f.parse_u2()?;
+impl ClassFile {
+    fn parse(context: &mut ParseContext, f: &mut dyn Read) -> Result<ClassFile, ClassParseError> {
+        context.offset.set(0);
 
-        let constant_pool_count = f.parse_u2()?;
+        let mut reader = CountingReader {
+            inner: f,
+            offset: context.offset.clone(),
+        };
+        let mut f: &mut dyn Read = &mut reader;
 
-        let mut constant_pool: Vec<ConstantPoolInfo> =
-            Vec::with_capacity(constant_pool_count as usize);
+        let magic = parse_magic(f)?;
+        let minor_version = f.parse_u2()?;
+        let major_version = parse_major_version(f)?;
 
-        for _ in 1..constant_pool_count {
-            let entry = ConstantPoolInfo::parse(f)?;
-            constant_pool.push(entry);
-        }
+        let constant_pool = parse_constant_pool(context, f)?;
 
         let access_flags_byte = f.parse_u2()?;
 
         let mut access_flags: Vec<ClassAccessFlags> = Vec::new();
 
         for flag in ClassAccessFlags::iter() {
-            if access_flags_byte & flag.clone() as u16 != 0 {
+            if access_flags_byte & flag as u16 != 0 {
                 access_flags.push(flag)
             }
         }
 
-        let this_class = f.parse_u2()?;
-        let super_class = f.parse_u2()?;
+        // Checked here so the name accessors on `ClassFile` can rely on these indices
+        let class_index = |index: u16| match constant_pool.get_class_name_from_index(index) {
+            Ok(_) => Ok(index),
+            Err(_) => Err(ClassParseError::InvalidClassIndex(index)),
+        };
+
+        let this_class = class_index(f.parse_u2()?)?;
+        let super_class = match f.parse_u2()? {
+            0 => 0,
+            super_class => class_index(super_class)?,
+        };
 
         let interfaces_count = f.parse_u2()?;
 
+        let mut interfaces: Vec<u16> = Vec::with_capacity(interfaces_count as usize);
+
         for _ in 0..interfaces_count {
-            todo!("Implement interfaces parsing");
+            interfaces.push(class_index(f.parse_u2()?)?);
         }
 
         let fields_count = f.parse_u2()?;
 
+        let mut fields: Vec<FieldInfo> = Vec::with_capacity(fields_count as usize);
+
         for _ in 0..fields_count {
-            todo!("Implement fields parsing");
+            fields.push(FieldInfo::parse(context, &constant_pool, f)?);
         }
 
         let methods_count = f.parse_u2()?;
@@ -264,18 +1147,18 @@ impl Parsable for ClassFile {
         let mut methods: Vec<MethodInfo> = Vec::with_capacity(methods_count as usize);
 
         for _ in 0..methods_count {
-            methods.push(MethodInfo::parse(&constant_pool, f)?);
+            methods.push(MethodInfo::parse(context, &constant_pool, f)?);
         }
 
         let attributes_count = f.parse_u2()?;
 
-        let mut attributes: Vec<AttributeInfo> = Vec::with_capacity(methods_count as usize);
+        let mut attributes: Vec<AttributeInfo> = Vec::with_capacity(attributes_count as usize);
 
         for _ in 0..attributes_count {
-            attributes.push(AttributeInfo::parse(&constant_pool, f)?);
+            attributes.push(AttributeInfo::parse(context, &constant_pool, f)?);
         }
 
-        Ok(ClassFile {
+        let class = ClassFile {
             magic,
             minor_version,
             major_version,
@@ -283,17 +1166,41 @@ impl Parsable for ClassFile {
             access_flags,
             this_class,
             super_class,
+            interfaces,
+            fields,
             methods,
             attributes,
-        })
+        };
+
+        // A constant pool that was read with the wrong number of slots shifts every later
+        // index, which shows up here long before it causes confusing behavior at runtime
+        for error in class.validate_constant_loads() {
+            if context.strict {
+                return Err(ClassParseError::Malformed(error.to_string()));
+            }
+
+            context.warnings.push(error.to_string());
+        }
+
+        Ok(class)
     }
 }
 
 impl ClassFile {
-    pub fn get_super_class_name(&self) -> &String {
-        self.constant_pool
+    /**
+     * Returns `None` for `java/lang/Object`, the only class without a super class
+     */
+    pub fn get_super_class_name(&self) -> Option<&String> {
+        if self.super_class == 0 {
+            return None;
+        }
+
+        let name = self
+            .constant_pool
             .get_class_name_from_index(self.super_class)
-            .expect("Could not find name of super class")
+            .expect("Could not find name of super class");
+
+        Some(name)
     }
 
     pub fn get_this_class_name(&self) -> &String {
@@ -302,311 +1209,3361 @@ impl ClassFile {
             .expect("Could not find name of this class")
     }
 
-    pub fn get_main_method(&self) -> Option<&MethodInfo> {
-        for method in &self.methods {
-            if method.name == "main" {
-                return Some(method);
-            }
+    pub fn this_class_ref(&self) -> ClassRef {
+        ClassRef {
+            index: self.this_class,
+            name: self.get_this_class_name().clone(),
         }
-
-        None
     }
-}
-
-impl Parsable for ConstantPoolInfo {
-    fn parse(mut f: &mut dyn Read) -> io::Result<ConstantPoolInfo> {
-        let tag = f.parse_u1()?;
-
-        let info = match tag {
-            ConstantPoolType::Class => ConstantPoolInfo::Class {
-                name_index: f.parse_u2()?,
-            },
-            ConstantPoolType::Fieldref => ConstantPoolInfo::Fieldref {
-                class_index: f.parse_u2()?,
-                name_and_type_index: f.parse_u2()?,
-            },
-            ConstantPoolType::Methodref => ConstantPoolInfo::Methodref {
-                class_index: f.parse_u2()?,
-                name_and_type_index: f.parse_u2()?,
-            },
-            ConstantPoolType::InterfaceMethodref => ConstantPoolInfo::InterfaceMethodref {
-                class_index: f.parse_u2()?,
-                name_and_type_index: f.parse_u2()?,
-            },
-            ConstantPoolType::String => ConstantPoolInfo::String {
-                string_index: f.parse_u2()?,
-            },
-            ConstantPoolType::Integer => ConstantPoolInfo::Integer {
-                value: f.parse_u4_as_i32()?,
-            },
-            ConstantPoolType::Float => ConstantPoolInfo::Float {
-                value: f.parse_u4_as_f32()?,
-            },
-            ConstantPoolType::Long => ConstantPoolInfo::Long {
-                value: f.parse_u8_as_i64()?,
-            },
-            ConstantPoolType::Double => ConstantPoolInfo::Double {
-                value: f.parse_u8_as_f64()?,
-            },
-            ConstantPoolType::NameAndType => ConstantPoolInfo::NameAndType {
-                name_index: f.parse_u2()?,
-                descriptor_index: f.parse_u2()?,
-            },
-            ConstantPoolType::Utf8 => {
-                let length = f.parse_u2()?;
-
-                ConstantPoolInfo::Utf8 {
-                    value: f.parse_utf8(length)?,
-                }
-            }
-            ConstantPoolType::MethodHandle => ConstantPoolInfo::MethodHandle {
-                reference_kind: f.parse_u1()?,
-                reference_index: f.parse_u2()?,
-            },
-            ConstantPoolType::MethodType => ConstantPoolInfo::MethodType {
-                descriptor_index: f.parse_u2()?,
-            },
-            ConstantPoolType::InvokeDynamic => ConstantPoolInfo::InvokeDynamic {
-                bootstrap_method_attr_index: f.parse_u2()?,
-                name_and_type_index: f.parse_u2()?,
-            },
-            _ => {
-                eprintln!("Unexpected constant pool type {tag}!");
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Unexpected constant pool type while parsing class file!",
-                ));
-            }
-        };
 
-        Ok(info)
+    /**
+     * Returns `None` for `java/lang/Object`, the only class without a super class
+     */
+    pub fn super_class_ref(&self) -> Option<ClassRef> {
+        Some(ClassRef {
+            index: self.super_class,
+            name: self.get_super_class_name()?.clone(),
+        })
     }
-}
-
-pub trait ConstantPool {
-    fn get_value(&self, index: u16) -> &ConstantPoolInfo;
-    fn get_class_name_from_index(&self, index: u16) -> Result<&String, ()>;
-    fn get_utf8_from_index(&self, index: u16) -> Result<&String, ()>;
-    fn get_name_and_type(&self, index: u16) -> Result<(&String, &String), ()>;
-}
 
-impl ConstantPool for Vec<ConstantPoolInfo> {
     /**
-     * Allows the constant pool vector to be indexed at 1 instead of 0
+     * Resolves the names of the interfaces the class directly implements (or extends, for an
+     * interface), in declaration order
      */
-    fn get_value(&self, index: u16) -> &ConstantPoolInfo {
-        let val = self.get(index as usize - 1);
-        val.expect(&format!("Illegal index {index} into constant pool!"))
+    pub fn get_interface_names(&self) -> Vec<&String> {
+        self.interfaces
+            .iter()
+            .map(|index| {
+                self.constant_pool
+                    .get_class_name_from_index(*index)
+                    .expect("Expected interface to have a valid class index")
+            })
+            .collect()
     }
 
-    fn get_class_name_from_index(&self, index: u16) -> Result<&String, ()> {
-        let class = self.get_value(index);
-        let ConstantPoolInfo::Class { name_index } = class else { return Err(()); };
+    /**
+     * Finds the class a nested class is declared in. Local and anonymous classes are resolved
+     * through their `EnclosingMethod` attribute and member classes through `InnerClasses`.
+     * Returns `None` for top level classes.
+     */
+    pub fn outer_class_name(&self) -> Option<String> {
+        if let Some(AttributeInfo {
+            attribute: AttributeKind::EnclosingMethod { class_index, .. },
+            ..
+        }) = self.attributes.get_by_name("EnclosingMethod")
+        {
+            return self
+                .constant_pool
+                .get_class_name_from_index(*class_index)
+                .ok()
+                .cloned();
+        }
+
+        let Some(AttributeInfo {
+            attribute: AttributeKind::InnerClasses { classes },
+            ..
+        }) = self.attributes.get_by_name("InnerClasses") else {
+            return None;
+        };
 
-        let name = self.get_value(*name_index);
-        let ConstantPoolInfo::Utf8 { value } = name else { return Err(()); };
+        let this_class_name = self.get_this_class_name();
 
-        Ok(&value)
+        classes
+            .iter()
+            .filter(|inner_class| inner_class.outer_class_info_index != 0)
+            .find(|inner_class| {
+                self.constant_pool
+                    .get_class_name_from_index(inner_class.inner_class_info_index)
+                    .is_ok_and(|name| name == this_class_name)
+            })
+            .and_then(|inner_class| {
+                self.constant_pool
+                    .get_class_name_from_index(inner_class.outer_class_info_index)
+                    .ok()
+                    .cloned()
+            })
     }
 
-    fn get_utf8_from_index(&self, index: u16) -> Result<&String, ()> {
-        let utf8 = self.get_value(index);
-        let ConstantPoolInfo::Utf8 { value } = utf8 else { return Err(()); };
+    pub fn is_interface(&self) -> bool {
+        self.access_flags.contains(&ClassAccessFlags::Interface)
+    }
 
-        Ok(&value)
+    pub fn is_enum(&self) -> bool {
+        self.access_flags.contains(&ClassAccessFlags::Enum)
     }
 
-    fn get_name_and_type(&self, index: u16) -> Result<(&String, &String), ()> {
-        let name_and_type = self.get_value(index);
+    pub fn get_method(&self, name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        self.get_method_index(name, descriptor)
+            .map(|index| &self.methods[index])
+    }
 
-        let ConstantPoolInfo::NameAndType { name_index, descriptor_index } = name_and_type else { return Err(()); };
+    /**
+     * Position of a method in `methods`, for callers that need a handle to it which outlives the borrow
+     */
+    pub fn get_method_index(&self, name: &str, descriptor: &str) -> Option<usize> {
+        self.methods
+            .iter()
+            .position(|method| method.name == name && descriptors_equal(&method.descriptor, descriptor))
+    }
 
-        let name = self.get_utf8_from_index(*name_index)?;
-        let descriptor = self.get_utf8_from_index(*descriptor_index)?;
+    /**
+     * Iterates the methods of the class, skipping compiler generated bridge methods
+     */
+    pub fn non_bridge_methods(&self) -> impl Iterator<Item = &MethodInfo> {
+        self.methods.iter().filter(|method| !method.is_bridge())
+    }
 
-        Ok((name, descriptor))
+    pub fn get_main_method(&self) -> Option<&MethodInfo> {
+        self.entry_point().ok()
     }
-}
 
-impl ClassParsable for MethodInfo {
-    fn parse(constant_pool: &dyn ConstantPool, mut f: &mut dyn Read) -> io::Result<MethodInfo> {
-        let access_flags_byte = f.parse_u2()?;
+    /**
+     * Picks the method to launch the class with, following the JEP 445 order:
+     *
+     * 1. `static void main(String[])`
+     * 2. `static void main()`
+     * 3. `void main(String[])`
+     * 4. `void main()`
+     *
+     * Private methods are never picked, and the instance forms are only picked when the class
+     * has a non-private no-argument constructor to create the receiver with.
+     */
+    pub fn entry_point(&self) -> Result<&MethodInfo, NotRunnable> {
+        if !self.methods.iter().any(|method| method.name == "main") {
+            return Err(NotRunnable::NoMain);
+        }
 
-        let mut access_flags: Vec<MethodAccessFlags> = Vec::new();
+        let candidates = ["([Ljava/lang/String;)V", "()V"]
+            .iter()
+            .filter_map(|descriptor| self.get_method("main", descriptor))
+            .filter(|method| !method.is_private())
+            .collect::<Vec<_>>();
 
-        for flag in MethodAccessFlags::iter() {
-            if access_flags_byte & flag.clone() as u16 != 0 {
-                access_flags.push(flag)
-            }
+        if candidates.is_empty() {
+            return Err(NotRunnable::MainWrongDescriptor);
         }
 
-        let name_index = f.parse_u2()?;
-        let name = constant_pool
-            .get_utf8_from_index(name_index)
-            .expect("Expected value at attribute_name_index to be utf-8")
-            .clone();
-
-        let descriptor_index = f.parse_u2()?;
-        let descriptor = constant_pool
-            .get_utf8_from_index(descriptor_index)
-            .expect("Expected value at descriptor to be utf-8")
-            .clone();
+        let main = match candidates.iter().find(|method| method.is_static()) {
+            Some(main) => main,
+            None => {
+                let has_constructor = self
+                    .get_method("<init>", "()V")
+                    .is_some_and(|init| !init.is_private());
 
-        let attributes_count = f.parse_u2()?;
+                if !has_constructor || self.access_flags.contains(&ClassAccessFlags::Abstract) {
+                    return Err(NotRunnable::MainNotStatic);
+                }
 
-        let mut attributes: Vec<AttributeInfo> = Vec::with_capacity(attributes_count as usize);
+                candidates[0]
+            }
+        };
 
-        for _ in 0..attributes_count {
-            attributes.push(AttributeInfo::parse(constant_pool, f)?);
+        if main.get_code_bytes().is_none() {
+            return Err(NotRunnable::MainHasNoCode);
         }
 
-        Ok(MethodInfo {
-            access_flags,
-            name_index,
-            name,
-            descriptor_index,
-            descriptor,
-            attributes,
-        })
+        Ok(main)
     }
-}
 
-impl MethodInfo {
-    pub fn get_code(&self) -> &AttributeInfo {
-        self.attributes
-            .get_by_name("Code")
-            .expect("Expected MethodInfo to have 'Code' attribute")
+    pub fn is_runnable(&self) -> bool {
+        self.entry_point().is_ok()
     }
-}
 
-impl ClassParsable for AttributeInfo {
-    fn parse(constant_pool: &dyn ConstantPool, mut f: &mut dyn Read) -> io::Result<AttributeInfo> {
-        let attribute_name_index = f.parse_u2()?;
+    /**
+     * Copies the constant pool into a `BoxedConstantPool` for faster repeated lookups
+     */
+    pub fn boxed_constant_pool(&self) -> BoxedConstantPool {
+        BoxedConstantPool::new(&self.constant_pool)
+    }
 
-        let attribute_name = constant_pool
-            .get_utf8_from_index(attribute_name_index)
-            .expect("Expected value at attribute_name_index to be utf-8")
-            .clone();
+    /**
+     * The source file name from the `SourceFile` attribute (ex. `Foo.java`)
+     */
+    pub fn source_file(&self) -> Option<&String> {
+        match &self.attributes.get_by_name("SourceFile")?.attribute {
+            AttributeKind::SourceFile {
+                source_file_value, ..
+            } => Some(source_file_value),
+            _ => None,
+        }
+    }
 
-        let attribute_length = f.parse_u4()?;
+    /**
+     * The debug information from the `SourceDebugExtension` attribute, if there is one
+     */
+    pub fn source_debug_extension(&self) -> Option<&String> {
+        match &self.attributes.get_by_name("SourceDebugExtension")?.attribute {
+            AttributeKind::SourceDebugExtension { value } => Some(value),
+            _ => None,
+        }
+    }
 
-        let bytes = f.parse_n_bytes(attribute_length as usize)?;
-        let mut attribute_bytes = Cursor::new(&bytes);
+    /**
+     * The entries of the class's `BootstrapMethods` attribute (empty if it has none)
+     */
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethod] {
+        match self.attributes.get_by_name("BootstrapMethods").map(|attribute| &attribute.attribute) {
+            Some(AttributeKind::BootstrapMethods { bootstrap_methods }) => bootstrap_methods,
+            _ => &[],
+        }
+    }
 
-        let attribute: AttributeKind = match attribute_name.as_str() {
-            "ConstantValue" => AttributeKind::ConstantValue {
-                constant_value_index: attribute_bytes.parse_u2()?,
-            },
-            "Code" => {
-                let max_stack = attribute_bytes.parse_u2()?;
-                let max_locals = attribute_bytes.parse_u2()?;
+    /**
+     * Resolves the bootstrap method at `bsm_index` in the `BootstrapMethods` attribute (the
+     * `bootstrap_method_attr_index` of an `InvokeDynamic` constant). Returns `None` if there is
+     * no such entry or it refers to something other than a method handle and loadable constants.
+     */
+    pub fn resolved_bootstrap_method(&self, bsm_index: u16) -> Option<ResolvedBootstrap> {
+        let bootstrap_method = self.bootstrap_methods().get(bsm_index as usize)?;
 
-                let code_length = attribute_bytes.parse_u4()?;
+        let method_handle = self
+            .constant_pool
+            .get_method_handle(bootstrap_method.bootstrap_method_ref)
+            .ok()?;
 
-                let code = attribute_bytes.parse_n_bytes(code_length as usize)?;
+        let arguments = bootstrap_method
+            .bootstrap_arguments
+            .iter()
+            .map(|index| self.constant_pool.get_loadable(*index))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
 
-                let exception_table_length = attribute_bytes.parse_u2()?;
+        Some(ResolvedBootstrap {
+            method_handle,
+            arguments,
+        })
+    }
 
-                let mut exception_table = Vec::with_capacity(exception_table_length as usize);
+    /**
+     * Checks that every concrete method has exactly one `Code` attribute and that
+     * `native`/`abstract` methods have none
+     */
+    pub fn validate_methods(&self) -> Vec<MethodValidationError> {
+        let mut errors = Vec::new();
 
-                for _ in 0..exception_table_length {
-                    exception_table.push(Exception::parse(&mut attribute_bytes)?);
+        for method in &self.methods {
+            let code_count = method
+                .attributes
+                .iter()
+                .filter(|attribute| attribute.attribute_name == "Code")
+                .count();
+
+            let is_concrete = !method.access_flags.contains(&MethodAccessFlags::Native)
+                && !method.access_flags.contains(&MethodAccessFlags::Abstract);
+
+            let name = method.name.clone();
+            let descriptor = method.descriptor.clone();
+
+            match (is_concrete, code_count) {
+                (true, 0) => errors.push(MethodValidationError::MissingCode { name, descriptor }),
+                (true, 1) | (false, 0) => {}
+                (true, _) => errors.push(MethodValidationError::DuplicateCode { name, descriptor }),
+                (false, _) => errors.push(MethodValidationError::UnexpectedCode { name, descriptor }),
+            }
+        }
+
+        errors
+    }
+
+    /**
+     * Resolves the text of every `String` constant in the constant pool
+     */
+    pub fn string_literals(&self) -> Vec<&str> {
+        self.constant_pool
+            .iter()
+            .filter_map(|entry| match entry {
+                ConstantPoolInfo::String { string_index } => self
+                    .constant_pool
+                    .get_utf8_from_index(*string_index)
+                    .ok()
+                    .map(|value| value.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+     * Checks that every `ldc`/`ldc_w` operand points at a single slot loadable constant and
+     * every `ldc2_w` operand at a `Long` or `Double`
+     */
+    pub fn validate_constant_loads(&self) -> Vec<ConstantLoadError> {
+        let mut errors = Vec::new();
+
+        for method in &self.methods {
+            let Some(code) = method.get_code_bytes() else {
+                continue;
+            };
+
+            let mut pc = 0;
+
+            while pc < code.len() {
+                let Ok(length) = instruction_length(code, pc) else {
+                    break;
+                };
+
+                // A truncated last instruction has no operands to check
+                if pc + length > code.len() {
+                    break;
                 }
 
-                let attributes_count = attribute_bytes.parse_u2()?;
+                let opcode = code[pc];
 
-                let mut attributes: Vec<AttributeInfo> =
-                    Vec::with_capacity(attributes_count as usize);
+                let index = match opcode {
+                    LDC => code[pc + 1] as u16,
+                    LDC_W | LDC2_W => u16::from_be_bytes([code[pc + 1], code[pc + 2]]),
+                    _ => {
+                        pc += length;
+                        continue;
+                    }
+                };
 
-                for _ in 0..attributes_count {
-                    attributes.push(AttributeInfo::parse(constant_pool, &mut attribute_bytes)?);
+                let entry = match index {
+                    0 => None,
+                    _ => self.constant_pool.get(index as usize - 1),
+                };
+
+                let is_loadable = matches!(
+                    (opcode, entry),
+                    (LDC2_W, Some(ConstantPoolInfo::Long { .. } | ConstantPoolInfo::Double { .. }))
+                        | (
+                            LDC | LDC_W,
+                            Some(
+                                ConstantPoolInfo::Integer { .. }
+                                    | ConstantPoolInfo::Float { .. }
+                                    | ConstantPoolInfo::String { .. }
+                                    | ConstantPoolInfo::Class { .. }
+                                    | ConstantPoolInfo::MethodHandle { .. }
+                                    | ConstantPoolInfo::MethodType { .. }
+                            )
+                        )
+                );
+
+                if !is_loadable {
+                    errors.push(ConstantLoadError {
+                        method_name: method.name.clone(),
+                        method_descriptor: method.descriptor.clone(),
+                        pc,
+                        opcode,
+                        index,
+                    });
                 }
 
-                AttributeKind::Code {
-                    max_stack,
-                    max_locals,
-                    code,
-                    exception_table,
-                    attributes,
+                pc += length;
+            }
+        }
+
+        errors
+    }
+
+    /**
+     * Returns the methods whose code contains an instruction with `opcode` (ex. every method
+     * using `invokedynamic`), in declaration order. Only whole instructions are matched, not
+     * operand bytes, and decoding a method's code stops at the first malformed instruction.
+     */
+    pub fn methods_using_opcode(&self, opcode: u8) -> Vec<&MethodInfo> {
+        self.methods
+            .iter()
+            .filter(|method| {
+                let Some(code) = method.get_code_bytes() else {
+                    return false;
+                };
+
+                let mut pc = 0;
+
+                while pc < code.len() {
+                    if code[pc] == opcode {
+                        return true;
+                    }
+
+                    let Ok(length) = instruction_length(code, pc) else {
+                        break;
+                    };
+
+                    pc += length;
                 }
+
+                false
+            })
+            .collect()
+    }
+
+    /**
+     * Lists every attribute in the class along with where it was found: the class itself,
+     * each method, and the attributes nested in each method's `Code`
+     */
+    pub fn all_attributes(&self) -> Vec<(AttributeLocation, &AttributeInfo)> {
+        let mut attributes: Vec<(AttributeLocation, &AttributeInfo)> = self
+            .attributes
+            .iter()
+            .map(|attribute| (AttributeLocation::Class, attribute))
+            .collect();
+
+        for field in &self.fields {
+            for attribute in &field.attributes {
+                attributes.push((
+                    AttributeLocation::Field {
+                        name: field.name.clone(),
+                        descriptor: field.descriptor.clone(),
+                    },
+                    attribute,
+                ));
             }
-            "SourceFile" => {
-                let source_file_index = attribute_bytes.parse_u2()?;
+        }
 
-                AttributeKind::SourceFile {
-                    source_file_index,
-                    source_file_value: constant_pool
-                        .get_utf8_from_index(source_file_index)
-                        .expect("Expected source file name to be utf-8")
-                        .clone(),
+        for method in &self.methods {
+            for attribute in &method.attributes {
+                attributes.push((
+                    AttributeLocation::Method {
+                        name: method.name.clone(),
+                        descriptor: method.descriptor.clone(),
+                    },
+                    attribute,
+                ));
+
+                if let AttributeKind::Code {
+                    attributes: code_attributes,
+                    ..
+                } = &attribute.attribute
+                {
+                    for code_attribute in code_attributes {
+                        attributes.push((
+                            AttributeLocation::Code {
+                                name: method.name.clone(),
+                                descriptor: method.descriptor.clone(),
+                            },
+                            code_attribute,
+                        ));
+                    }
                 }
             }
-            "LineNumberTable" => {
-                let line_number_table_length = attribute_bytes.parse_u2()?;
+        }
 
-                let mut line_number_table: Vec<LineNumber> =
-                    Vec::with_capacity(line_number_table_length as usize);
+        attributes
+    }
 
-                for _ in 0..line_number_table_length {
-                    line_number_table.push(LineNumber::parse(&mut attribute_bytes)?);
+    /**
+     * Gathers the runtime visible and invisible annotations from every location in the class
+     */
+    pub fn all_annotations(&self) -> Vec<(AttributeLocation, &Annotation)> {
+        self.all_attributes()
+            .into_iter()
+            .flat_map(|(location, attribute)| {
+                let annotations = match &attribute.attribute {
+                    AttributeKind::RuntimeVisibleAnnotations { annotations }
+                    | AttributeKind::RuntimeInvisibleAnnotations { annotations } => {
+                        annotations.as_slice()
+                    }
+                    _ => &[],
+                };
+
+                annotations
+                    .iter()
+                    .map(move |annotation| (location.clone(), annotation))
+            })
+            .collect()
+    }
+
+    pub fn method_count(&self) -> usize {
+        self.methods.len()
+    }
+
+    pub fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /**
+     * Lists the constants of an enum class in declaration order, i.e. its public static final
+     * fields whose type is the enum itself. The fields also need the `ACC_ENUM` flag, which
+     * leaves out ordinary constants like `public static final Color DEFAULT = RED`.
+     * Empty for other classes.
+     */
+    pub fn enum_constants(&self) -> Vec<String> {
+        if !self.is_enum() {
+            return Vec::new();
+        }
+
+        let enum_descriptor = format!("L{};", self.get_this_class_name());
+
+        self.fields
+            .iter()
+            .filter(|field| {
+                field.access_flags.contains(&FieldAccessFlags::Public)
+                    && field.access_flags.contains(&FieldAccessFlags::Enum)
+                    && field.is_static()
+                    && field.access_flags.contains(&FieldAccessFlags::Final)
+                    && field.descriptor == enum_descriptor
+            })
+            .map(|field| field.name.clone())
+            .collect()
+    }
+
+    /**
+     * Sums the length of the bytecode of every method that has a `Code` attribute
+     */
+    pub fn total_code_bytes(&self) -> usize {
+        self.methods
+            .iter()
+            .filter_map(|method| method.get_code_bytes())
+            .map(|code| code.len())
+            .sum()
+    }
+
+    /**
+     * Maps the (name, descriptor) of every method that has a `Code` attribute to its bytecode.
+     * Abstract and native methods are left out.
+     */
+    pub fn all_code(&self) -> HashMap<(String, String), &[u8]> {
+        self.methods
+            .iter()
+            .filter_map(|method| {
+                let code = method.get_code_bytes()?;
+
+                Some(((method.name.clone(), method.descriptor.clone()), code))
+            })
+            .collect()
+    }
+
+    /**
+     * Reports how `other` differs from this class: its access flags, super class and interfaces,
+     * and which fields and methods were added, removed or had their access flags changed.
+     * Bytecode is not compared since constant pool indices differ between builds anyway.
+     */
+    pub fn diff(&self, other: &ClassFile) -> Vec<ClassDiff> {
+        let mut diffs = Vec::new();
+
+        if self.access_flags != other.access_flags {
+            diffs.push(ClassDiff::AccessFlagsChanged {
+                old: self.access_flags.clone(),
+                new: other.access_flags.clone(),
+            });
+        }
+
+        if self.get_super_class_name() != other.get_super_class_name() {
+            diffs.push(ClassDiff::SuperClassChanged {
+                old: self.get_super_class_name().cloned(),
+                new: other.get_super_class_name().cloned(),
+            });
+        }
+
+        let old_interfaces = self.get_interface_names();
+        let new_interfaces = other.get_interface_names();
+
+        for name in old_interfaces.iter().filter(|name| !new_interfaces.contains(name)) {
+            diffs.push(ClassDiff::InterfaceRemoved {
+                name: name.to_string(),
+            });
+        }
+
+        for name in new_interfaces.iter().filter(|name| !old_interfaces.contains(name)) {
+            diffs.push(ClassDiff::InterfaceAdded {
+                name: name.to_string(),
+            });
+        }
+
+        for field in &self.fields {
+            let (name, descriptor) = (field.name.clone(), field.descriptor.clone());
+
+            match other.find_field(&field.name, &field.descriptor) {
+                None => diffs.push(ClassDiff::FieldRemoved { name, descriptor }),
+                Some(new) if new.access_flags != field.access_flags => {
+                    diffs.push(ClassDiff::FieldAccessFlagsChanged {
+                        name,
+                        descriptor,
+                        old: field.access_flags.clone(),
+                        new: new.access_flags.clone(),
+                    })
                 }
+                Some(_) => {}
+            }
+        }
 
-                AttributeKind::LineNumberTable { line_number_table }
+        for field in &other.fields {
+            if self.find_field(&field.name, &field.descriptor).is_none() {
+                diffs.push(ClassDiff::FieldAdded {
+                    name: field.name.clone(),
+                    descriptor: field.descriptor.clone(),
+                });
             }
-            _ => {
-                eprintln!("[WARN] Got unexpected attribute kind with name: {attribute_name}");
-                AttributeKind::Other {
-                    bytes: bytes.to_vec(),
+        }
+
+        for method in &self.methods {
+            let (name, descriptor) = (method.name.clone(), method.descriptor.clone());
+
+            match other.find_method(&method.name, &method.descriptor) {
+                None => diffs.push(ClassDiff::MethodRemoved { name, descriptor }),
+                Some(new) if new.access_flags != method.access_flags => {
+                    diffs.push(ClassDiff::MethodAccessFlagsChanged {
+                        name,
+                        descriptor,
+                        old: method.access_flags.clone(),
+                        new: new.access_flags.clone(),
+                    })
                 }
+                Some(_) => {}
             }
-        };
+        }
 
-        Ok(AttributeInfo {
-            attribute_name_index,
-            attribute_name,
-            attribute,
-        })
+        for method in &other.methods {
+            if self.find_method(&method.name, &method.descriptor).is_none() {
+                diffs.push(ClassDiff::MethodAdded {
+                    name: method.name.clone(),
+                    descriptor: method.descriptor.clone(),
+                });
+            }
+        }
+
+        diffs
     }
-}
 
-pub trait Attributes {
-    fn get_by_name(&self, name: &str) -> Option<&AttributeInfo>;
-}
+    /**
+     * Finds a field by its exact descriptor, for comparing members across classes
+     */
+    fn find_field(&self, name: &str, descriptor: &str) -> Option<&FieldInfo> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name && field.descriptor == descriptor)
+    }
 
-impl Attributes for Vec<AttributeInfo> {
-    fn get_by_name(&self, name: &str) -> Option<&AttributeInfo> {
-        for attribute in self {
-            if attribute.attribute_name.as_str() == name {
-                return Some(attribute);
+    /**
+     * Finds a method by its exact descriptor. Unlike `get_method`, descriptors that only parse
+     * to the same types are not treated as equal.
+     */
+    fn find_method(&self, name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        self.methods
+            .iter()
+            .find(|method| method.name == name && method.descriptor == descriptor)
+    }
+
+    /**
+     * Renames the class `old_name` to `new_name` (both internal names, ex. `com/example/Foo`)
+     * everywhere the constant pool refers to it: `Class` constants (which covers `this_class`,
+     * `super_class`, the interfaces and the owners of member references) as well as field,
+     * method, `NameAndType` and `MethodType` descriptors.
+     *
+     * The affected constants are pointed at new `Utf8` entries instead of being edited in place,
+     * because the same `Utf8` can also be used by something that must not change (ex. a `String`
+     * literal with the class name). Generic signatures and other attributes are left as is.
+     */
+    pub fn rename_class(&mut self, old_name: &str, new_name: &str) {
+        let mut renamed: HashMap<u16, u16> = HashMap::new();
+
+        let mut rename = |constant_pool: &mut Vec<ConstantPoolInfo>, index: u16| -> u16 {
+            if let Some(new_index) = renamed.get(&index) {
+                return *new_index;
+            }
+
+            let Ok(value) = constant_pool.get_utf8_from_index(index) else {
+                return index;
+            };
+
+            let new_value = if value == old_name {
+                new_name.to_string()
+            } else {
+                rename_in_descriptor(value, old_name, new_name)
+            };
+
+            if &new_value == value {
+                return index;
+            }
+
+            let new_index = intern_utf8(constant_pool, new_value);
+            renamed.insert(index, new_index);
+
+            new_index
+        };
+
+        for i in 0..self.constant_pool.len() {
+            let index = match &self.constant_pool[i] {
+                ConstantPoolInfo::Class { name_index } => *name_index,
+                ConstantPoolInfo::NameAndType { descriptor_index, .. } => *descriptor_index,
+                ConstantPoolInfo::MethodType { descriptor_index } => *descriptor_index,
+                _ => continue,
+            };
+
+            let new_index = rename(&mut self.constant_pool, index);
+
+            match &mut self.constant_pool[i] {
+                ConstantPoolInfo::Class { name_index } => *name_index = new_index,
+                ConstantPoolInfo::NameAndType { descriptor_index, .. } => {
+                    *descriptor_index = new_index
+                }
+                ConstantPoolInfo::MethodType { descriptor_index } => *descriptor_index = new_index,
+                _ => unreachable!(),
             }
         }
 
-        None
+        for field in &mut self.fields {
+            field.descriptor_index = rename(&mut self.constant_pool, field.descriptor_index);
+            field.descriptor = rename_in_descriptor(&field.descriptor, old_name, new_name);
+        }
+
+        for method in &mut self.methods {
+            method.descriptor_index = rename(&mut self.constant_pool, method.descriptor_index);
+            method.descriptor = rename_in_descriptor(&method.descriptor, old_name, new_name);
+        }
     }
 }
 
-impl Parsable for Exception {
-    fn parse(mut f: &mut dyn Read) -> io::Result<Self>
-    where
-        Self: Sized,
-    {
-        Ok(Exception {
-            start_pc: f.parse_u2()?,
-            end_pc: f.parse_u2()?,
-            handler_pc: f.parse_u2()?,
-            catch_type: f.parse_u2()?,
-        })
+/**
+ * Replaces every `L<old_name>;` class type in a field or method descriptor
+ */
+fn rename_in_descriptor(descriptor: &str, old_name: &str, new_name: &str) -> String {
+    let mut renamed = String::with_capacity(descriptor.len());
+    let mut rest = descriptor;
+
+    // Class names are skipped as a whole so a name that merely ends with `old_name` (or
+    // contains an `L`) is never matched
+    while let Some(start) = rest.find('L') {
+        let Some(length) = rest[start..].find(';') else {
+            break;
+        };
+
+        let class_name = &rest[start + 1..start + length];
+
+        renamed.push_str(&rest[..start + 1]);
+        renamed.push_str(if class_name == old_name { new_name } else { class_name });
+        renamed.push(';');
+
+        rest = &rest[start + length + 1..];
     }
+
+    renamed.push_str(rest);
+
+    renamed
 }
 
-impl Parsable for LineNumber {
-    fn parse(mut f: &mut dyn Read) -> io::Result<Self>
-    where
-        Self: Sized,
-    {
-        Ok(LineNumber {
-            start_pc: f.parse_u2()?,
-            line_number: f.parse_u2()?,
-        })
+/**
+ * Returns the index of a `Utf8` constant with the given value, adding one if there is none yet
+ */
+fn intern_utf8(constant_pool: &mut Vec<ConstantPoolInfo>, value: String) -> u16 {
+    let existing = constant_pool.iter().position(|info| match info {
+        ConstantPoolInfo::Utf8 { value: existing } => *existing == value,
+        ConstantPoolInfo::LazyUtf8 { value: existing } => existing.get() == Some(&value),
+        _ => false,
+    });
+
+    let position = existing.unwrap_or_else(|| {
+        constant_pool.push(ConstantPoolInfo::Utf8 { value });
+        constant_pool.len() - 1
+    });
+
+    u16::try_from(position + 1).expect("Expected constant pool to have room for another entry")
+}
+
+impl ConstantPoolInfo {
+    /**
+     * The decoded `reference_kind` of a `MethodHandle` constant. Returns `None` for other
+     * constants or for a kind outside 1-9.
+     */
+    pub fn method_handle_kind(&self) -> Option<MethodHandleKind> {
+        match self {
+            ConstantPoolInfo::MethodHandle { reference_kind, .. } => {
+                MethodHandleKind::from_u8(*reference_kind)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(
+        context: &ParseContext,
+        mut f: &mut dyn Read,
+    ) -> Result<ConstantPoolInfo, ClassParseError> {
+        let tag = f.parse_u1()?;
+
+        let info = match tag {
+            ConstantPoolType::Class => ConstantPoolInfo::Class {
+                name_index: f.parse_u2()?,
+            },
+            ConstantPoolType::Fieldref => ConstantPoolInfo::Fieldref {
+                class_index: f.parse_u2()?,
+                name_and_type_index: f.parse_u2()?,
+            },
+            ConstantPoolType::Methodref => ConstantPoolInfo::Methodref {
+                class_index: f.parse_u2()?,
+                name_and_type_index: f.parse_u2()?,
+            },
+            ConstantPoolType::InterfaceMethodref => ConstantPoolInfo::InterfaceMethodref {
+                class_index: f.parse_u2()?,
+                name_and_type_index: f.parse_u2()?,
+            },
+            ConstantPoolType::String => ConstantPoolInfo::String {
+                string_index: f.parse_u2()?,
+            },
+            ConstantPoolType::Integer => ConstantPoolInfo::Integer {
+                value: f.parse_u4_as_i32()?,
+            },
+            ConstantPoolType::Float => ConstantPoolInfo::Float {
+                value: f.parse_u4_as_f32()?,
+            },
+            ConstantPoolType::Long => ConstantPoolInfo::Long {
+                value: f.parse_u8_as_i64()?,
+            },
+            ConstantPoolType::Double => ConstantPoolInfo::Double {
+                value: f.parse_u8_as_f64()?,
+            },
+            ConstantPoolType::NameAndType => ConstantPoolInfo::NameAndType {
+                name_index: f.parse_u2()?,
+                descriptor_index: f.parse_u2()?,
+            },
+            ConstantPoolType::Utf8 => {
+                let length = f.parse_u2()?;
+
+                if let Some(remaining) = context.remaining() {
+                    if length as u64 > remaining {
+                        return Err(ClassParseError::Malformed(format!(
+                            "Utf8 constant declares {length} bytes but only {remaining} are left in the class file"
+                        )));
+                    }
+                }
+
+                match &context.source {
+                    Some(source) if context.lazy_utf8 => {
+                        let offset = context.offset.get() as usize;
+
+                        // Skip over the bytes without copying them anywhere
+                        let skipped = io::copy(&mut f.take(length as u64), &mut io::sink())?;
+
+                        if skipped < length as u64 {
+                            return Err(ClassParseError::TruncatedInput);
+                        }
+
+                        ConstantPoolInfo::LazyUtf8 {
+                            value: LazyString {
+                                source: source.clone(),
+                                offset,
+                                length: length as usize,
+                                decoded: OnceCell::new(),
+                            },
+                        }
+                    }
+                    _ => ConstantPoolInfo::Utf8 {
+                        value: f.parse_utf8(length)?,
+                    },
+                }
+            }
+            ConstantPoolType::MethodHandle => ConstantPoolInfo::MethodHandle {
+                reference_kind: f.parse_u1()?,
+                reference_index: f.parse_u2()?,
+            },
+            ConstantPoolType::MethodType => ConstantPoolInfo::MethodType {
+                descriptor_index: f.parse_u2()?,
+            },
+            ConstantPoolType::InvokeDynamic => ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index: f.parse_u2()?,
+                name_and_type_index: f.parse_u2()?,
+            },
+            _ => return Err(ClassParseError::UnexpectedConstantTag(tag)),
+        };
+
+        Ok(info)
+    }
+}
+
+/**
+ * A constant pool index that is out of range or does not point at the kind of entry it should
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidConstant;
+
+impl fmt::Display for InvalidConstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid constant pool reference")
+    }
+}
+
+impl std::error::Error for InvalidConstant {}
+
+pub trait ConstantPool {
+    fn get_value(&self, index: u16) -> &ConstantPoolInfo;
+    /** Like `get_value`, but `None` instead of a panic for an index that is not a valid entry */
+    fn try_get_value(&self, index: u16) -> Option<&ConstantPoolInfo>;
+    fn get_class_name_from_index(&self, index: u16) -> Result<&String, InvalidConstant>;
+    fn get_utf8_from_index(&self, index: u16) -> Result<&String, InvalidConstant>;
+    fn get_name_and_type(&self, index: u16) -> Result<(&String, &String), InvalidConstant>;
+
+    /**
+     * Resolves a `Fieldref` constant to the `(owner_class, field_name, field_descriptor)` of the
+     * field it refers to
+     */
+    fn get_field_ref(&self, index: u16) -> Result<(String, String, String), InvalidConstant> {
+        let ConstantPoolInfo::Fieldref { class_index, name_and_type_index } = self.get_value(index) else {
+            return Err(InvalidConstant);
+        };
+
+        let class_name = self.get_class_name_from_index(*class_index)?;
+        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
+
+        Ok((class_name.clone(), name.clone(), descriptor.clone()))
+    }
+
+    /**
+     * Resolves a `Methodref` or `InterfaceMethodref` constant to its class name, method name and descriptor
+     */
+    fn get_method_ref(&self, index: u16) -> Result<(String, String, String), InvalidConstant> {
+        let (ConstantPoolInfo::Methodref { class_index, name_and_type_index }
+        | ConstantPoolInfo::InterfaceMethodref { class_index, name_and_type_index }) = self.get_value(index) else {
+            return Err(InvalidConstant);
+        };
+
+        let class_name = self.get_class_name_from_index(*class_index)?;
+        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
+
+        Ok((class_name.clone(), name.clone(), descriptor.clone()))
+    }
+
+    /**
+     * Resolves a constant that `ldc` or a bootstrap method argument can load. Fails for
+     * constants that are not loadable (ex. a `Fieldref`).
+     */
+    fn get_loadable(&self, index: u16) -> Result<LoadableConstant, InvalidConstant> {
+        let constant = match self.get_value(index) {
+            ConstantPoolInfo::Integer { value } => LoadableConstant::Int(*value),
+            ConstantPoolInfo::Float { value } => LoadableConstant::Float(*value),
+            ConstantPoolInfo::Long { value } => LoadableConstant::Long(*value),
+            ConstantPoolInfo::Double { value } => LoadableConstant::Double(*value),
+            ConstantPoolInfo::String { string_index } => {
+                LoadableConstant::String(self.get_utf8_from_index(*string_index)?.clone())
+            }
+            ConstantPoolInfo::Class { .. } => {
+                LoadableConstant::Class(self.get_class_name_from_index(index)?.clone())
+            }
+            ConstantPoolInfo::MethodHandle { .. } => {
+                LoadableConstant::MethodHandle(self.get_method_handle(index)?)
+            }
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                LoadableConstant::MethodType(self.get_utf8_from_index(*descriptor_index)?.clone())
+            }
+            _ => return Err(InvalidConstant),
+        };
+
+        Ok(constant)
+    }
+
+    /**
+     * Resolves a `MethodHandle` constant to its kind and the field or method it refers to
+     */
+    fn get_method_handle(&self, index: u16) -> Result<ResolvedMethodHandle, InvalidConstant> {
+        let ConstantPoolInfo::MethodHandle { reference_kind, reference_index } = self.get_value(index) else {
+            return Err(InvalidConstant);
+        };
+
+        let kind = MethodHandleKind::from_u8(*reference_kind).ok_or(InvalidConstant)?;
+
+        let (ConstantPoolInfo::Fieldref { class_index, name_and_type_index }
+        | ConstantPoolInfo::Methodref { class_index, name_and_type_index }
+        | ConstantPoolInfo::InterfaceMethodref { class_index, name_and_type_index }) = self.try_get_value(*reference_index).ok_or(InvalidConstant)? else {
+            return Err(InvalidConstant);
+        };
+
+        let class_name = self.get_class_name_from_index(*class_index)?;
+        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
+
+        Ok(ResolvedMethodHandle {
+            kind,
+            class_name: class_name.clone(),
+            name: name.clone(),
+            descriptor: descriptor.clone(),
+        })
+    }
+
+    /**
+     * Describes any entry the way the comments of `javap -v` do (ex. `Hello` for a `Utf8`,
+     * `java/io/PrintStream.println:(I)V` for a `Methodref`, `5l` for a `Long`). Entries that
+     * don't resolve are described as `<invalid #index>`, and the slot after a `Long` or
+     * `Double` as `(unusable)`.
+     */
+    fn describe(&self, index: u16) -> String {
+        let invalid = || format!("<invalid #{index}>");
+
+        let Some(entry) = self.try_get_value(index) else {
+            return invalid();
+        };
+
+        let name_and_type = |name_and_type_index: &u16| {
+            self.get_name_and_type(*name_and_type_index)
+                .map(|(name, descriptor)| format!("{name}:{descriptor}"))
+        };
+
+        let description = match entry {
+            ConstantPoolInfo::Utf8 { value } => Ok(value.clone()),
+            ConstantPoolInfo::LazyUtf8 { value } => {
+                Ok(value.get().cloned().unwrap_or_else(|| format!("{value:?}")))
+            }
+            ConstantPoolInfo::Class { .. } => self.get_class_name_from_index(index).map(|name| {
+                // Array classes are quoted, since their descriptors contain `;`
+                if name.starts_with('[') {
+                    format!("\"{name}\"")
+                } else {
+                    name.clone()
+                }
+            }),
+            ConstantPoolInfo::String { string_index } => self.get_utf8_from_index(*string_index).cloned(),
+            ConstantPoolInfo::Integer { value } => Ok(value.to_string()),
+            ConstantPoolInfo::Float { value } => Ok(format!("{value:?}f")),
+            ConstantPoolInfo::Long { value } => Ok(format!("{value}l")),
+            ConstantPoolInfo::Double { value } => Ok(format!("{value:?}d")),
+            ConstantPoolInfo::Fieldref { class_index, name_and_type_index }
+            | ConstantPoolInfo::Methodref { class_index, name_and_type_index }
+            | ConstantPoolInfo::InterfaceMethodref { class_index, name_and_type_index } => self
+                .get_class_name_from_index(*class_index)
+                .and_then(|class_name| Ok(format!("{class_name}.{}", name_and_type(name_and_type_index)?))),
+            ConstantPoolInfo::NameAndType { .. } => name_and_type(&index),
+            ConstantPoolInfo::MethodHandle { .. } => self.get_method_handle(index).map(|handle| {
+                format!("{} {}.{}:{}", handle.kind, handle.class_name, handle.name, handle.descriptor)
+            }),
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                self.get_utf8_from_index(*descriptor_index).cloned()
+            }
+            ConstantPoolInfo::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } => {
+                name_and_type(name_and_type_index)
+                    .map(|name_and_type| format!("#{bootstrap_method_attr_index}:{name_and_type}"))
+            }
+            ConstantPoolInfo::Unusable => Ok(String::from("(unusable)")),
+        };
+
+        description.unwrap_or_else(|_| invalid())
+    }
+}
+
+/**
+ * A constant that can be pushed by `ldc`/`ldc_w`/`ldc2_w` or passed to a bootstrap method,
+ * resolved through the constant pool
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadableConstant {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+    /** Internal name of the class (ex. `java/lang/String`, or `[I` for an array class) */
+    Class(String),
+    MethodHandle(ResolvedMethodHandle),
+    /** A method descriptor (ex. `(Ljava/lang/Object;)Z`) */
+    MethodType(String),
+}
+
+/**
+ * The `reference_kind` of a `MethodHandle` constant
+ */
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter)]
+pub enum MethodHandleKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+
+impl MethodHandleKind {
+    pub fn from_u8(reference_kind: u8) -> Option<MethodHandleKind> {
+        MethodHandleKind::iter().find(|kind| *kind as u8 == reference_kind)
+    }
+}
+
+/**
+ * Formats the kind with the `REF_` name the JVM spec and `javap` use (ex. `REF_invokeStatic`)
+ */
+impl fmt::Display for MethodHandleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MethodHandleKind::GetField => "REF_getField",
+            MethodHandleKind::GetStatic => "REF_getStatic",
+            MethodHandleKind::PutField => "REF_putField",
+            MethodHandleKind::PutStatic => "REF_putStatic",
+            MethodHandleKind::InvokeVirtual => "REF_invokeVirtual",
+            MethodHandleKind::InvokeStatic => "REF_invokeStatic",
+            MethodHandleKind::InvokeSpecial => "REF_invokeSpecial",
+            MethodHandleKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+            MethodHandleKind::InvokeInterface => "REF_invokeInterface",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/**
+ * A `MethodHandle` constant with its target member resolved through the constant pool
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMethodHandle {
+    pub kind: MethodHandleKind,
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/**
+ * Looks up a 1-based constant pool index without panicking on 0 or an index past the end
+ */
+fn constant_pool_entry(constant_pool: &[ConstantPoolInfo], index: u16) -> Option<&ConstantPoolInfo> {
+    (index as usize)
+        .checked_sub(1)
+        .and_then(|index| constant_pool.get(index))
+}
+
+impl ConstantPool for Vec<ConstantPoolInfo> {
+    /**
+     * Allows the constant pool vector to be indexed at 1 instead of 0
+     */
+    fn get_value(&self, index: u16) -> &ConstantPoolInfo {
+        let val = constant_pool_entry(self, index);
+        val.unwrap_or_else(|| panic!("Illegal index {index} into constant pool!"))
+    }
+
+    fn try_get_value(&self, index: u16) -> Option<&ConstantPoolInfo> {
+        constant_pool_entry(self, index)
+    }
+
+    fn get_class_name_from_index(&self, index: u16) -> Result<&String, InvalidConstant> {
+        let class = constant_pool_entry(self, index);
+        let Some(ConstantPoolInfo::Class { name_index }) = class else { return Err(InvalidConstant); };
+
+        self.get_utf8_from_index(*name_index)
+    }
+
+    fn get_utf8_from_index(&self, index: u16) -> Result<&String, InvalidConstant> {
+        match constant_pool_entry(self, index) {
+            Some(ConstantPoolInfo::Utf8 { value }) => Ok(value),
+            Some(ConstantPoolInfo::LazyUtf8 { value }) => value.get().ok_or(InvalidConstant),
+            _ => Err(InvalidConstant),
+        }
+    }
+
+    fn get_name_and_type(&self, index: u16) -> Result<(&String, &String), InvalidConstant> {
+        let Some(name_and_type) = constant_pool_entry(self, index) else { return Err(InvalidConstant); };
+
+        let ConstantPoolInfo::NameAndType { name_index, descriptor_index } = name_and_type else { return Err(InvalidConstant); };
+
+        let name = self.get_utf8_from_index(*name_index)?;
+        let descriptor = self.get_utf8_from_index(*descriptor_index)?;
+
+        Ok((name, descriptor))
+    }
+}
+
+/**
+ * A constant pool laid out for lookup heavy workloads like interpreter resolution. Entries are
+ * stored at their constant pool index (slot 0 is empty), and the names of
+ * `Class` and `NameAndType` entries are resolved up front so they take a single lookup.
+ */
+#[derive(Debug, Clone)]
+pub struct BoxedConstantPool {
+    entries: Box<[Option<ConstantPoolInfo>]>,
+    class_names: Box<[Option<String>]>,
+    names_and_types: Box<[Option<(String, String)>]>,
+}
+
+impl BoxedConstantPool {
+    /**
+     * Copies `constant_pool` and resolves its names. A `LazyUtf8` that isn't valid modified
+     * UTF-8 leaves the `Class` and `NameAndType` entries that use it unresolved, so looking
+     * them up fails with `InvalidConstant` just like the lazy string itself does. (A pool parsed
+     * without `ParseContext::lazy_utf8` can't contain such a string, parsing it fails instead.)
+     */
+    pub fn new(constant_pool: &[ConstantPoolInfo]) -> BoxedConstantPool {
+        let entries = std::iter::once(None)
+            .chain(constant_pool.iter().cloned().map(Some))
+            .collect::<Box<[_]>>();
+
+        let utf8 = |index: &u16| match entries.get(*index as usize) {
+            Some(Some(ConstantPoolInfo::Utf8 { value })) => Some(value.clone()),
+            Some(Some(ConstantPoolInfo::LazyUtf8 { value })) => value.get().cloned(),
+            _ => None,
+        };
+
+        let class_names = entries
+            .iter()
+            .map(|entry| match entry {
+                Some(ConstantPoolInfo::Class { name_index }) => utf8(name_index),
+                _ => None,
+            })
+            .collect();
+
+        let names_and_types = entries
+            .iter()
+            .map(|entry| match entry {
+                Some(ConstantPoolInfo::NameAndType { name_index, descriptor_index }) => {
+                    Some((utf8(name_index)?, utf8(descriptor_index)?))
+                }
+                _ => None,
+            })
+            .collect();
+
+        BoxedConstantPool {
+            entries,
+            class_names,
+            names_and_types,
+        }
+    }
+}
+
+impl From<&[ConstantPoolInfo]> for BoxedConstantPool {
+    fn from(constant_pool: &[ConstantPoolInfo]) -> Self {
+        BoxedConstantPool::new(constant_pool)
+    }
+}
+
+impl ConstantPool for BoxedConstantPool {
+    fn get_value(&self, index: u16) -> &ConstantPoolInfo {
+        match self.entries.get(index as usize) {
+            Some(Some(info)) => info,
+            _ => panic!("Illegal index {index} into constant pool!"),
+        }
+    }
+
+    fn try_get_value(&self, index: u16) -> Option<&ConstantPoolInfo> {
+        match self.entries.get(index as usize) {
+            Some(Some(info)) => Some(info),
+            _ => None,
+        }
+    }
+
+    fn get_class_name_from_index(&self, index: u16) -> Result<&String, InvalidConstant> {
+        match self.class_names.get(index as usize) {
+            Some(Some(name)) => Ok(name),
+            _ => Err(InvalidConstant),
+        }
+    }
+
+    fn get_utf8_from_index(&self, index: u16) -> Result<&String, InvalidConstant> {
+        match self.entries.get(index as usize) {
+            Some(Some(ConstantPoolInfo::Utf8 { value })) => Ok(value),
+            Some(Some(ConstantPoolInfo::LazyUtf8 { value })) => value.get().ok_or(InvalidConstant),
+            _ => Err(InvalidConstant),
+        }
+    }
+
+    fn get_name_and_type(&self, index: u16) -> Result<(&String, &String), InvalidConstant> {
+        match self.names_and_types.get(index as usize) {
+            Some(Some((name, descriptor))) => Ok((name, descriptor)),
+            _ => Err(InvalidConstant),
+        }
+    }
+}
+
+impl ClassParsable for FieldInfo {
+    fn parse(
+        context: &mut ParseContext,
+        constant_pool: &dyn ConstantPool,
+        mut f: &mut dyn Read,
+    ) -> Result<FieldInfo, ClassParseError> {
+        let start = context.offset.get();
+
+        let access_flags_byte = f.parse_u2()?;
+
+        let mut access_flags: Vec<FieldAccessFlags> = Vec::new();
+
+        for flag in FieldAccessFlags::iter() {
+            if access_flags_byte & flag as u16 != 0 {
+                access_flags.push(flag)
+            }
+        }
+
+        let name_index = f.parse_u2()?;
+        let name = constant_pool
+            .get_utf8_from_index(name_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(name_index))?
+            .clone();
+
+        let descriptor_index = f.parse_u2()?;
+        let descriptor = constant_pool
+            .get_utf8_from_index(descriptor_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(descriptor_index))?
+            .clone();
+
+        let attributes_count = f.parse_u2()?;
+
+        let mut attributes: Vec<AttributeInfo> = Vec::with_capacity(attributes_count as usize);
+
+        for _ in 0..attributes_count {
+            attributes.push(AttributeInfo::parse(context, constant_pool, f)?);
+        }
+
+        Ok(FieldInfo {
+            access_flags,
+            name_index,
+            name,
+            descriptor_index,
+            descriptor,
+            attributes,
+            span: context.record_spans.then(|| (start, context.offset.get())),
+        })
+    }
+}
+
+impl FieldInfo {
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(&FieldAccessFlags::Static)
+    }
+
+    /**
+     * The constant pool index of the field's `ConstantValue` attribute, if it has one
+     */
+    pub fn constant_value_index(&self) -> Option<u16> {
+        match &self.attributes.get_by_name("ConstantValue")?.attribute {
+            AttributeKind::ConstantValue {
+                constant_value_index,
+            } => Some(*constant_value_index),
+            _ => None,
+        }
+    }
+}
+
+impl ClassParsable for MethodInfo {
+    fn parse(
+        context: &mut ParseContext,
+        constant_pool: &dyn ConstantPool,
+        mut f: &mut dyn Read,
+    ) -> Result<MethodInfo, ClassParseError> {
+        let start = context.offset.get();
+
+        let access_flags_byte = f.parse_u2()?;
+
+        let mut access_flags: Vec<MethodAccessFlags> = Vec::new();
+
+        for flag in MethodAccessFlags::iter() {
+            if access_flags_byte & flag as u16 != 0 {
+                access_flags.push(flag)
+            }
+        }
+
+        let name_index = f.parse_u2()?;
+        let name = constant_pool
+            .get_utf8_from_index(name_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(name_index))?
+            .clone();
+
+        let descriptor_index = f.parse_u2()?;
+        let descriptor = constant_pool
+            .get_utf8_from_index(descriptor_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(descriptor_index))?
+            .clone();
+
+        let attributes_count = f.parse_u2()?;
+
+        let mut attributes: Vec<AttributeInfo> = Vec::with_capacity(attributes_count as usize);
+
+        for _ in 0..attributes_count {
+            attributes.push(AttributeInfo::parse(context, constant_pool, f)?);
+        }
+
+        Ok(MethodInfo {
+            access_flags,
+            name_index,
+            name,
+            descriptor_index,
+            descriptor,
+            attributes,
+            span: context.record_spans.then(|| (start, context.offset.get())),
+        })
+    }
+}
+
+impl MethodInfo {
+    pub fn get_code(&self) -> &AttributeInfo {
+        self.attributes
+            .get_by_name("Code")
+            .expect("Expected MethodInfo to have 'Code' attribute")
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(&MethodAccessFlags::Static)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags.contains(&MethodAccessFlags::Abstract)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.access_flags.contains(&MethodAccessFlags::Private)
+    }
+
+    /**
+     * Whether this is a bridge method generated by the compiler for generic type erasure
+     */
+    pub fn is_bridge(&self) -> bool {
+        self.access_flags.contains(&MethodAccessFlags::Bridge)
+    }
+
+    /**
+     * Whether this is a Java 8+ `default` method, i.e. a public instance method with a body declared
+     * on an interface (Java 9+ private interface methods also have bodies but are not defaults)
+     */
+    pub fn is_default_interface_method(&self, class: &ClassFile) -> bool {
+        class.is_interface()
+            && !self.is_static()
+            && !self.is_abstract()
+            && !self.is_private()
+    }
+
+    /**
+     * Number of operand stack slots an invocation of this method pops: the slots of its
+     * arguments (2 for each `long` and `double`) plus 1 for the receiver of an instance call
+     */
+    pub fn operand_arity(&self, is_instance: bool) -> usize {
+        let method_descriptor = parse_method_descriptor(&self.descriptor)
+            .expect("Expected method to have a valid descriptor");
+
+        let argument_slots: usize = method_descriptor.params.iter().map(FieldType::slots).sum();
+
+        argument_slots + is_instance as usize
+    }
+
+    /**
+     * Maps a pc to the source line it was compiled from using the `LineNumberTable` attributes
+     * of the method's code. The line is the one of the closest entry starting at or before `pc`.
+     */
+    pub fn line_number_at(&self, pc: u16) -> Option<u16> {
+        let AttributeKind::Code { attributes, .. } = &self.attributes.get_by_name("Code")?.attribute else {
+            return None;
+        };
+
+        attributes
+            .iter()
+            .flat_map(|attribute| match &attribute.attribute {
+                AttributeKind::LineNumberTable { line_number_table } => line_number_table.as_slice(),
+                _ => &[],
+            })
+            .filter(|entry| entry.start_pc <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
+
+    /**
+     * Returns the bytecode of the method, or `None` if it has no `Code` attribute (ex. abstract or native)
+     */
+    pub fn get_code_bytes(&self) -> Option<&[u8]> {
+        let AttributeKind::Code { code, .. } = &self.attributes.get_by_name("Code")?.attribute else {
+            return None;
+        };
+
+        Some(code.code_bytes())
+    }
+}
+
+impl ClassParsable for AttributeInfo {
+    fn parse(
+        context: &mut ParseContext,
+        constant_pool: &dyn ConstantPool,
+        mut f: &mut dyn Read,
+    ) -> Result<AttributeInfo, ClassParseError> {
+        // Nested attributes are read from a copy of their parent's bytes, so the parent
+        // sets the offset to where each of them starts
+        let start = context.offset.get();
+
+        let attribute_name_index = f.parse_u2()?;
+
+        let attribute_name = constant_pool
+            .get_utf8_from_index(attribute_name_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(attribute_name_index))?
+            .clone();
+
+        let attribute_length = f.parse_u4()?;
+
+        let body_offset = start as usize + 6;
+        let end = body_offset as u64 + attribute_length as u64;
+
+        let bytes = f.parse_n_bytes(attribute_length as usize)?;
+        let mut attribute_bytes = Cursor::new(&bytes);
+
+        let attribute: AttributeKind = match attribute_name.as_str() {
+            "ConstantValue" => AttributeKind::ConstantValue {
+                constant_value_index: attribute_bytes.parse_u2()?,
+            },
+            "Code" => {
+                let max_stack = attribute_bytes.parse_u2()?;
+                let max_locals = attribute_bytes.parse_u2()?;
+
+                let code_length = attribute_bytes.parse_u4()?;
+
+                let code = match &context.source {
+                    Some(source) if context.lazy_code && context.attribute_depth == 0 => {
+                        let offset = body_offset + attribute_bytes.position() as usize;
+
+                        attribute_bytes.set_position(attribute_bytes.position() + code_length as u64);
+
+                        if attribute_bytes.position() > attribute_length as u64 {
+                            return Err(ClassParseError::Malformed(
+                                "Code attribute is shorter than its code_length".to_string(),
+                            ));
+                        }
+
+                        CodeBytes::Lazy {
+                            source: source.clone(),
+                            offset,
+                            length: code_length as usize,
+                        }
+                    }
+                    _ => CodeBytes::Owned(attribute_bytes.parse_n_bytes(code_length as usize)?),
+                };
+
+                let exception_table_length = attribute_bytes.parse_u2()?;
+
+                let mut exception_table = Vec::with_capacity(exception_table_length as usize);
+
+                for _ in 0..exception_table_length {
+                    exception_table.push(Exception::parse(&mut attribute_bytes)?);
+                }
+
+                let attributes_count = attribute_bytes.parse_u2()?;
+
+                let mut attributes: Vec<AttributeInfo> =
+                    Vec::with_capacity(attributes_count as usize);
+
+                context.attribute_depth += 1;
+
+                for _ in 0..attributes_count {
+                    context
+                        .offset
+                        .set(body_offset as u64 + attribute_bytes.position());
+
+                    let attribute = AttributeInfo::parse(context, constant_pool, &mut attribute_bytes);
+
+                    if attribute.is_err() {
+                        context.attribute_depth -= 1;
+                    }
+
+                    attributes.push(attribute?);
+                }
+
+                context.attribute_depth -= 1;
+                context.offset.set(end);
+
+                let trailing = bytes[attribute_bytes.position() as usize..].to_vec();
+                attribute_bytes.set_position(attribute_length as u64);
+
+                AttributeKind::Code {
+                    max_stack,
+                    max_locals,
+                    code,
+                    exception_table,
+                    attributes,
+                    trailing,
+                }
+            }
+            "StackMapTable" => {
+                let number_of_entries = attribute_bytes.parse_u2()?;
+
+                let mut entries = Vec::with_capacity(number_of_entries as usize);
+
+                for _ in 0..number_of_entries {
+                    entries.push(StackMapFrame::parse(&mut attribute_bytes)?);
+                }
+
+                AttributeKind::StackMapTable { entries }
+            }
+            "SourceFile" => {
+                let source_file_index = attribute_bytes.parse_u2()?;
+
+                AttributeKind::SourceFile {
+                    source_file_index,
+                    source_file_value: constant_pool
+                        .get_utf8_from_index(source_file_index)
+                        .map_err(|_| ClassParseError::InvalidUtf8Index(source_file_index))?
+                        .clone(),
+                }
+            }
+            // The whole attribute is the string, without the length prefix a Utf8 constant has
+            "SourceDebugExtension" => {
+                attribute_bytes.set_position(attribute_length as u64);
+
+                AttributeKind::SourceDebugExtension {
+                    value: decode_modified_utf8(&bytes)?,
+                }
+            }
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = attribute_bytes.parse_u2()?;
+
+                let mut bootstrap_methods = Vec::with_capacity(num_bootstrap_methods as usize);
+
+                for _ in 0..num_bootstrap_methods {
+                    bootstrap_methods.push(BootstrapMethod::parse(&mut attribute_bytes)?);
+                }
+
+                AttributeKind::BootstrapMethods { bootstrap_methods }
+            }
+            "InnerClasses" => {
+                let number_of_classes = attribute_bytes.parse_u2()?;
+
+                let mut classes: Vec<InnerClass> = Vec::with_capacity(number_of_classes as usize);
+
+                for _ in 0..number_of_classes {
+                    classes.push(InnerClass::parse(&mut attribute_bytes)?);
+                }
+
+                AttributeKind::InnerClasses { classes }
+            }
+            "RuntimeVisibleAnnotations" => AttributeKind::RuntimeVisibleAnnotations {
+                annotations: parse_annotations(constant_pool, &mut attribute_bytes)?,
+            },
+            "RuntimeInvisibleAnnotations" => AttributeKind::RuntimeInvisibleAnnotations {
+                annotations: parse_annotations(constant_pool, &mut attribute_bytes)?,
+            },
+            "RuntimeVisibleParameterAnnotations" => {
+                AttributeKind::RuntimeVisibleParameterAnnotations {
+                    parameter_annotations: parse_parameter_annotations(constant_pool, &mut attribute_bytes)?,
+                }
+            }
+            "RuntimeInvisibleParameterAnnotations" => {
+                AttributeKind::RuntimeInvisibleParameterAnnotations {
+                    parameter_annotations: parse_parameter_annotations(constant_pool, &mut attribute_bytes)?,
+                }
+            }
+            "EnclosingMethod" => AttributeKind::EnclosingMethod {
+                class_index: attribute_bytes.parse_u2()?,
+                method_index: attribute_bytes.parse_u2()?,
+            },
+            "LineNumberTable" => {
+                let line_number_table_length = attribute_bytes.parse_u2()?;
+
+                let mut line_number_table: Vec<LineNumber> =
+                    Vec::with_capacity(line_number_table_length as usize);
+
+                for _ in 0..line_number_table_length {
+                    line_number_table.push(LineNumber::parse(&mut attribute_bytes)?);
+                }
+
+                AttributeKind::LineNumberTable { line_number_table }
+            }
+            _ => {
+                if !context.unknown_attributes.contains(&attribute_name) {
+                    context.unknown_attributes.push(attribute_name.clone());
+                }
+
+                AttributeKind::Other {
+                    bytes: bytes.to_vec(),
+                }
+            }
+        };
+
+        // Unknown attributes keep all of their bytes, so only the known parsers can come up short.
+        // Code keeps its leftovers too, but they are still reported.
+        let consumed = match &attribute {
+            AttributeKind::Code { trailing, .. } => attribute_bytes.position() - trailing.len() as u64,
+            _ => attribute_bytes.position(),
+        };
+
+        if !matches!(attribute, AttributeKind::Other { .. }) && consumed < attribute_length as u64 {
+            let message = format!(
+                "Attribute {attribute_name} declares a length of {attribute_length} bytes but only {consumed} were used"
+            );
+
+            if context.strict {
+                return Err(ClassParseError::Malformed(message));
+            }
+
+            context.warnings.push(message);
+        }
+
+        Ok(AttributeInfo {
+            attribute_name_index,
+            attribute_name,
+            attribute,
+            span: context.record_spans.then_some((start, end)),
+        })
+    }
+}
+
+pub trait Attributes {
+    fn get_by_name(&self, name: &str) -> Option<&AttributeInfo>;
+}
+
+impl Attributes for Vec<AttributeInfo> {
+    fn get_by_name(&self, name: &str) -> Option<&AttributeInfo> {
+        self.iter()
+            .find(|attribute| attribute.attribute_name.as_str() == name)
+    }
+}
+
+impl Exception {
+    pub fn start_pc(&self) -> u16 {
+        self.start_pc
+    }
+
+    pub fn end_pc(&self) -> u16 {
+        self.end_pc
+    }
+
+    pub fn handler_pc(&self) -> u16 {
+        self.handler_pc
+    }
+
+    /**
+     * Constant pool index of the caught class, or 0 for a handler that catches everything (ex. `finally`)
+     */
+    pub fn catch_type(&self) -> u16 {
+        self.catch_type
+    }
+
+    /**
+     * Resolves the internal name of the caught class (ex. `java/io/IOException`), or `None` for
+     * a catch-all handler
+     */
+    pub fn catch_class_name<'a>(&self, constant_pool: &'a dyn ConstantPool) -> Option<&'a String> {
+        if self.catch_type == 0 {
+            return None;
+        }
+
+        constant_pool.get_class_name_from_index(self.catch_type).ok()
+    }
+
+    /**
+     * Whether `pc` falls in the `[start_pc, end_pc)` range protected by this handler
+     */
+    pub fn covers(&self, pc: u16) -> bool {
+        self.start_pc <= pc && pc < self.end_pc
+    }
+}
+
+impl AttributeKind {
+    /**
+     * Returns the exception handlers of a `Code` attribute that cover `pc`, in exception table
+     * order (the order the JVM searches them in). Other attributes have no handlers.
+     */
+    pub fn handlers_covering(&self, pc: u16) -> impl Iterator<Item = &Exception> {
+        let exception_table = match self {
+            AttributeKind::Code { exception_table, .. } => exception_table.as_slice(),
+            _ => &[],
+        };
+
+        exception_table
+            .iter()
+            .filter(move |exception| exception.covers(pc))
+    }
+}
+
+impl Parsable for Exception {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        Ok(Exception {
+            start_pc: f.parse_u2()?,
+            end_pc: f.parse_u2()?,
+            handler_pc: f.parse_u2()?,
+            catch_type: f.parse_u2()?,
+        })
+    }
+}
+
+impl Parsable for StackMapFrame {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        let frame_type = f.parse_u1()?;
+
+        let frame = match frame_type {
+            0..=63 => StackMapFrame::SameFrame {
+                offset_delta: frame_type as u16,
+            },
+            64..=127 => StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta: frame_type as u16 - 64,
+                stack: VerificationTypeInfo::parse(f)?,
+            },
+            247 => StackMapFrame::SameLocals1StackItemFrameExtended {
+                offset_delta: f.parse_u2()?,
+                stack: VerificationTypeInfo::parse(f)?,
+            },
+            248..=250 => StackMapFrame::ChopFrame {
+                offset_delta: f.parse_u2()?,
+                chopped: 251 - frame_type,
+            },
+            251 => StackMapFrame::SameFrameExtended {
+                offset_delta: f.parse_u2()?,
+            },
+            252..=254 => {
+                let offset_delta = f.parse_u2()?;
+
+                let mut locals = Vec::with_capacity(frame_type as usize - 251);
+
+                for _ in 0..frame_type - 251 {
+                    locals.push(VerificationTypeInfo::parse(f)?);
+                }
+
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals,
+                }
+            }
+            255 => {
+                let offset_delta = f.parse_u2()?;
+
+                let number_of_locals = f.parse_u2()?;
+                let mut locals = Vec::with_capacity(number_of_locals as usize);
+
+                for _ in 0..number_of_locals {
+                    locals.push(VerificationTypeInfo::parse(f)?);
+                }
+
+                let number_of_stack_items = f.parse_u2()?;
+                let mut stack = Vec::with_capacity(number_of_stack_items as usize);
+
+                for _ in 0..number_of_stack_items {
+                    stack.push(VerificationTypeInfo::parse(f)?);
+                }
+
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals,
+                    stack,
+                }
+            }
+            _ => {
+                return Err(ClassParseError::Malformed(format!("Reserved stack map frame type {frame_type}")))
+            }
+        };
+
+        Ok(frame)
+    }
+}
+
+impl Parsable for VerificationTypeInfo {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        let tag = f.parse_u1()?;
+
+        let info = match tag {
+            0 => VerificationTypeInfo::Top,
+            1 => VerificationTypeInfo::Integer,
+            2 => VerificationTypeInfo::Float,
+            3 => VerificationTypeInfo::Double,
+            4 => VerificationTypeInfo::Long,
+            5 => VerificationTypeInfo::Null,
+            6 => VerificationTypeInfo::UninitializedThis,
+            7 => VerificationTypeInfo::Object {
+                cpool_index: f.parse_u2()?,
+            },
+            8 => VerificationTypeInfo::Uninitialized {
+                offset: f.parse_u2()?,
+            },
+            _ => {
+                return Err(ClassParseError::Malformed(format!("Unexpected verification type tag {tag}")))
+            }
+        };
+
+        Ok(info)
+    }
+}
+
+impl Parsable for LineNumber {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        Ok(LineNumber {
+            start_pc: f.parse_u2()?,
+            line_number: f.parse_u2()?,
+        })
+    }
+}
+
+/**
+ * Converts a field descriptor to a Java type name, leaving it as is if it is malformed
+ */
+fn descriptor_to_java_name(descriptor: &str) -> String {
+    parse_field_descriptor(descriptor).map_or_else(|_| descriptor.to_string(), |t| t.to_string())
+}
+
+fn parse_annotations(
+    constant_pool: &dyn ConstantPool,
+    mut f: &mut dyn Read,
+) -> Result<Vec<Annotation>, ClassParseError> {
+    let num_annotations = f.parse_u2()?;
+
+    let mut annotations = Vec::with_capacity(num_annotations as usize);
+
+    for _ in 0..num_annotations {
+        annotations.push(Annotation::parse(constant_pool, f)?);
+    }
+
+    Ok(annotations)
+}
+
+fn parse_parameter_annotations(
+    constant_pool: &dyn ConstantPool,
+    mut f: &mut dyn Read,
+) -> Result<Vec<Vec<Annotation>>, ClassParseError> {
+    let num_parameters = f.parse_u1()?;
+
+    let mut parameter_annotations = Vec::with_capacity(num_parameters as usize);
+
+    for _ in 0..num_parameters {
+        parameter_annotations.push(parse_annotations(constant_pool, f)?);
+    }
+
+    Ok(parameter_annotations)
+}
+
+impl Annotation {
+    /**
+     * Reconstructs the annotation as it would be written in Java source
+     * (ex. `@com.example.Foo(value = 3, name = "x")`)
+     */
+    pub fn to_source_string(&self) -> String {
+        let type_name = descriptor_to_java_name(&self.type_name);
+
+        if self.element_value_pairs.is_empty() {
+            return format!("@{type_name}");
+        }
+
+        let elements = self
+            .element_value_pairs
+            .iter()
+            .map(|pair| format!("{} = {}", pair.element_name, pair.value.to_source_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("@{type_name}({elements})")
+    }
+
+    fn parse(
+        constant_pool: &dyn ConstantPool,
+        mut f: &mut dyn Read,
+    ) -> Result<Annotation, ClassParseError> {
+        let type_index = f.parse_u2()?;
+        let type_name = constant_pool
+            .get_utf8_from_index(type_index)
+            .map_err(|_| ClassParseError::InvalidUtf8Index(type_index))?
+            .clone();
+
+        let num_element_value_pairs = f.parse_u2()?;
+
+        let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+
+        for _ in 0..num_element_value_pairs {
+            let element_name_index = f.parse_u2()?;
+            let element_name = constant_pool
+                .get_utf8_from_index(element_name_index)
+                .map_err(|_| ClassParseError::InvalidUtf8Index(element_name_index))?
+                .clone();
+
+            element_value_pairs.push(ElementValuePair {
+                element_name_index,
+                element_name,
+                value: ElementValue::parse(constant_pool, f)?,
+            });
+        }
+
+        Ok(Annotation {
+            type_index,
+            type_name,
+            element_value_pairs,
+        })
+    }
+}
+
+impl ElementValue {
+    /**
+     * Formats the value as a Java source literal (ex. `3L`, `"x"`, `Color.RED`, `String.class`, `{1, 2}`)
+     */
+    pub fn to_source_string(&self) -> String {
+        match self {
+            ElementValue::Const { value, .. } => match value {
+                ConstValue::Char(value) => {
+                    let c = char::from_u32(*value as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+
+                    format!("'{}'", c.escape_default())
+                }
+                ConstValue::Byte(value) => value.to_string(),
+                ConstValue::Short(value) => value.to_string(),
+                ConstValue::Int(value) => value.to_string(),
+                ConstValue::Boolean(value) => value.to_string(),
+                ConstValue::Long(value) => format!("{value}L"),
+                ConstValue::Float(value) => format!("{value:?}f"),
+                ConstValue::Double(value) => format!("{value:?}"),
+                ConstValue::String(value) => format!("{value:?}"),
+            },
+            ElementValue::Enum {
+                type_name,
+                const_name,
+                ..
+            } => format!("{}.{const_name}", descriptor_to_java_name(type_name)),
+            ElementValue::Class {
+                return_descriptor, ..
+            } => {
+                let class_name = match return_descriptor.as_str() {
+                    "V" => String::from("void"),
+                    descriptor => descriptor_to_java_name(descriptor),
+                };
+
+                format!("{class_name}.class")
+            }
+            ElementValue::Annotation(annotation) => annotation.to_source_string(),
+            ElementValue::Array(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| value.to_source_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{{{values}}}")
+            }
+        }
+    }
+
+    fn parse(
+        constant_pool: &dyn ConstantPool,
+        mut f: &mut dyn Read,
+    ) -> Result<ElementValue, ClassParseError> {
+        let tag = f.parse_u1()? as char;
+
+        let utf8 = |index: u16| {
+            constant_pool
+                .get_utf8_from_index(index)
+                .cloned()
+                .map_err(|_| ClassParseError::InvalidUtf8Index(index))
+        };
+
+        let value = match tag {
+            'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' | 's' => {
+                let const_value_index = f.parse_u2()?;
+
+                ElementValue::Const {
+                    tag,
+                    const_value_index,
+                    value: ConstValue::resolve(constant_pool, tag, const_value_index)?,
+                }
+            }
+            'e' => {
+                let type_name_index = f.parse_u2()?;
+                let const_name_index = f.parse_u2()?;
+
+                ElementValue::Enum {
+                    type_name_index,
+                    type_name: utf8(type_name_index)?,
+                    const_name_index,
+                    const_name: utf8(const_name_index)?,
+                }
+            }
+            'c' => {
+                let class_info_index = f.parse_u2()?;
+
+                ElementValue::Class {
+                    class_info_index,
+                    return_descriptor: utf8(class_info_index)?,
+                }
+            }
+            '@' => ElementValue::Annotation(Annotation::parse(constant_pool, f)?),
+            '[' => {
+                let num_values = f.parse_u2()?;
+
+                let mut values = Vec::with_capacity(num_values as usize);
+
+                for _ in 0..num_values {
+                    values.push(ElementValue::parse(constant_pool, f)?);
+                }
+
+                ElementValue::Array(values)
+            }
+            _ => {
+                return Err(ClassParseError::Malformed(format!("Unexpected annotation element value tag '{tag}'")))
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+impl ConstValue {
+    /**
+     * Reads the constant a `const_value_index` points at, which has to be of the kind its tag
+     * calls for (`Integer` for `B C I S Z`, `Long` for `J`, `Float` for `F`, `Double` for `D`
+     * and `Utf8` for `s`). Narrow types are truncated the same way Java would cast them.
+     */
+    fn resolve(
+        constant_pool: &dyn ConstantPool,
+        tag: char,
+        const_value_index: u16,
+    ) -> Result<ConstValue, ClassParseError> {
+        if tag == 's' {
+            return constant_pool
+                .get_utf8_from_index(const_value_index)
+                .map(|value| ConstValue::String(value.clone()))
+                .map_err(|_| ClassParseError::InvalidUtf8Index(const_value_index));
+        }
+
+        let Some(info) = constant_pool.try_get_value(const_value_index) else {
+            return Err(ClassParseError::Malformed(format!(
+                "Annotation element with tag '{tag}' points at missing constant #{const_value_index}"
+            )));
+        };
+
+        let value = match (tag, info) {
+            ('B', ConstantPoolInfo::Integer { value }) => ConstValue::Byte(*value as i8),
+            ('C', ConstantPoolInfo::Integer { value }) => ConstValue::Char(*value as u16),
+            ('I', ConstantPoolInfo::Integer { value }) => ConstValue::Int(*value),
+            ('S', ConstantPoolInfo::Integer { value }) => ConstValue::Short(*value as i16),
+            ('Z', ConstantPoolInfo::Integer { value }) => ConstValue::Boolean(*value != 0),
+            ('J', ConstantPoolInfo::Long { value }) => ConstValue::Long(*value),
+            ('F', ConstantPoolInfo::Float { value }) => ConstValue::Float(*value),
+            ('D', ConstantPoolInfo::Double { value }) => ConstValue::Double(*value),
+            (_, info) => {
+                return Err(ClassParseError::Malformed(format!(
+                    "Annotation element with tag '{tag}' points at constant #{const_value_index} ({info:?})"
+                )))
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+impl Parsable for BootstrapMethod {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        let bootstrap_method_ref = f.parse_u2()?;
+        let num_bootstrap_arguments = f.parse_u2()?;
+
+        let mut bootstrap_arguments = Vec::with_capacity(num_bootstrap_arguments as usize);
+
+        for _ in 0..num_bootstrap_arguments {
+            bootstrap_arguments.push(f.parse_u2()?);
+        }
+
+        Ok(BootstrapMethod {
+            bootstrap_method_ref,
+            bootstrap_arguments,
+        })
+    }
+}
+
+impl Parsable for InnerClass {
+    fn parse(mut f: &mut dyn Read) -> Result<Self, ClassParseError>
+    where
+        Self: Sized,
+    {
+        let inner_class_info_index = f.parse_u2()?;
+        let outer_class_info_index = f.parse_u2()?;
+        let inner_name_index = f.parse_u2()?;
+
+        let access_flags_byte = f.parse_u2()?;
+
+        let mut inner_class_access_flags: Vec<InnerClassAccessFlags> = Vec::new();
+
+        for flag in InnerClassAccessFlags::iter() {
+            if access_flags_byte & flag as u16 != 0 {
+                inner_class_access_flags.push(flag)
+            }
+        }
+
+        Ok(InnerClass {
+            inner_class_info_index,
+            outer_class_info_index,
+            inner_name_index,
+            inner_class_access_flags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture_logs, fixtures_dir, ClassBuilder, ACC_NATIVE, ACC_PUBLIC, ACC_STATIC};
+
+    fn parse_fixture(class_name: &str) -> ClassFile {
+        parse_class_file(&fixtures_dir().join(format!("{class_name}.class"))).unwrap()
+    }
+
+    #[test]
+    fn total_code_bytes_sums_every_method() {
+        let class = parse_fixture("SumLoop");
+
+        // <init> is 5 bytes, sum() 21 bytes and main 10 bytes (see `javap -c`)
+        assert_eq!(class.method_count(), 3);
+        assert_eq!(class.total_code_bytes(), 5 + 21 + 10);
+
+        // The native method has no code to count
+        let class = parse_fixture("Natives");
+
+        assert_eq!(class.method_count(), 3);
+        assert_eq!(class.total_code_bytes(), 5 + 12);
+    }
+
+    #[test]
+    fn validate_methods_checks_code_against_flags() {
+        let mut builder = ClassBuilder::new("Validated");
+        builder.method_with_attributes(ACC_PUBLIC | ACC_STATIC, "noCode", "()V", &[]);
+        builder.method(ACC_PUBLIC | ACC_NATIVE, "nativeWithCode", "()V", 0, 1, &[0xb1]);
+        builder.static_method("fine", "()V", 0, 0, &[0xb1]);
+
+        assert_eq!(
+            builder.parse().validate_methods(),
+            [
+                MethodValidationError::MissingCode {
+                    name: String::from("noCode"),
+                    descriptor: String::from("()V"),
+                },
+                MethodValidationError::UnexpectedCode {
+                    name: String::from("nativeWithCode"),
+                    descriptor: String::from("()V"),
+                },
+            ]
+        );
+
+        assert_eq!(parse_fixture("Natives").validate_methods(), []);
+    }
+
+    /**
+     * Parses a class with `context`, keeping whatever it collected
+     */
+    fn parse_with(builder: &ClassBuilder, context: &mut ParseContext) -> Result<ClassFile, ClassParseError> {
+        parse_class_source(Rc::from(builder.build()), context)
+    }
+
+    #[test]
+    fn over_long_attributes_warn_or_fail_in_strict_mode() {
+        let mut builder = ClassBuilder::new("LongSourceFile");
+        let source_file = builder.utf8("LongSourceFile.java");
+        // Two bytes more than the `sourcefile_index` a SourceFile attribute holds
+        let attribute = builder.attribute("SourceFile", &[&source_file.to_be_bytes()[..], &[0, 0]].concat());
+        builder.class_attribute(attribute);
+
+        let mut context = ParseContext::default();
+        let class = parse_with(&builder, &mut context).unwrap();
+
+        assert_eq!(class.source_file().map(String::as_str), Some("LongSourceFile.java"));
+        assert_eq!(
+            context.warnings,
+            ["Attribute SourceFile declares a length of 4 bytes but only 2 were used"]
+        );
+
+        let mut context = ParseContext {
+            strict: true,
+            ..ParseContext::default()
+        };
+
+        assert!(matches!(parse_with(&builder, &mut context), Err(ClassParseError::Malformed(_))));
+    }
+
+    #[test]
+    fn flags_interface_default_and_static_methods() {
+        let class = parse_fixture("Greeter");
+        assert!(class.is_interface());
+
+        let name = class.get_method("name", "()Ljava/lang/String;").unwrap();
+        assert!(name.is_abstract());
+        assert!(!name.is_default_interface_method(&class));
+
+        let greeting = class.get_method("greeting", "()Ljava/lang/String;").unwrap();
+        assert!(!greeting.is_abstract());
+        assert!(greeting.is_default_interface_method(&class));
+
+        let anonymous = class.get_method("anonymous", "()LGreeter;").unwrap();
+        assert!(anonymous.is_static());
+        assert!(!anonymous.is_default_interface_method(&class));
+
+        assert!(!parse_fixture("SumLoop").is_interface());
+    }
+
+    #[test]
+    fn lists_string_literals() {
+        let class = parse_fixture("PrintStreams");
+
+        assert_eq!(class.string_literals(), ["to out", "to err"]);
+    }
+
+    #[test]
+    fn get_method_ignores_descriptor_formatting() {
+        let class = parse_fixture("IntMath");
+
+        assert!(class.get_method("subtract", "(I I)I").is_some());
+        assert!(class.get_method("subtract", "(IJ)I").is_none());
+    }
+
+    #[test]
+    fn class_refs_pair_indices_with_names() {
+        let mut builder = ClassBuilder::new("Refs");
+        let class = builder.parse();
+
+        // ClassBuilder adds the Utf8 name right before each Class constant
+        assert_eq!(
+            class.this_class_ref(),
+            ClassRef {
+                index: 2,
+                name: String::from("Refs"),
+            }
+        );
+        assert_eq!(
+            class.super_class_ref(),
+            Some(ClassRef {
+                index: 4,
+                name: String::from("java/lang/Object"),
+            })
+        );
+
+        builder.super_class_index(0);
+        assert_eq!(builder.parse().super_class_ref(), None);
+    }
+
+    #[test]
+    fn resolves_the_outer_class_of_nested_and_anonymous_classes() {
+        assert_eq!(parse_fixture("Outer$Nested").outer_class_name().as_deref(), Some("Outer"));
+        assert_eq!(parse_fixture("Outer$1").outer_class_name().as_deref(), Some("Outer"));
+        assert_eq!(parse_fixture("Outer").outer_class_name(), None);
+
+        // The anonymous class has no name of its own in its InnerClasses entry
+        let anonymous = parse_fixture("Outer$1");
+        let Some(AttributeInfo {
+            attribute: AttributeKind::InnerClasses { classes },
+            ..
+        }) = anonymous.attributes.get_by_name("InnerClasses")
+        else {
+            panic!("Expected Outer$1 to have an InnerClasses attribute")
+        };
+
+        assert!(classes.iter().any(|inner_class| inner_class.inner_name_index == 0));
+    }
+
+    #[test]
+    fn detects_constant_loads_of_the_wrong_kind() {
+        let mut builder = ClassBuilder::new("ShiftedPool");
+        let int_index = builder.integer(5);
+        let long_index = builder.long(5);
+        builder.static_method(
+            "load",
+            "()V",
+            2,
+            0,
+            &[
+                0x12, int_index as u8, // ldc of an Integer
+                0x14, (long_index >> 8) as u8, long_index as u8, // ldc2_w of a Long
+                0x14, (int_index >> 8) as u8, int_index as u8, // ldc2_w of an Integer
+                0x12, long_index as u8, // ldc of a Long
+                0xb1, // return
+            ],
+        );
+
+        let mut context = ParseContext::default();
+        let class = parse_with(&builder, &mut context).unwrap();
+
+        assert_eq!(
+            class.validate_constant_loads(),
+            [
+                ConstantLoadError {
+                    method_name: String::from("load"),
+                    method_descriptor: String::from("()V"),
+                    pc: 5,
+                    opcode: 0x14,
+                    index: int_index,
+                },
+                ConstantLoadError {
+                    method_name: String::from("load"),
+                    method_descriptor: String::from("()V"),
+                    pc: 8,
+                    opcode: 0x12,
+                    index: long_index,
+                },
+            ]
+        );
+        assert_eq!(context.warnings.len(), 2);
+
+        // Checked in release builds too, and fatal in strict mode
+        let mut context = ParseContext {
+            strict: true,
+            ..ParseContext::default()
+        };
+
+        assert!(matches!(parse_with(&builder, &mut context), Err(ClassParseError::Malformed(_))));
+
+        assert_eq!(parse_fixture("TwoSlotMath").validate_constant_loads(), []);
+    }
+
+    #[test]
+    fn lists_handlers_covering_a_pc() {
+        let class = parse_fixture("NestedTry");
+        let method = class.get_method("divide", "(I)I").unwrap();
+        let code = &method.attributes.get_by_name("Code").unwrap().attribute;
+
+        let handlers = |pc: u16| {
+            code.handlers_covering(pc)
+                .map(|handler| {
+                    let catch_class = handler.catch_class_name(&class.constant_pool).cloned();
+
+                    (handler.handler_pc(), catch_class)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Both the inner and the outer try cover the idiv, innermost first
+        assert_eq!(
+            handlers(3),
+            [
+                (5, Some(String::from("java/lang/ArithmeticException"))),
+                (8, Some(String::from("java/lang/RuntimeException"))),
+            ]
+        );
+        // The inner catch block is only covered by the outer try
+        assert_eq!(handlers(6), [(8, Some(String::from("java/lang/RuntimeException")))]);
+        // `end_pc` is exclusive
+        assert_eq!(handlers(4), []);
+    }
+
+    #[test]
+    fn parses_classes_without_methods() {
+        let mut builder = ClassBuilder::new("NoMethods");
+        let source_file = builder.utf8("NoMethods.java");
+        let attributes = [
+            builder.attribute("SourceFile", &source_file.to_be_bytes()),
+            builder.attribute("SourceDebugExtension", b"SMAP"),
+            builder.attribute("Deprecated", &[]),
+        ];
+
+        for attribute in attributes {
+            builder.class_attribute(attribute);
+        }
+
+        let class = builder.parse();
+
+        assert!(class.methods.is_empty());
+        assert_eq!(
+            class
+                .attributes
+                .iter()
+                .map(|attribute| attribute.attribute_name.as_str())
+                .collect::<Vec<_>>(),
+            ["SourceFile", "SourceDebugExtension", "Deprecated"]
+        );
+        assert_eq!(class.source_file().map(String::as_str), Some("NoMethods.java"));
+    }
+
+    #[test]
+    fn resolves_a_lambdas_method_handles() {
+        let class = parse_fixture("Lambdas");
+        let bootstrap = class.resolved_bootstrap_method(0).unwrap();
+
+        assert_eq!(bootstrap.method_handle.kind, MethodHandleKind::InvokeStatic);
+        assert_eq!(bootstrap.method_handle.class_name, "java/lang/invoke/LambdaMetafactory");
+        assert_eq!(bootstrap.method_handle.name, "metafactory");
+
+        // The second static argument is the handle of the method the lambda's body compiled to
+        assert_eq!(
+            bootstrap.arguments,
+            [
+                LoadableConstant::MethodType(String::from("()V")),
+                LoadableConstant::MethodHandle(ResolvedMethodHandle {
+                    kind: MethodHandleKind::InvokeStatic,
+                    class_name: String::from("Lambdas"),
+                    name: String::from("lambda$greeter$0"),
+                    descriptor: String::from("()V"),
+                }),
+                LoadableConstant::MethodType(String::from("()V")),
+            ]
+        );
+        assert_eq!(bootstrap.method_handle.kind.to_string(), "REF_invokeStatic");
+    }
+
+    #[test]
+    fn resolves_string_bootstrap_arguments() {
+        let class = parse_fixture("Concat");
+        let bootstrap = class.resolved_bootstrap_method(0).unwrap();
+
+        assert_eq!(bootstrap.method_handle.class_name, "java/lang/invoke/StringConcatFactory");
+        assert_eq!(bootstrap.method_handle.name, "makeConcatWithConstants");
+
+        // The recipe marks where each argument goes with \u0001
+        assert_eq!(
+            bootstrap.arguments,
+            [LoadableConstant::String(String::from("Hi \u{1} x\u{1}"))]
+        );
+
+        assert!(class.resolved_bootstrap_method(1).is_none());
+    }
+
+    #[test]
+    fn lazy_code_matches_the_eager_copy() {
+        let bytes: Rc<[u8]> = Rc::from(fs::read(fixtures_dir().join("NestedTry.class")).unwrap());
+
+        let eager = parse_class_source(bytes.clone(), &mut ParseContext::default()).unwrap();
+        let lazy = parse_class_source(
+            bytes,
+            &mut ParseContext {
+                lazy_code: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(lazy.methods.len(), eager.methods.len());
+
+        for (lazy_method, eager_method) in lazy.methods.iter().zip(&eager.methods) {
+            let AttributeKind::Code { code, .. } = &lazy_method.attributes.get_by_name("Code").unwrap().attribute else {
+                panic!("Expected a Code attribute");
+            };
+
+            assert!(matches!(code, CodeBytes::Lazy { .. }));
+            assert_eq!(lazy_method.get_code_bytes(), eager_method.get_code_bytes());
+        }
+    }
+
+    #[test]
+    fn lists_enum_constants_in_declaration_order() {
+        let class = parse_fixture("Color");
+
+        assert!(class.is_enum());
+        // Leaves out `DEFAULT`, which is not an enum constant, and the synthetic `$VALUES`
+        assert_eq!(class.enum_constants(), ["RED", "GREEN", "BLUE"]);
+
+        assert_eq!(parse_fixture("SumLoop").enum_constants(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn non_bridge_methods_hide_the_erased_override() {
+        let class = parse_fixture("Bridges");
+
+        // `compare(Object, Object)` is the bridge `javac` generates for `Comparator<String>`
+        let bridges = class
+            .methods
+            .iter()
+            .filter(|method| method.is_bridge())
+            .map(|method| method.descriptor.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bridges, ["(Ljava/lang/Object;Ljava/lang/Object;)I"]);
+
+        assert_eq!(
+            class
+                .non_bridge_methods()
+                .map(|method| (method.name.as_str(), method.descriptor.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("<init>", "()V"),
+                ("compare", "(Ljava/lang/String;Ljava/lang/String;)I"),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_annotations_gathers_every_level() {
+        let class = parse_fixture("Annotated");
+
+        let annotations = class
+            .all_annotations()
+            .into_iter()
+            .map(|(location, annotation)| {
+                let [ElementValuePair {
+                    value: ElementValue::Const {
+                        value: ConstValue::String(value),
+                        ..
+                    },
+                    ..
+                }] = annotation.element_value_pairs.as_slice()
+                else {
+                    panic!("Expected a single string element");
+                };
+
+                assert_eq!(annotation.type_name, "LMarker;");
+
+                (location, value.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            annotations,
+            [
+                (AttributeLocation::Class, "class"),
+                (
+                    AttributeLocation::Field {
+                        name: String::from("count"),
+                        descriptor: String::from("I"),
+                    },
+                    "field"
+                ),
+                (
+                    AttributeLocation::Method {
+                        name: String::from("run"),
+                        descriptor: String::from("()V"),
+                    },
+                    "method"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8_longer_than_the_file_is_rejected() {
+        let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+        // One constant, a Utf8 declaring 0xFFFF bytes followed by only three
+        bytes.extend_from_slice(&[0, 2, 1, 0xFF, 0xFF]);
+        bytes.extend_from_slice(b"abc");
+
+        let Err(ClassParseError::Malformed(message)) = parse_class_bytes(&bytes) else {
+            panic!("Expected the Utf8 length to be rejected");
+        };
+
+        assert_eq!(
+            message,
+            "Utf8 constant declares 65535 bytes but only 3 are left in the class file"
+        );
+    }
+
+    #[test]
+    fn entry_point_explains_why_a_class_cant_run() {
+        let entry_point = |add_methods: &dyn Fn(&mut ClassBuilder)| {
+            let mut builder = ClassBuilder::new("Launched");
+            add_methods(&mut builder);
+
+            builder.parse().entry_point().map(|main| main.descriptor.clone())
+        };
+
+        assert_eq!(
+            entry_point(&|builder| {
+                builder.static_method("run", "([Ljava/lang/String;)V", 0, 1, &[0xb1]);
+            }),
+            Err(NotRunnable::NoMain)
+        );
+        // Without a constructor there is nothing to call an instance `main` on
+        assert_eq!(
+            entry_point(&|builder| {
+                builder.method(ACC_PUBLIC, "main", "([Ljava/lang/String;)V", 0, 2, &[0xb1]);
+            }),
+            Err(NotRunnable::MainNotStatic)
+        );
+        assert_eq!(
+            entry_point(&|builder| {
+                builder.static_method("main", "(I)V", 0, 1, &[0xb1]);
+            }),
+            Err(NotRunnable::MainWrongDescriptor)
+        );
+        assert_eq!(
+            entry_point(&|builder| {
+                builder.method_with_attributes(ACC_PUBLIC | ACC_STATIC | ACC_NATIVE, "main", "([Ljava/lang/String;)V", &[]);
+            }),
+            Err(NotRunnable::MainHasNoCode)
+        );
+
+        assert!(parse_fixture("SumLoop").is_runnable());
+        assert!(!parse_fixture("Greeter").is_runnable());
+    }
+
+    #[test]
+    fn maps_every_method_handle_kind() {
+        let kinds = (0..=10).map(MethodHandleKind::from_u8).collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                None,
+                Some(MethodHandleKind::GetField),
+                Some(MethodHandleKind::GetStatic),
+                Some(MethodHandleKind::PutField),
+                Some(MethodHandleKind::PutStatic),
+                Some(MethodHandleKind::InvokeVirtual),
+                Some(MethodHandleKind::InvokeStatic),
+                Some(MethodHandleKind::InvokeSpecial),
+                Some(MethodHandleKind::NewInvokeSpecial),
+                Some(MethodHandleKind::InvokeInterface),
+                None,
+            ]
+        );
+        assert_eq!(
+            kinds.iter().flatten().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "REF_getField",
+                "REF_getStatic",
+                "REF_putField",
+                "REF_putStatic",
+                "REF_invokeVirtual",
+                "REF_invokeStatic",
+                "REF_invokeSpecial",
+                "REF_newInvokeSpecial",
+                "REF_invokeInterface",
+            ]
+        );
+
+        // The accessor on the raw constant, for the lambda's bootstrap method
+        let class = parse_fixture("Lambdas");
+        let bootstrap_method_ref = class.bootstrap_methods()[0].bootstrap_method_ref;
+        assert_eq!(
+            class.constant_pool.get_value(bootstrap_method_ref).method_handle_kind(),
+            Some(MethodHandleKind::InvokeStatic)
+        );
+    }
+
+    #[test]
+    fn renders_annotations_as_java_source() {
+        let class = parse_fixture("Documented");
+        let [(AttributeLocation::Class, annotation)] = class.all_annotations()[..] else {
+            panic!("Expected a single class annotation");
+        };
+
+        assert_eq!(
+            annotation.to_source_string(),
+            "@Info(version = 3, name = \"x\", id = 7L, grade = 'A', \
+             policy = java.lang.annotation.RetentionPolicy.CLASS, type = java.lang.String.class, \
+             sizes = {1, 2}, author = @Author(value = \"me\"))"
+        );
+    }
+
+    #[test]
+    fn resolves_enum_and_class_annotation_elements() {
+        let class = parse_fixture("Documented");
+        let [(AttributeLocation::Class, annotation)] = class.all_annotations()[..] else {
+            panic!("Expected a single class annotation");
+        };
+
+        let element = |name: &str| {
+            &annotation
+                .element_value_pairs
+                .iter()
+                .find(|pair| pair.element_name == name)
+                .unwrap()
+                .value
+        };
+
+        assert!(matches!(
+            element("policy"),
+            ElementValue::Enum { type_name, const_name, .. }
+                if type_name == "Ljava/lang/annotation/RetentionPolicy;" && const_name == "CLASS"
+        ));
+        assert!(matches!(
+            element("type"),
+            ElementValue::Class { return_descriptor, .. } if return_descriptor == "Ljava/lang/String;"
+        ));
+    }
+
+    #[test]
+    fn annotation_constants_out_of_the_pool_are_errors() {
+        let mut builder = ClassBuilder::new("BadAnnotation");
+        let type_index = builder.utf8("LMarker;");
+        let name_index = builder.utf8("value");
+        let body = [
+            &1u16.to_be_bytes()[..], // num_annotations
+            &type_index.to_be_bytes(),
+            &1u16.to_be_bytes(), // num_element_value_pairs
+            &name_index.to_be_bytes(),
+            b"I",
+            &0xFFu16.to_be_bytes(), // const_value_index past the end of the pool
+        ]
+        .concat();
+        let annotations = builder.attribute("RuntimeVisibleAnnotations", &body);
+        builder.class_attribute(annotations);
+
+        let result = parse_with(&builder, &mut ParseContext::default());
+        assert!(matches!(
+            result,
+            Err(ClassParseError::Malformed(message)) if message.contains("missing constant #255")
+        ));
+    }
+
+    #[test]
+    fn code_keeps_its_trailing_bytes() {
+        let mut builder = ClassBuilder::new("Trailing");
+        let body = [
+            &[0, 0, 0, 0][..], // max_stack, max_locals
+            &1u32.to_be_bytes(),
+            &[0xb1], // return
+            &[0, 0, 0, 0], // no exception table or attributes
+            &[0xAB, 0xCD],
+        ]
+        .concat();
+        let code = builder.attribute("Code", &body);
+        builder.method_with_attributes(ACC_PUBLIC | ACC_STATIC, "run", "()V", &[code]);
+
+        let mut context = ParseContext::default();
+        let class = parse_with(&builder, &mut context).unwrap();
+
+        let AttributeKind::Code { code, trailing, .. } = &class.methods[0].attributes[0].attribute else {
+            panic!("Expected a Code attribute");
+        };
+        assert_eq!(code.code_bytes(), [0xb1]);
+        assert_eq!(trailing, &[0xAB, 0xCD]);
+        assert_eq!(
+            context.warnings,
+            ["Attribute Code declares a length of 15 bytes but only 13 were used"]
+        );
+
+        // Without leftovers there is nothing to keep
+        let AttributeKind::Code { trailing, .. } = &parse_fixture("SumLoop").methods[0].attributes[0].attribute else {
+            panic!("Expected a Code attribute");
+        };
+        assert!(trailing.is_empty());
+    }
+
+    #[test]
+    fn all_code_maps_methods_to_their_bytecode() {
+        let mut builder = ClassBuilder::new("TwoMethods");
+        builder.static_method("one", "()I", 1, 0, &[0x04, 0xac]);
+        builder.static_method("run", "(I)V", 0, 1, &[0xb1]);
+        builder.method_with_attributes(ACC_PUBLIC | ACC_NATIVE, "external", "()V", &[]);
+
+        let class = builder.parse();
+
+        assert_eq!(
+            class.all_code(),
+            HashMap::from([
+                ((String::from("one"), String::from("()I")), &[0x04, 0xac][..]),
+                ((String::from("run"), String::from("(I)V")), &[0xb1][..]),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_annotations_per_parameter() {
+        let class = parse_fixture("Parameters");
+        let greet = class.get_method("greet", "(ILjava/lang/String;)V").unwrap();
+
+        let AttributeKind::RuntimeVisibleParameterAnnotations { parameter_annotations } = &greet
+            .attributes
+            .get_by_name("RuntimeVisibleParameterAnnotations")
+            .unwrap()
+            .attribute
+        else {
+            panic!("Expected parameter annotations");
+        };
+
+        let type_names = parameter_annotations
+            .iter()
+            .map(|annotations| annotations.iter().map(|annotation| annotation.type_name.as_str()).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        assert_eq!(type_names, [vec![], vec!["LNotNull;"]]);
+    }
+
+    #[test]
+    fn records_the_span_of_a_method() {
+        let mut builder = ClassBuilder::new("A");
+        builder.static_method("run", "()V", 0, 0, &[0xb1]);
+
+        // The constants are `A` (4 bytes), its Class (3), `java/lang/Object` (19), its Class (3),
+        // then `run` (6), `()V` (6) and `Code` (7). 10 bytes of magic, versions and constant
+        // count come before them, then 12 bytes of flags, class names, empty interface and field
+        // tables and the method count.
+        let method_start = 10 + 48 + 12;
+        // Access flags, name, descriptor and attribute count, then the Code attribute's 6 byte
+        // header with stack and locals (4), the code length (4) and code (1), and two empty tables
+        let code_start = method_start + 8;
+        let method_end = code_start + 6 + 4 + 4 + 1 + 4;
+
+        let mut context = ParseContext {
+            record_spans: true,
+            ..Default::default()
+        };
+        let class = parse_with(&builder, &mut context).unwrap();
+        let method = &class.methods[0];
+
+        assert_eq!(method.span, Some((method_start, method_end)));
+        assert_eq!(method.attributes[0].span, Some((code_start, method_end)));
+        // Only the class attribute count is left after the method
+        assert_eq!(builder.build().len() as u64, method_end + 2);
+
+        assert_eq!(builder.parse().methods[0].span, None);
+    }
+
+    #[test]
+    fn class_identity_reads_only_the_names() {
+        let bytes = fs::read(fixtures_dir().join("Square.class")).unwrap();
+        let mut reader = &bytes[..];
+
+        let identity = parse_class_identity(&mut reader).unwrap();
+
+        assert_eq!(
+            identity,
+            ClassIdentity {
+                this_name: String::from("Square"),
+                super_name: Some(String::from("AbstractPolygon")),
+                interface_names: vec![String::from("java/io/Serializable")],
+            }
+        );
+        // The fields, methods and attributes are left unread
+        assert!(!reader.is_empty());
+    }
+
+    #[test]
+    fn resolves_the_names_of_implemented_interfaces() {
+        let class = parse_fixture("Bridges");
+
+        assert_eq!(class.interfaces.len(), 1);
+        assert_eq!(class.get_interface_names(), [&String::from("java/util/Comparator")]);
+
+        assert!(parse_fixture("SumLoop").get_interface_names().is_empty());
+    }
+
+    #[test]
+    fn long_constants_take_two_slots() {
+        let class = parse_fixture("LongThenString");
+
+        // #7 is the long, #8 the unusable slot after it and #9 the string (see `javap -v`)
+        assert!(matches!(
+            class.constant_pool.get_value(7),
+            ConstantPoolInfo::Long { value: 123456789012 }
+        ));
+        assert!(matches!(class.constant_pool.get_value(8), ConstantPoolInfo::Unusable));
+        assert_eq!(
+            class.constant_pool.get_loadable(9),
+            Ok(LoadableConstant::String(String::from("after")))
+        );
+    }
+
+    #[test]
+    fn decodes_the_flags_of_a_private_static_nested_class() {
+        let outer = parse_fixture("Outer");
+        let Some(AttributeInfo {
+            attribute: AttributeKind::InnerClasses { classes },
+            ..
+        }) = outer.attributes.get_by_name("InnerClasses")
+        else {
+            panic!("Expected Outer to have an InnerClasses attribute")
+        };
+
+        let nested = classes
+            .iter()
+            .find(|inner_class| {
+                outer
+                    .constant_pool
+                    .get_class_name_from_index(inner_class.inner_class_info_index)
+                    .is_ok_and(|name| name == "Outer$Nested")
+            })
+            .unwrap();
+
+        // Flags a top level class can't have
+        assert_eq!(
+            nested.inner_class_access_flags,
+            [InnerClassAccessFlags::Private, InnerClassAccessFlags::Static]
+        );
+    }
+
+    #[test]
+    fn operand_arity_counts_long_slots_and_the_receiver() {
+        let mut builder = ClassBuilder::new("Arity");
+        builder.static_method("scale", "(JI)V", 0, 3, &[0xb1]);
+        builder.method(ACC_PUBLIC, "offset", "(J)V", 0, 3, &[0xb1]);
+
+        let class = builder.parse();
+
+        assert_eq!(class.get_method("scale", "(JI)V").unwrap().operand_arity(false), 3);
+        assert_eq!(class.get_method("offset", "(J)V").unwrap().operand_arity(true), 3);
+    }
+
+    /**
+     * The `StackMapTable` frames of a method's code
+     */
+    fn stack_map_frames<'a>(class: &'a ClassFile, name: &str, descriptor: &str) -> &'a [StackMapFrame] {
+        let method = class.get_method(name, descriptor).unwrap();
+        let AttributeKind::Code { attributes, .. } = &method.attributes.get_by_name("Code").unwrap().attribute else {
+            panic!("Expected {name}{descriptor} to have code");
+        };
+        let AttributeKind::StackMapTable { entries } = &attributes.get_by_name("StackMapTable").unwrap().attribute else {
+            panic!("Expected {name}{descriptor} to have a StackMapTable");
+        };
+
+        entries
+    }
+
+    #[test]
+    fn parses_the_stack_map_frames_javac_emits() {
+        // The loop of `sum` adds `i` and `total`, then drops `i` after it (see `javap -v`)
+        assert_eq!(
+            stack_map_frames(&parse_fixture("SumLoop"), "sum", "()I"),
+            [
+                StackMapFrame::AppendFrame {
+                    offset_delta: 4,
+                    locals: vec![VerificationTypeInfo::Integer, VerificationTypeInfo::Integer],
+                },
+                StackMapFrame::ChopFrame {
+                    offset_delta: 14,
+                    chopped: 1,
+                },
+            ]
+        );
+
+        // Each catch block starts with the caught exception on the stack
+        let class = parse_fixture("NestedTry");
+        let caught = stack_map_frames(&class, "divide", "(I)I")
+            .iter()
+            .map(|frame| {
+                let StackMapFrame::SameLocals1StackItemFrame {
+                    stack: VerificationTypeInfo::Object { cpool_index },
+                    ..
+                } = frame
+                else {
+                    panic!("Expected a frame with a single object on the stack, got {frame:?}");
+                };
+
+                class.constant_pool.get_class_name_from_index(*cpool_index).unwrap().as_str()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(caught, ["java/lang/ArithmeticException", "java/lang/RuntimeException"]);
+    }
+
+    #[test]
+    fn a_class_without_a_superclass_has_no_super_name() {
+        // Only `java/lang/Object` has a `super_class` of 0
+        let mut builder = ClassBuilder::new("java/lang/Object");
+        builder.super_class_index(0);
+
+        let class = builder.parse();
+
+        assert_eq!(class.get_super_class_name(), None);
+        assert_eq!(parse_fixture("SumLoop").get_super_class_name().map(String::as_str), Some("java/lang/Object"));
+    }
+
+    #[test]
+    fn random_bytes_are_bad_magic() {
+        let bytes = [0x3F, 0x91, 0x0C, 0xE2, 0x57, 0x88, 0x1D, 0xA0, 0x6B, 0x04];
+
+        assert!(matches!(
+            parse_class_bytes(&bytes),
+            Err(ClassParseError::BadMagic([0x3F, 0x91, 0x0C, 0xE2]))
+        ));
+    }
+
+    /**
+     * The exception table of a method's code
+     */
+    fn exception_table<'a>(class: &'a ClassFile, name: &str, descriptor: &str) -> &'a [Exception] {
+        let method = class.get_method(name, descriptor).unwrap();
+        let AttributeKind::Code { exception_table, .. } = &method.attributes.get_by_name("Code").unwrap().attribute else {
+            panic!("Expected {name}{descriptor} to have code");
+        };
+
+        exception_table
+    }
+
+    #[test]
+    fn reads_the_details_of_exception_handlers() {
+        let class = parse_fixture("NestedTry");
+
+        let handlers = exception_table(&class, "divide", "(I)I")
+            .iter()
+            .map(|handler| {
+                (
+                    handler.start_pc(),
+                    handler.end_pc(),
+                    handler.handler_pc(),
+                    handler.catch_class_name(&class.constant_pool).map(String::as_str),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            handlers,
+            [
+                (0, 4, 5, Some("java/lang/ArithmeticException")),
+                (0, 4, 8, Some("java/lang/RuntimeException")),
+                (5, 7, 8, Some("java/lang/RuntimeException")),
+            ]
+        );
+
+        // `finally` catches everything
+        let class = parse_fixture("Finally");
+        let [handler] = exception_table(&class, "run", "()V") else {
+            panic!("Expected a single handler");
+        };
+
+        assert_eq!(handler.catch_type(), 0);
+        assert_eq!(handler.catch_class_name(&class.constant_pool), None);
+    }
+
+    #[test]
+    fn bytes_and_files_parse_the_same() {
+        let path = fixtures_dir().join("SumLoop.class");
+
+        let from_file = parse_class_file(&path).unwrap();
+        let from_bytes = parse_class_bytes(&fs::read(&path).unwrap()).unwrap();
+
+        assert_eq!(from_bytes.this_class, from_file.this_class);
+        assert_eq!(from_bytes.get_this_class_name(), "SumLoop");
+        assert_eq!(from_bytes.methods.len(), from_file.methods.len());
+    }
+
+    #[test]
+    fn resolves_a_fieldref_into_owner_name_and_type() {
+        let utf8 = |value: &str| ConstantPoolInfo::Utf8 {
+            value: String::from(value),
+        };
+        let constant_pool: Vec<ConstantPoolInfo> = vec![
+            ConstantPoolInfo::Fieldref {
+                class_index: 2,
+                name_and_type_index: 4,
+            },
+            ConstantPoolInfo::Class { name_index: 3 },
+            utf8("com/example/Counter"),
+            ConstantPoolInfo::NameAndType {
+                name_index: 5,
+                descriptor_index: 6,
+            },
+            utf8("count"),
+            utf8("I"),
+        ];
+
+        assert_eq!(
+            constant_pool.get_field_ref(1),
+            Ok((
+                String::from("com/example/Counter"),
+                String::from("count"),
+                String::from("I"),
+            ))
+        );
+        // A Class constant is not a Fieldref
+        assert_eq!(constant_pool.get_field_ref(2), Err(InvalidConstant));
+    }
+
+    #[test]
+    fn renaming_a_class_updates_every_reference() {
+        let mut class = parse_fixture("Inherited");
+
+        class.rename_class("Inherited", "com/example/Inherited");
+        class.rename_class("Base", "com/example/Base");
+
+        assert_eq!(class.get_this_class_name(), "com/example/Inherited");
+        assert_eq!(class.get_super_class_name().map(String::as_str), Some("com/example/Base"));
+        // The descriptor of `read(Inherited)` and the owners of member references
+        assert!(class.get_method("read", "(Lcom/example/Inherited;)I").is_some());
+        assert_eq!(
+            class.constant_pool.get_field_ref(7),
+            Ok((
+                String::from("com/example/Inherited"),
+                String::from("value"),
+                String::from("I"),
+            ))
+        );
+        assert_eq!(
+            class.constant_pool.get_method_ref(1),
+            Ok((String::from("com/example/Base"), String::from("<init>"), String::from("()V")))
+        );
+        // Other classes are left alone
+        assert_eq!(
+            class.constant_pool.get_field_ref(13),
+            Ok((
+                String::from("java/lang/System"),
+                String::from("out"),
+                String::from("Ljava/io/PrintStream;"),
+            ))
+        );
+    }
+
+    #[test]
+    fn keeps_the_whole_smap_of_a_source_debug_extension() {
+        // What a JSP compiler emits: the attribute length is the length of the text, which has no
+        // length prefix of its own
+        let smap = "SMAP\nindex_jsp.java\nJSP\n*S JSP\n*F\n+ 0 index.jsp\nindex.jsp\n*L\n1,5:62\n6:67,2\n*E\n";
+
+        let mut builder = ClassBuilder::new("index_jsp");
+        let attribute = builder.attribute("SourceDebugExtension", smap.as_bytes());
+        builder.class_attribute(attribute);
+
+        assert_eq!(builder.parse().source_debug_extension().map(String::as_str), Some(smap));
+    }
+
+    #[test]
+    fn diff_reports_an_added_method() {
+        let version = |with_reset: bool| {
+            let mut builder = ClassBuilder::new("Counter");
+            builder.static_method("increment", "()V", 0, 0, &[0xb1]);
+
+            if with_reset {
+                builder.static_method("reset", "()V", 0, 0, &[0xb1]);
+            }
+
+            builder.parse()
+        };
+
+        let before = version(false);
+        let after = version(true);
+
+        assert_eq!(
+            before.diff(&after),
+            [ClassDiff::MethodAdded {
+                name: String::from("reset"),
+                descriptor: String::from("()V"),
+            }]
+        );
+        assert_eq!(
+            after.diff(&before),
+            [ClassDiff::MethodRemoved {
+                name: String::from("reset"),
+                descriptor: String::from("()V"),
+            }]
+        );
+        assert_eq!(before.diff(&version(false)), []);
+    }
+
+    #[test]
+    fn unknown_attributes_are_collected_instead_of_printed() {
+        let mut builder = ClassBuilder::new("Custom");
+        let custom = builder.attribute("com.example.Custom", &[1, 2, 3]);
+        builder.class_attribute(custom);
+
+        let mut context = ParseContext::default();
+        parse_with(&builder, &mut context).unwrap();
+        assert_eq!(context.unknown_attributes, ["com.example.Custom"]);
+
+        // Without a context the names go to the log rather than stderr
+        let (result, logs) = capture_logs(|| parse_class_bytes(&builder.build()));
+        result.unwrap();
+        assert_eq!(
+            logs,
+            [(log::Level::Warn, String::from("Got unexpected attribute kind with name: com.example.Custom"))]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_to_json_with_resolved_names() {
+        let json = serde_json::to_value(parse_fixture("SumLoop")).unwrap();
+
+        assert_eq!(json["this_class_name"], "SumLoop");
+        assert_eq!(json["super_class_name"], "java/lang/Object");
+        assert_eq!(json["major_version"], 52);
+        assert_eq!(json["constant_pool"][3]["Utf8"]["value"], "java/lang/Object");
+
+        let methods = json["methods"].as_array().unwrap();
+        let names = methods.iter().map(|method| method["name"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, ["<init>", "sum", "main"]);
+        assert_eq!(methods[0]["attributes"][0]["attribute_name"], "Code");
+    }
+
+    #[test]
+    fn finds_the_methods_using_invokevirtual() {
+        const INVOKEVIRTUAL: u8 = 0xb6;
+
+        let class = parse_fixture("Dispatch");
+        let names = class
+            .methods_using_opcode(INVOKEVIRTUAL)
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect::<Vec<_>>();
+
+        // <init> only uses invokespecial
+        assert_eq!(names, ["main"]);
+
+        // An operand byte that happens to be the opcode is not an instruction
+        let mut builder = ClassBuilder::new("OperandOnly");
+        builder.static_method("value", "()I", 1, 0, &[0x10, INVOKEVIRTUAL, 0xac]); // bipush -74, ireturn
+        assert!(builder.parse().methods_using_opcode(INVOKEVIRTUAL).is_empty());
+    }
+
+    #[test]
+    fn describes_every_kind_of_entry_like_javap() {
+        let mut builder = ClassBuilder::new("Described");
+        let integer = builder.integer(-7);
+        let long = builder.long(5);
+        let float = builder.float(1.5);
+        let double = builder.double(0.25);
+        let string = builder.string("Hello");
+        let array_class = builder.class("[I");
+        let method = builder.method_ref("java/io/PrintStream", "println", "(I)V");
+        let field = builder.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;");
+        let name_and_type = builder.name_and_type("run", "()V");
+        let class = builder.parse();
+        let pool = &class.constant_pool;
+
+        assert_eq!(pool.describe(class.this_class), "Described");
+        assert_eq!(pool.describe(array_class), "\"[I\"");
+        assert_eq!(pool.describe(integer), "-7");
+        assert_eq!(pool.describe(long), "5l");
+        assert_eq!(pool.describe(long + 1), "(unusable)");
+        assert_eq!(pool.describe(float), "1.5f");
+        assert_eq!(pool.describe(double), "0.25d");
+        assert_eq!(pool.describe(string), "Hello");
+        assert_eq!(pool.describe(method), "java/io/PrintStream.println:(I)V");
+        assert_eq!(pool.describe(field), "java/lang/System.out:Ljava/io/PrintStream;");
+        assert_eq!(pool.describe(name_and_type), "run:()V");
+        assert_eq!(pool.describe(0), "<invalid #0>");
+        assert_eq!(pool.describe(u16::MAX), format!("<invalid #{}>", u16::MAX));
+    }
+
+    #[test]
+    fn boxed_constant_pool_agrees_with_the_parsed_pool() {
+        let mut builder = ClassBuilder::new("Boxed");
+        builder.long(1);
+        builder.double(2.0);
+        builder.string("text");
+        builder.method_ref("java/io/PrintStream", "println", "(I)V");
+        builder.field_ref("Boxed", "count", "I");
+        let class = builder.parse();
+        let pool = &class.constant_pool;
+        let boxed = class.boxed_constant_pool();
+
+        // Index 0, every entry including the slots after the Long and Double, and past the end
+        for index in 0..=pool.len() as u16 + 1 {
+            assert_eq!(boxed.try_get_value(index), pool.try_get_value(index), "#{index}");
+            assert_eq!(
+                boxed.get_class_name_from_index(index).ok(),
+                pool.get_class_name_from_index(index).ok(),
+                "#{index}"
+            );
+            assert_eq!(boxed.get_name_and_type(index).ok(), pool.get_name_and_type(index).ok(), "#{index}");
+            assert_eq!(boxed.get_utf8_from_index(index).ok(), pool.get_utf8_from_index(index).ok(), "#{index}");
+        }
+
+        assert_eq!(boxed.get_value(class.this_class), pool.get_value(class.this_class));
+        assert!(boxed.try_get_value(0).is_none());
+        assert!(pool.iter().any(|info| matches!(info, ConstantPoolInfo::Unusable)));
+    }
+
+    #[test]
+    fn boxed_constant_pool_leaves_undecodable_lazy_strings_unresolved() {
+        let mut builder = ClassBuilder::new("LazyBoxed");
+        let invalid = builder.utf8_bytes(&[0xFF]);
+        let mut context = ParseContext {
+            lazy_utf8: true,
+            ..ParseContext::default()
+        };
+        let class = parse_with(&builder, &mut context).unwrap();
+        let boxed = class.boxed_constant_pool();
+
+        assert_eq!(boxed.get_utf8_from_index(invalid), Err(InvalidConstant));
+        assert_eq!(class.constant_pool.get_utf8_from_index(invalid), Err(InvalidConstant));
+        assert_eq!(boxed.get_class_name_from_index(class.this_class).unwrap(), "LazyBoxed");
+    }
+
+    #[test]
+    fn lazy_utf8_matches_the_eager_parse() {
+        let source: Rc<[u8]> = Rc::from(fs::read(fixtures_dir().join("Documented.class")).unwrap());
+        let eager = parse_class_source(source.clone(), &mut ParseContext::default()).unwrap();
+        let mut context = ParseContext {
+            lazy_utf8: true,
+            ..ParseContext::default()
+        };
+        let lazy = parse_class_source(source, &mut context).unwrap();
+
+        assert!(lazy.constant_pool.iter().any(|info| matches!(info, ConstantPoolInfo::LazyUtf8 { .. })));
+
+        for index in 0..=eager.constant_pool.len() as u16 {
+            assert_eq!(
+                lazy.constant_pool.get_utf8_from_index(index).ok(),
+                eager.constant_pool.get_utf8_from_index(index).ok(),
+                "#{index}"
+            );
+            assert_eq!(
+                lazy.constant_pool.describe(index),
+                eager.constant_pool.describe(index),
+                "#{index}"
+            );
+        }
+    }
+
+    #[test]
+    fn lazy_utf8_defers_invalid_strings_to_lookup() {
+        let mut builder = ClassBuilder::new("BadString");
+        // A lone continuation byte is not modified UTF-8
+        let invalid = builder.utf8_bytes(&[b'a', 0x80]);
+
+        assert!(matches!(
+            parse_with(&builder, &mut ParseContext::default()),
+            Err(ClassParseError::Malformed(_))
+        ));
+
+        let mut context = ParseContext {
+            lazy_utf8: true,
+            ..ParseContext::default()
+        };
+        let class = parse_with(&builder, &mut context).unwrap();
+        assert_eq!(class.constant_pool.get_utf8_from_index(invalid), Err(InvalidConstant));
     }
 }
@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use log::warn;
+
+use crate::class::{parse_class_file, ClassFile};
+
+/**
+ * Finds class files on the class path by their internal name (ex. `com/example/Foo`)
+ * and keeps every parsed class around so it is only ever read from disk once
+ */
+pub struct ClassLoader {
+    class_path: Vec<PathBuf>,
+    classes: HashMap<String, Rc<ClassFile>>,
+}
+
+impl ClassLoader {
+    pub fn new(class_path: Vec<PathBuf>) -> ClassLoader {
+        ClassLoader {
+            class_path,
+            classes: HashMap::new(),
+        }
+    }
+
+    /**
+     * Registers an already parsed class with the loader (ex. the class passed on the command line)
+     */
+    pub fn add_class(&mut self, class: ClassFile) -> Rc<ClassFile> {
+        let class = Rc::new(class);
+
+        self.classes
+            .insert(class.get_this_class_name().clone(), class.clone());
+
+        class
+    }
+
+    pub fn is_loaded(&self, class_name: &str) -> bool {
+        self.classes.contains_key(class_name)
+    }
+
+    /**
+     * Returns the class with the given internal name, searching the class path if it
+     * has not been loaded yet
+     */
+    pub fn load_class(&mut self, class_name: &str) -> io::Result<Rc<ClassFile>> {
+        if let Some(class) = self.classes.get(class_name) {
+            return Ok(class.clone());
+        }
+
+        for directory in &self.class_path {
+            let path = directory.join(format!("{class_name}.class"));
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let class = parse_class_file(&path)?;
+
+            if class.get_this_class_name() != class_name {
+                return Err(io::Error::other(format!(
+                    "Expected {} to contain class {class_name}, but found {}",
+                    path.display(),
+                    class.get_this_class_name()
+                )));
+            }
+
+            return Ok(self.add_class(class));
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Could not find class {class_name} on the class path"),
+        ))
+    }
+
+    /**
+     * Whether `class_name` is `super_name` or extends or implements it transitively, loading the
+     * classes in between as needed. Every class is a subtype of `java/lang/Object`. A class that
+     * can't be loaded is skipped with a warning, so the answer is `false` unless another path
+     * proves the relationship.
+     */
+    pub fn is_subtype_of(&mut self, class_name: &str, super_name: &str) -> bool {
+        if class_name == super_name || super_name == "java/lang/Object" {
+            return true;
+        }
+
+        let mut pending = vec![class_name.to_string()];
+        let mut visited = HashSet::new();
+
+        while let Some(current) = pending.pop() {
+            // java/lang/Object is not on the class path, and it has already been ruled out
+            if current == "java/lang/Object" || !visited.insert(current.clone()) {
+                continue;
+            }
+
+            let class = match self.load_class(&current) {
+                Ok(class) => class,
+                Err(err) => {
+                    warn!("Could not load {current} while checking if {class_name} is a subtype of {super_name}: {err}");
+                    continue;
+                }
+            };
+
+            let supertypes = class
+                .super_class_ref()
+                .map(|super_class| super_class.name)
+                .into_iter()
+                .chain(class.get_interface_names().into_iter().cloned());
+
+            for supertype in supertypes {
+                if supertype == super_name {
+                    return true;
+                }
+
+                pending.push(supertype);
+            }
+        }
+
+        false
+    }
+
+    /**
+     * Finds the class that declares the field a `Fieldref` to `class_name` refers to. Like the
+     * JVM, this searches the class itself, then the interfaces it extends or implements, then its
+     * superclass. Classes that can't be loaded are skipped with a warning.
+     */
+    pub fn resolve_field(&mut self, class_name: &str, field_name: &str) -> Option<Rc<ClassFile>> {
+        if class_name == "java/lang/Object" {
+            return None;
+        }
+
+        let class = match self.load_class(class_name) {
+            Ok(class) => class,
+            Err(err) => {
+                warn!("Could not load {class_name} while resolving field {field_name}: {err}");
+                return None;
+            }
+        };
+
+        if class.fields.iter().any(|field| field.name == field_name) {
+            return Some(class);
+        }
+
+        for interface in class.get_interface_names() {
+            if let Some(declaring_class) = self.resolve_field(interface, field_name) {
+                return Some(declaring_class);
+            }
+        }
+
+        let super_class = class.super_class_ref()?;
+
+        self.resolve_field(&super_class.name, field_name)
+    }
+
+    /**
+     * Every interface `class_name` implements, directly, through its superclasses or through
+     * the interfaces those extend, in the order they are found and without duplicates. Classes
+     * that can't be loaded are skipped with a warning, so their interfaces are missing from the result.
+     */
+    pub fn all_interfaces(&mut self, class_name: &str) -> Vec<String> {
+        let mut interfaces = Vec::new();
+        let mut pending = VecDeque::from([class_name.to_string()]);
+        let mut visited = HashSet::new();
+
+        while let Some(current) = pending.pop_front() {
+            // A malformed hierarchy can contain cycles, so every type is only expanded once
+            if current == "java/lang/Object" || !visited.insert(current.clone()) {
+                continue;
+            }
+
+            let class = match self.load_class(&current) {
+                Ok(class) => class,
+                Err(err) => {
+                    warn!("Could not load {current} while collecting the interfaces of {class_name}: {err}");
+                    continue;
+                }
+            };
+
+            if let Some(super_class) = class.super_class_ref() {
+                pending.push_back(super_class.name);
+            }
+
+            for interface in class.get_interface_names() {
+                if !interfaces.contains(interface) {
+                    interfaces.push(interface.clone());
+                }
+
+                pending.push_back(interface.clone());
+            }
+        }
+
+        interfaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture_logs, fixtures_dir, ClassBuilder};
+
+    #[test]
+    fn subtypes_follow_superclasses_and_interfaces() {
+        let mut class_loader = ClassLoader::new(vec![fixtures_dir()]);
+
+        assert!(class_loader.is_subtype_of("Square", "Square"));
+        assert!(class_loader.is_subtype_of("Square", "AbstractPolygon"));
+        // Through the interface the superclass implements, and the one that extends
+        assert!(class_loader.is_subtype_of("Square", "Polygon"));
+        assert!(class_loader.is_subtype_of("Square", "Shape"));
+        assert!(class_loader.is_subtype_of("Square", "java/io/Serializable"));
+        assert!(class_loader.is_subtype_of("Polygon", "java/lang/Object"));
+
+        assert!(!class_loader.is_subtype_of("AbstractPolygon", "Square"));
+        assert!(!class_loader.is_subtype_of("Shape", "Polygon"));
+    }
+
+    #[test]
+    fn unloadable_supertypes_are_not_subtypes() {
+        let mut builder = ClassBuilder::new("Orphan");
+        let parent = builder.class("missing/Parent");
+        builder.super_class_index(parent);
+
+        let mut class_loader = ClassLoader::new(Vec::new());
+        class_loader.add_class(builder.parse());
+
+        let (is_subtype, logs) = capture_logs(|| class_loader.is_subtype_of("Orphan", "missing/Grandparent"));
+
+        assert!(!is_subtype);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0, log::Level::Warn);
+        assert!(logs[0].1.starts_with("Could not load missing/Parent while checking if Orphan is a subtype of missing/Grandparent"));
+    }
+
+    #[test]
+    fn all_interfaces_includes_the_interfaces_they_extend() {
+        let mut class_loader = ClassLoader::new(vec![fixtures_dir()]);
+
+        // Serializable directly, Polygon through AbstractPolygon, and Shape through Polygon
+        assert_eq!(class_loader.all_interfaces("Square"), ["java/io/Serializable", "Polygon", "Shape"]);
+        assert_eq!(class_loader.all_interfaces("Polygon"), ["Shape"]);
+        assert!(class_loader.all_interfaces("Shape").is_empty());
+    }
+
+    #[test]
+    fn all_interfaces_stops_at_cycles() {
+        let mut first = ClassBuilder::new("First");
+        let second = first.class("Second");
+        first.super_class_index(second);
+
+        let mut second = ClassBuilder::new("Second");
+        let first_index = second.class("First");
+        second.super_class_index(first_index);
+
+        let mut class_loader = ClassLoader::new(Vec::new());
+        class_loader.add_class(first.parse());
+        class_loader.add_class(second.parse());
+
+        assert!(class_loader.all_interfaces("First").is_empty());
+    }
+}
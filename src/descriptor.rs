@@ -0,0 +1,351 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Object(String),
+    Array(Box<FieldType>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReturnType {
+    Void,
+    Value(FieldType),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodDescriptor {
+    pub params: Vec<FieldType>,
+    pub return_type: ReturnType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DescriptorError {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    TrailingCharacters(String),
+}
+
+impl FieldType {
+    /**
+     * Number of local variable or operand stack slots a value of this type takes up
+     * (2 for `long` and `double`, 1 for everything else)
+     */
+    pub fn slots(&self) -> usize {
+        match self {
+            FieldType::Long | FieldType::Double => 2,
+            _ => 1,
+        }
+    }
+
+    /**
+     * The field descriptor of the type (ex. `I`, `[Ljava/lang/String;`). Array classes are also
+     * named by their descriptor (ex. `[I`).
+     */
+    pub fn to_descriptor(&self) -> String {
+        match self {
+            FieldType::Byte => String::from("B"),
+            FieldType::Char => String::from("C"),
+            FieldType::Double => String::from("D"),
+            FieldType::Float => String::from("F"),
+            FieldType::Int => String::from("I"),
+            FieldType::Long => String::from("J"),
+            FieldType::Short => String::from("S"),
+            FieldType::Boolean => String::from("Z"),
+            FieldType::Object(class_name) => format!("L{class_name};"),
+            FieldType::Array(component) => format!("[{}", component.to_descriptor()),
+        }
+    }
+}
+
+/**
+ * Formats types the way they are written in Java source (ex. `int`, `java.lang.String[]`)
+ */
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Byte => write!(f, "byte"),
+            FieldType::Char => write!(f, "char"),
+            FieldType::Double => write!(f, "double"),
+            FieldType::Float => write!(f, "float"),
+            FieldType::Int => write!(f, "int"),
+            FieldType::Long => write!(f, "long"),
+            FieldType::Short => write!(f, "short"),
+            FieldType::Boolean => write!(f, "boolean"),
+            FieldType::Object(class_name) => write!(f, "{}", class_name.replace('/', ".")),
+            FieldType::Array(component) => write!(f, "{component}[]"),
+        }
+    }
+}
+
+impl fmt::Display for ReturnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReturnType::Void => write!(f, "void"),
+            ReturnType::Value(field_type) => write!(f, "{field_type}"),
+        }
+    }
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::UnexpectedEnd => write!(f, "Unexpected end of descriptor"),
+            DescriptorError::UnexpectedChar(c) => write!(f, "Unexpected character '{c}' in descriptor"),
+            DescriptorError::TrailingCharacters(rest) => {
+                write!(f, "Unexpected trailing characters \"{rest}\" in descriptor")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DescriptorError {}
+
+/**
+ * Parses a field descriptor (ex. `I`, `[[J` or `Ljava/lang/String;`)
+ */
+pub fn parse_field_descriptor(descriptor: &str) -> Result<FieldType, DescriptorError> {
+    let mut chars = descriptor.chars().peekable();
+
+    let field_type = parse_field_type(&mut chars)?;
+
+    expect_end(chars)?;
+
+    Ok(field_type)
+}
+
+/**
+ * Parses a method descriptor (ex. `(ILjava/lang/String;)V`) into its parameter and return types
+ */
+pub fn parse_method_descriptor(descriptor: &str) -> Result<MethodDescriptor, DescriptorError> {
+    let mut chars = descriptor.chars().peekable();
+
+    match chars.next() {
+        Some('(') => {}
+        Some(c) => return Err(DescriptorError::UnexpectedChar(c)),
+        None => return Err(DescriptorError::UnexpectedEnd),
+    }
+
+    let mut params = Vec::new();
+
+    loop {
+        match chars.peek() {
+            Some(')') => {
+                chars.next();
+                break;
+            }
+            Some(_) => params.push(parse_field_type(&mut chars)?),
+            None => return Err(DescriptorError::UnexpectedEnd),
+        }
+    }
+
+    let return_type = match chars.peek() {
+        Some('V') => {
+            chars.next();
+            ReturnType::Void
+        }
+        _ => ReturnType::Value(parse_field_type(&mut chars)?),
+    };
+
+    expect_end(chars)?;
+
+    Ok(MethodDescriptor {
+        params,
+        return_type,
+    })
+}
+
+/**
+ * Formats a method the way it would be declared in Java (ex. `add` + `(II)I` becomes `int add(int, int)`).
+ * Falls back to the raw name and descriptor if the descriptor is malformed.
+ */
+pub fn format_method_signature(name: &str, descriptor: &str) -> String {
+    let Ok(method_descriptor) = parse_method_descriptor(descriptor) else {
+        return format!("{name}{descriptor}");
+    };
+
+    let params = method_descriptor
+        .params
+        .iter()
+        .map(|param| param.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{} {name}({params})", method_descriptor.return_type)
+}
+
+/**
+ * Compares two method or field descriptors by their parsed types rather than their raw text,
+ * ignoring any whitespace in them (ex. `(I I)V` equals `(II)V`). Descriptors that fail to parse
+ * are only equal if they are textually identical.
+ */
+pub fn descriptors_equal(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+
+    let a: String = a.chars().filter(|c| !c.is_whitespace()).collect();
+    let b: String = b.chars().filter(|c| !c.is_whitespace()).collect();
+    let (a, b) = (a.as_str(), b.as_str());
+
+    if let (Ok(a), Ok(b)) = (parse_method_descriptor(a), parse_method_descriptor(b)) {
+        return a == b;
+    }
+
+    if let (Ok(a), Ok(b)) = (parse_field_descriptor(a), parse_field_descriptor(b)) {
+        return a == b;
+    }
+
+    false
+}
+
+fn parse_field_type(chars: &mut Peekable<Chars>) -> Result<FieldType, DescriptorError> {
+    let Some(c) = chars.next() else {
+        return Err(DescriptorError::UnexpectedEnd);
+    };
+
+    let field_type = match c {
+        'B' => FieldType::Byte,
+        'C' => FieldType::Char,
+        'D' => FieldType::Double,
+        'F' => FieldType::Float,
+        'I' => FieldType::Int,
+        'J' => FieldType::Long,
+        'S' => FieldType::Short,
+        'Z' => FieldType::Boolean,
+        'L' => {
+            let mut class_name = String::new();
+
+            loop {
+                match chars.next() {
+                    Some(';') => break,
+                    Some(c) => class_name.push(c),
+                    None => return Err(DescriptorError::UnexpectedEnd),
+                }
+            }
+
+            if class_name.is_empty() {
+                return Err(DescriptorError::UnexpectedChar(';'));
+            }
+
+            FieldType::Object(class_name)
+        }
+        '[' => FieldType::Array(Box::new(parse_field_type(chars)?)),
+        c => return Err(DescriptorError::UnexpectedChar(c)),
+    };
+
+    Ok(field_type)
+}
+
+fn expect_end(chars: Peekable<Chars>) -> Result<(), DescriptorError> {
+    let rest: String = chars.collect();
+
+    if !rest.is_empty() {
+        return Err(DescriptorError::TrailingCharacters(rest));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_mixed_parameter_types() {
+        assert_eq!(
+            format_method_signature("put", "(IJ[Ljava/lang/String;[[DZ)Ljava/util/Map;"),
+            "java.util.Map put(int, long, java.lang.String[], double[][], boolean)"
+        );
+        assert_eq!(format_method_signature("main", "([Ljava/lang/String;)V"), "void main(java.lang.String[])");
+
+        // Malformed descriptors are shown as they are
+        assert_eq!(format_method_signature("broken", "(I"), "broken(I");
+    }
+
+    #[test]
+    fn descriptors_compare_by_their_types() {
+        assert!(descriptors_equal("(II)V", "(II)V"));
+        assert!(descriptors_equal("( I I ) V", "(II)V"));
+        assert!(descriptors_equal(" [Ljava/lang/String; ", "[Ljava/lang/String;"));
+
+        assert!(!descriptors_equal("(II)V", "(IJ)V"));
+        assert!(!descriptors_equal("(II)V", "(II)I"));
+        assert!(!descriptors_equal("I", "(I)V"));
+
+        // Malformed descriptors only match themselves
+        assert!(descriptors_equal("(I", "(I"));
+        assert!(!descriptors_equal("(I", "( I"));
+    }
+
+    #[test]
+    fn parses_method_descriptors() {
+        assert_eq!(
+            parse_method_descriptor("(II)I"),
+            Ok(MethodDescriptor {
+                params: vec![FieldType::Int, FieldType::Int],
+                return_type: ReturnType::Value(FieldType::Int),
+            })
+        );
+        assert_eq!(
+            parse_method_descriptor("([Ljava/lang/String;)V"),
+            Ok(MethodDescriptor {
+                params: vec![FieldType::Array(Box::new(FieldType::Object(String::from("java/lang/String"))))],
+                return_type: ReturnType::Void,
+            })
+        );
+        assert_eq!(
+            parse_method_descriptor("()Ljava/lang/Object;"),
+            Ok(MethodDescriptor {
+                params: vec![],
+                return_type: ReturnType::Value(FieldType::Object(String::from("java/lang/Object"))),
+            })
+        );
+        assert_eq!(
+            parse_field_descriptor("[[I"),
+            Ok(FieldType::Array(Box::new(FieldType::Array(Box::new(FieldType::Int)))))
+        );
+    }
+
+    #[test]
+    fn descriptors_round_trip() {
+        for descriptor in ["(II)I", "([Ljava/lang/String;)V", "()Ljava/lang/Object;", "(J[[DLjava/util/List;Z)[B"] {
+            let method_descriptor = parse_method_descriptor(descriptor).unwrap();
+
+            let params = method_descriptor
+                .params
+                .iter()
+                .map(FieldType::to_descriptor)
+                .collect::<String>();
+            let return_type = match method_descriptor.return_type {
+                ReturnType::Void => String::from("V"),
+                ReturnType::Value(field_type) => field_type.to_descriptor(),
+            };
+
+            assert_eq!(format!("({params}){return_type}"), descriptor);
+        }
+    }
+
+    #[test]
+    fn malformed_descriptors_are_errors() {
+        assert_eq!(parse_method_descriptor(""), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(parse_method_descriptor("II)I"), Err(DescriptorError::UnexpectedChar('I')));
+        assert_eq!(parse_method_descriptor("(I"), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(parse_method_descriptor("(I)"), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(parse_method_descriptor("(Q)V"), Err(DescriptorError::UnexpectedChar('Q')));
+        // An object type without its closing semicolon
+        assert_eq!(parse_method_descriptor("(Ljava/lang/String)V"), Err(DescriptorError::UnexpectedEnd));
+        assert_eq!(
+            parse_method_descriptor("()VI"),
+            Err(DescriptorError::TrailingCharacters(String::from("I")))
+        );
+        assert_eq!(parse_field_descriptor("V"), Err(DescriptorError::UnexpectedChar('V')));
+    }
+}
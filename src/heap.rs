@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::{descriptor::FieldType, interpreter::OperandStackEntry};
+
+/**
+ * Something created by `new` or one of the array creation instructions
+ */
+#[derive(Debug, Clone)]
+pub enum HeapObject {
+    Object {
+        class_name: String,
+        /** Values of the instance fields, keyed by field name */
+        fields: HashMap<String, OperandStackEntry>,
+    },
+    /**
+     * An array, whose length is the number of elements. Elements of `boolean`, `byte`, `char`
+     * and `short` arrays are stored as `Int`s, already truncated to the element type.
+     */
+    Array {
+        element_type: FieldType,
+        elements: Vec<OperandStackEntry>,
+    },
+}
+
+impl HeapObject {
+    /**
+     * An array of `length` elements that all hold the default value of `element_type`
+     */
+    pub fn new_array(element_type: FieldType, length: usize) -> HeapObject {
+        let elements = vec![OperandStackEntry::default_for(&element_type); length];
+
+        HeapObject::Array {
+            element_type,
+            elements,
+        }
+    }
+
+    /**
+     * The name `getClass().getName()` would return (ex. `com/example/Foo`, or `[I` for an `int[]`)
+     */
+    pub fn class_name(&self) -> String {
+        match self {
+            HeapObject::Object { class_name, .. } => class_name.clone(),
+            HeapObject::Array { element_type, .. } => {
+                FieldType::Array(Box::new(element_type.clone())).to_descriptor()
+            }
+        }
+    }
+}
+
+/**
+ * Owns every object the interpreter creates. References on the operand stack and in locals are
+ * handles (indices into the heap), so copying a reference aliases the object instead of copying
+ * it. Objects are never freed, so a handle stays valid for the lifetime of the heap.
+ */
+#[derive(Debug, Default)]
+pub struct Heap {
+    objects: Vec<HeapObject>,
+}
+
+impl Heap {
+    pub fn new() -> Heap {
+        Heap::default()
+    }
+
+    /**
+     * Moves an object onto the heap and returns the handle that refers to it
+     */
+    pub fn allocate(&mut self, object: HeapObject) -> usize {
+        self.objects.push(object);
+
+        self.objects.len() - 1
+    }
+
+    pub fn get(&self, handle: usize) -> Option<&HeapObject> {
+        self.objects.get(handle)
+    }
+
+    pub fn get_mut(&mut self, handle: usize) -> Option<&mut HeapObject> {
+        self.objects.get_mut(handle)
+    }
+
+    /**
+     * Number of objects allocated so far
+     */
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
@@ -0,0 +1,613 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use crate::{
+    bytes::ByteParsable,
+    class::{ConstantPool, ConstantPoolInfo},
+};
+
+pub const ILOAD: u8 = 0x15;
+pub const ALOAD: u8 = 0x19;
+pub const ISTORE: u8 = 0x36;
+pub const ASTORE: u8 = 0x3a;
+pub const LDC: u8 = 0x12;
+pub const LDC_W: u8 = 0x13;
+pub const LDC2_W: u8 = 0x14;
+pub const TABLESWITCH: u8 = 0xaa;
+pub const LOOKUPSWITCH: u8 = 0xab;
+pub const WIDE: u8 = 0xc4;
+pub const IINC: u8 = 0x84;
+pub const GOTO: u8 = 0xa7;
+pub const JSR: u8 = 0xa8;
+pub const RET: u8 = 0xa9;
+pub const IRETURN: u8 = 0xac;
+pub const RETURN: u8 = 0xb1;
+pub const ATHROW: u8 = 0xbf;
+pub const IFNULL: u8 = 0xc6;
+pub const IFNONNULL: u8 = 0xc7;
+pub const GOTO_W: u8 = 0xc8;
+pub const JSR_W: u8 = 0xc9;
+
+/**
+ * Returns the length in bytes (opcode included) of the instruction starting at `pc`
+ */
+pub fn instruction_length(code: &[u8], pc: usize) -> io::Result<usize> {
+    let Some(&opcode) = code.get(pc) else {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Expected an instruction at {pc}"),
+        ));
+    };
+
+    let length = match opcode {
+        0x00..=0x0f => 1,
+        0x10 => 2,
+        0x11 => 3,
+        LDC => 2,
+        LDC_W | LDC2_W => 3,
+        0x15..=0x19 => 2,
+        0x1a..=0x35 => 1,
+        0x36..=0x3a => 2,
+        0x3b..=0x83 => 1,
+        IINC => 3,
+        0x85..=0x98 => 1,
+        0x99..=0xa8 => 3,
+        0xa9 => 2,
+        TABLESWITCH | LOOKUPSWITCH => {
+            let mut cursor = Cursor::new(code);
+            SwitchTable::parse(&mut cursor, pc as u64)?;
+
+            cursor.position() as usize - pc
+        }
+        0xac..=0xb1 => 1,
+        0xb2..=0xb8 => 3,
+        0xb9 | 0xba => 5,
+        0xbb => 3,
+        0xbc => 2,
+        0xbd => 3,
+        0xbe | 0xbf => 1,
+        0xc0 | 0xc1 => 3,
+        0xc2 | 0xc3 => 1,
+        WIDE => match code.get(pc + 1) {
+            Some(&IINC) => 6,
+            Some(_) => 4,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Expected an instruction after wide at {pc}"),
+                ))
+            }
+        },
+        0xc5 => 4,
+        0xc6 | 0xc7 => 3,
+        0xc8 | 0xc9 => 5,
+        0xca | 0xfe | 0xff => 1,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown opcode 0x{opcode:02x} at {pc}"),
+            ))
+        }
+    };
+
+    if pc + length > code.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Instruction 0x{opcode:02x} at {pc} runs past the end of the code"),
+        ));
+    }
+
+    Ok(length)
+}
+
+/**
+ * Mnemonics of the opcodes `0x00` (`nop`) to `0xc9` (`jsr_w`), indexed by opcode
+ */
+const MNEMONICS: [&str; 0xca] = [
+    "nop", "aconst_null", "iconst_m1", "iconst_0", "iconst_1", "iconst_2", "iconst_3", "iconst_4",
+    "iconst_5", "lconst_0", "lconst_1", "fconst_0", "fconst_1", "fconst_2", "dconst_0", "dconst_1",
+    "bipush", "sipush", "ldc", "ldc_w", "ldc2_w", "iload", "lload", "fload", "dload", "aload",
+    "iload_0", "iload_1", "iload_2", "iload_3", "lload_0", "lload_1", "lload_2", "lload_3",
+    "fload_0", "fload_1", "fload_2", "fload_3", "dload_0", "dload_1", "dload_2", "dload_3",
+    "aload_0", "aload_1", "aload_2", "aload_3", "iaload", "laload", "faload", "daload", "aaload",
+    "baload", "caload", "saload", "istore", "lstore", "fstore", "dstore", "astore", "istore_0",
+    "istore_1", "istore_2", "istore_3", "lstore_0", "lstore_1", "lstore_2", "lstore_3", "fstore_0",
+    "fstore_1", "fstore_2", "fstore_3", "dstore_0", "dstore_1", "dstore_2", "dstore_3", "astore_0",
+    "astore_1", "astore_2", "astore_3", "iastore", "lastore", "fastore", "dastore", "aastore",
+    "bastore", "castore", "sastore", "pop", "pop2", "dup", "dup_x1", "dup_x2", "dup2", "dup2_x1",
+    "dup2_x2", "swap", "iadd", "ladd", "fadd", "dadd", "isub", "lsub", "fsub", "dsub", "imul",
+    "lmul", "fmul", "dmul", "idiv", "ldiv", "fdiv", "ddiv", "irem", "lrem", "frem", "drem", "ineg",
+    "lneg", "fneg", "dneg", "ishl", "lshl", "ishr", "lshr", "iushr", "lushr", "iand", "land", "ior",
+    "lor", "ixor", "lxor", "iinc", "i2l", "i2f", "i2d", "l2i", "l2f", "l2d", "f2i", "f2l", "f2d",
+    "d2i", "d2l", "d2f", "i2b", "i2c", "i2s", "lcmp", "fcmpl", "fcmpg", "dcmpl", "dcmpg", "ifeq",
+    "ifne", "iflt", "ifge", "ifgt", "ifle", "if_icmpeq", "if_icmpne", "if_icmplt", "if_icmpge",
+    "if_icmpgt", "if_icmple", "if_acmpeq", "if_acmpne", "goto", "jsr", "ret", "tableswitch",
+    "lookupswitch", "ireturn", "lreturn", "freturn", "dreturn", "areturn", "return", "getstatic",
+    "putstatic", "getfield", "putfield", "invokevirtual", "invokespecial", "invokestatic",
+    "invokeinterface", "invokedynamic", "new", "newarray", "anewarray", "arraylength", "athrow",
+    "checkcast", "instanceof", "monitorenter", "monitorexit", "wide", "multianewarray", "ifnull",
+    "ifnonnull", "goto_w", "jsr_w",
+];
+
+/**
+ * The mnemonic of an opcode as `javap` prints it (ex. `invokevirtual`), or `None` for opcodes
+ * the spec does not define
+ */
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    match opcode {
+        0xca => Some("breakpoint"),
+        0xfe => Some("impdep1"),
+        0xff => Some("impdep2"),
+        _ => MNEMONICS.get(opcode as usize).copied(),
+    }
+}
+
+/**
+ * Formats code in the style of `javap -c`, one instruction per line with its offset, mnemonic
+ * and operands. Constant pool operands are followed by what they resolve to:
+ *
+ * ```text
+ *        0: getstatic     #7                  // Field java/lang/System.out:Ljava/io/PrintStream;
+ *        3: ldc           #13                 // String Hello
+ *        5: invokevirtual #15                 // Method java/io/PrintStream.println:(Ljava/lang/String;)V
+ *        8: return
+ * ```
+ *
+ * Decoding stops at an unknown opcode or an instruction that runs past the end of the code,
+ * which are marked in the output, since the instructions after it can't be located.
+ */
+pub fn disassemble(code: &[u8], constant_pool: &dyn ConstantPool) -> String {
+    let mut out = String::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let opcode = code[pc];
+
+        let length = match instruction_length(code, pc) {
+            Ok(length) => length,
+            Err(err) => {
+                let marker = match mnemonic(opcode) {
+                    Some(name) => format!("{name} <{err}>"),
+                    None => format!("<unknown opcode 0x{opcode:02x}>"),
+                };
+
+                out.push_str(&format!("{pc:>8}: {marker}\n"));
+                break;
+            }
+        };
+
+        // The length check above guarantees the operands are there
+        let line = format_instruction(code, pc, constant_pool)
+            .expect("Expected instruction operands to be within the code");
+
+        // The continuation lines of a switch line up under the instruction like in javap
+        let line = line.replace('\n', &format!("\n{:10}", ""));
+
+        out.push_str(&format!("{pc:>8}: {line}\n"));
+
+        pc += length;
+    }
+
+    out
+}
+
+/**
+ * Formats the instruction at `pc` without its offset, as `mnemonic operands // comment`
+ */
+fn format_instruction(code: &[u8], pc: usize, constant_pool: &dyn ConstantPool) -> io::Result<String> {
+    let mut cursor = Cursor::new(code);
+    cursor.set_position(pc as u64 + 1);
+
+    let opcode = code[pc];
+    let name = mnemonic(opcode).unwrap_or("<unknown>");
+
+    let (operands, comment) = match opcode {
+        0x10 => (cursor.parse_i1()?.to_string(), None),
+        0x11 => (cursor.parse_i2()?.to_string(), None),
+        LDC => {
+            let index = cursor.parse_u1()? as u16;
+
+            (format!("#{index}"), describe_constant(constant_pool, index))
+        }
+        LDC_W | LDC2_W | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => {
+            let index = cursor.parse_u2()?;
+
+            (format!("#{index}"), describe_constant(constant_pool, index))
+        }
+        ILOAD..=ALOAD | ISTORE..=ASTORE | RET => (cursor.parse_u1()?.to_string(), None),
+        IINC => (format!("{}, {}", cursor.parse_u1()?, cursor.parse_i1()?), None),
+        0x99..=JSR | IFNULL | IFNONNULL => {
+            (target(pc, cursor.parse_i2()? as i32), None)
+        }
+        GOTO_W | JSR_W => (target(pc, cursor.parse_i4()?), None),
+        TABLESWITCH | LOOKUPSWITCH => {
+            // The table already starts with the mnemonic
+            return Ok(SwitchTable::parse(&mut cursor, pc as u64)?.format(pc as u64));
+        }
+        0xb9 => {
+            let index = cursor.parse_u2()?;
+            let count = cursor.parse_u1()?;
+
+            (format!("#{index},  {count}"), describe_constant(constant_pool, index))
+        }
+        0xba => {
+            let index = cursor.parse_u2()?;
+
+            (format!("#{index},  0"), describe_constant(constant_pool, index))
+        }
+        0xbc => {
+            let atype = cursor.parse_u1()?;
+
+            let type_name = match atype {
+                4 => String::from("boolean"),
+                5 => String::from("char"),
+                6 => String::from("float"),
+                7 => String::from("double"),
+                8 => String::from("byte"),
+                9 => String::from("short"),
+                10 => String::from("int"),
+                11 => String::from("long"),
+                _ => format!("<unknown type {atype}>"),
+            };
+
+            (type_name, None)
+        }
+        0xc5 => {
+            let index = cursor.parse_u2()?;
+            let dimensions = cursor.parse_u1()?;
+
+            (format!("#{index},  {dimensions}"), describe_constant(constant_pool, index))
+        }
+        WIDE => {
+            let modified = cursor.parse_u1()?;
+            let modified_name = mnemonic(modified).unwrap_or("<unknown>");
+            let index = cursor.parse_u2()?;
+
+            let operands = match modified {
+                IINC => format!("{modified_name} {index}, {}", cursor.parse_i2()?),
+                _ => format!("{modified_name} {index}"),
+            };
+
+            (operands, None)
+        }
+        _ => (String::new(), None),
+    };
+
+    if operands.is_empty() {
+        return Ok(name.to_string());
+    }
+
+    let instruction = format!("{name:<13} {operands}");
+
+    // Comments line up in a column unless the instruction is too long for it
+    Ok(match comment {
+        Some(comment) => format!("{instruction:<33} // {comment}"),
+        None => instruction,
+    })
+}
+
+fn target(pc: usize, offset: i32) -> String {
+    (pc as i64 + offset as i64).to_string()
+}
+
+/**
+ * Describes a constant the way `javap -c` comments it, as its kind and `ConstantPool::describe`
+ * (ex. `Method java/io/PrintStream.println:(I)V`). `None` for entries an instruction can't refer to.
+ */
+fn describe_constant(constant_pool: &dyn ConstantPool, index: u16) -> Option<String> {
+    let kind = match constant_pool.try_get_value(index)? {
+        ConstantPoolInfo::Fieldref { .. } => "Field",
+        ConstantPoolInfo::Methodref { .. } => "Method",
+        ConstantPoolInfo::InterfaceMethodref { .. } => "InterfaceMethod",
+        ConstantPoolInfo::InvokeDynamic { .. } => "InvokeDynamic",
+        ConstantPoolInfo::Class { .. } => "class",
+        ConstantPoolInfo::String { .. } => "String",
+        ConstantPoolInfo::Integer { .. } => "int",
+        ConstantPoolInfo::Float { .. } => "float",
+        ConstantPoolInfo::Long { .. } => "long",
+        ConstantPoolInfo::Double { .. } => "double",
+        ConstantPoolInfo::MethodHandle { .. } => "MethodHandle",
+        ConstantPoolInfo::MethodType { .. } => "MethodType",
+        _ => return None,
+    };
+
+    Some(format!("{kind} {}", constant_pool.describe(index)))
+}
+
+/**
+ * Returns the absolute targets of the branch or switch instruction at `pc`, which may lie
+ * outside the code if it is malformed. Other instructions have no targets.
+ */
+pub fn branch_targets(code: &[u8], pc: usize) -> io::Result<Vec<i64>> {
+    let mut cursor = Cursor::new(code);
+    cursor.set_position(pc as u64 + 1);
+
+    let targets = match code.get(pc).copied() {
+        Some(0x99..=JSR | IFNULL | IFNONNULL) => vec![pc as i64 + cursor.parse_i2()? as i64],
+        Some(GOTO_W | JSR_W) => vec![pc as i64 + cursor.parse_i4()? as i64],
+        Some(TABLESWITCH | LOOKUPSWITCH) => {
+            let table = SwitchTable::parse(&mut cursor, pc as u64)?;
+
+            table
+                .cases
+                .iter()
+                .map(|(_, offset)| *offset)
+                .chain([table.default_offset])
+                .map(|offset| pc as i64 + offset as i64)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Ok(targets)
+}
+
+/**
+ * Whether execution can never continue to the instruction after this one
+ */
+pub fn is_unconditional_transfer(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        IRETURN..=RETURN | ATHROW | GOTO | GOTO_W | RET | TABLESWITCH | LOOKUPSWITCH
+    )
+}
+
+/**
+ * Returns the highest local variable slot read or written by the instruction at `pc`,
+ * counting the second slot taken by a `long` or `double`. `None` if it does not use locals.
+ */
+pub fn highest_local_slot(code: &[u8], pc: usize) -> io::Result<Option<u16>> {
+    let mut cursor = Cursor::new(code);
+    cursor.set_position(pc as u64 + 1);
+
+    let Some(&opcode) = code.get(pc) else {
+        return Ok(None);
+    };
+
+    let (opcode, index) = match opcode {
+        ILOAD..=ALOAD | ISTORE..=ASTORE | IINC | RET => (opcode, cursor.parse_u1()? as u16),
+        WIDE => {
+            let opcode = cursor.parse_u1()?;
+
+            (opcode, cursor.parse_u2()?)
+        }
+        // The <x>load_<n> and <x>store_<n> forms, in groups of 4 per type (i, l, f, d, a)
+        0x1a..=0x2d => (ILOAD + (opcode - 0x1a) / 4, ((opcode - 0x1a) % 4) as u16),
+        0x3b..=0x4e => (ISTORE + (opcode - 0x3b) / 4, ((opcode - 0x3b) % 4) as u16),
+        _ => return Ok(None),
+    };
+
+    // lload, dload, lstore and dstore
+    let is_wide_value = matches!(opcode, 0x16 | 0x18 | 0x37 | 0x39);
+
+    Ok(Some(index + is_wide_value as u16))
+}
+
+/**
+ * The decoded operands of a `tableswitch` or `lookupswitch`. Offsets are relative to the
+ * switch instruction, like they are in the bytecode.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchTable {
+    pub opcode: u8,
+    pub default_offset: i32,
+    /** (key, offset) pairs in the order they appear in the bytecode */
+    pub cases: Vec<(i32, i32)>,
+}
+
+impl SwitchTable {
+    /**
+     * Decodes the operands of the switch instruction at `pc`. The cursor is left after the
+     * last operand, skipping the 0-3 padding bytes that align the operands to 4 bytes.
+     */
+    pub fn parse<R: Read + Seek>(code: &mut R, pc: u64) -> io::Result<SwitchTable> {
+        code.seek(SeekFrom::Start(pc))?;
+
+        let opcode = code.parse_u1()?;
+
+        let padding = (4 - (pc + 1) % 4) % 4;
+        code.seek(SeekFrom::Current(padding as i64))?;
+
+        let default_offset = code.parse_i4()?;
+
+        let cases = match opcode {
+            TABLESWITCH => {
+                let low = code.parse_i4()?;
+                let high = code.parse_i4()?;
+
+                if high < low {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("tableswitch at {pc} has high ({high}) < low ({low})"),
+                    ));
+                }
+
+                // Not pre-sized, since the count comes from untrusted bytecode and the cases run
+                // out long before a hostile count is reached
+                let mut cases = Vec::new();
+
+                for key in low..=high {
+                    cases.push((key, code.parse_i4()?));
+                }
+
+                cases
+            }
+            LOOKUPSWITCH => {
+                let npairs = code.parse_i4()?;
+
+                if npairs < 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("lookupswitch at {pc} has a negative number of pairs"),
+                    ));
+                }
+
+                let mut cases = Vec::new();
+
+                for _ in 0..npairs {
+                    cases.push((code.parse_i4()?, code.parse_i4()?));
+                }
+
+                cases
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Expected a switch instruction at {pc} but got 0x{opcode:02x}"),
+                ))
+            }
+        };
+
+        Ok(SwitchTable {
+            opcode,
+            default_offset,
+            cases,
+        })
+    }
+
+    /**
+     * Formats the cases with absolute targets in the multi-line style `javap -c` uses:
+     *
+     * ```text
+     * tableswitch   { // 1 to 3
+     *            1: 28
+     *            2: 33
+     *            3: 38
+     *      default: 43
+     * }
+     * ```
+     */
+    pub fn format(&self, pc: u64) -> String {
+        let target = |offset: i32| pc as i64 + offset as i64;
+
+        let mut out = match self.opcode {
+            TABLESWITCH => format!(
+                "tableswitch   {{ // {} to {}\n",
+                self.cases.first().map_or(0, |case| case.0),
+                self.cases.last().map_or(0, |case| case.0)
+            ),
+            _ => format!("lookupswitch  {{ // {}\n", self.cases.len()),
+        };
+
+        for (key, offset) in &self.cases {
+            out.push_str(&format!("{key:>12}: {}\n", target(*offset)));
+        }
+
+        out.push_str(&format!("{:>12}: {}\n", "default", target(self.default_offset)));
+        out.push('}');
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{class::parse_class_file, test_support::fixtures_dir};
+
+    #[test]
+    fn disassembles_switches() {
+        let class = parse_class_file(&fixtures_dir().join("Switches.class")).unwrap();
+
+        let disassemble_method = |name: &str| {
+            let code = class.get_method(name, "(I)I").unwrap().get_code_bytes().unwrap();
+
+            disassemble(code, &class.constant_pool)
+        };
+
+        // The same text as `javap -c`
+        assert_eq!(
+            disassemble_method("dense"),
+            concat!(
+                "       0: iload_0\n",
+                "       1: tableswitch   { // 1 to 3\n",
+                "                     1: 28\n",
+                "                     2: 31\n",
+                "                     3: 34\n",
+                "               default: 37\n",
+                "          }\n",
+                "      28: bipush        10\n",
+                "      30: ireturn\n",
+                "      31: bipush        20\n",
+                "      33: ireturn\n",
+                "      34: bipush        30\n",
+                "      36: ireturn\n",
+                "      37: iconst_0\n",
+                "      38: ireturn\n",
+            )
+        );
+        assert_eq!(
+            disassemble_method("sparse"),
+            concat!(
+                "       0: iload_0\n",
+                "       1: lookupswitch  { // 2\n",
+                "                  -100: 28\n",
+                "                  1000: 30\n",
+                "               default: 32\n",
+                "          }\n",
+                "      28: iconst_1\n",
+                "      29: ireturn\n",
+                "      30: iconst_2\n",
+                "      31: ireturn\n",
+                "      32: iconst_0\n",
+                "      33: ireturn\n",
+            )
+        );
+    }
+
+    #[test]
+    fn disassembles_a_println_main_like_javap() {
+        let class = parse_class_file(&fixtures_dir().join("PrintStreams.class")).unwrap();
+        let code = class
+            .get_method("main", "([Ljava/lang/String;)V")
+            .unwrap()
+            .get_code_bytes()
+            .unwrap();
+
+        assert_eq!(
+            disassemble(code, &class.constant_pool),
+            concat!(
+                "       0: getstatic     #7                  // Field java/lang/System.out:Ljava/io/PrintStream;\n",
+                "       3: ldc           #13                 // String to out\n",
+                "       5: invokevirtual #15                 // Method java/io/PrintStream.println:(Ljava/lang/String;)V\n",
+                "       8: getstatic     #21                 // Field java/lang/System.err:Ljava/io/PrintStream;\n",
+                "      11: ldc           #24                 // String to err\n",
+                "      13: invokevirtual #15                 // Method java/io/PrintStream.println:(Ljava/lang/String;)V\n",
+                "      16: return\n",
+            )
+        );
+    }
+
+    #[test]
+    fn marks_unknown_and_truncated_instructions() {
+        let class = parse_class_file(&fixtures_dir().join("PrintStreams.class")).unwrap();
+
+        assert_eq!(
+            disassemble(&[0x04, 0xcb, 0x04], &class.constant_pool),
+            "       0: iconst_1\n       1: <unknown opcode 0xcb>\n"
+        );
+        assert!(disassemble(&[0x10], &class.constant_pool).starts_with("       0: bipush <"));
+    }
+
+    #[test]
+    fn hostile_switch_counts_fail_without_allocating() {
+        let class = parse_class_file(&fixtures_dir().join("PrintStreams.class")).unwrap();
+
+        // 16 bytes claiming 2^32 cases
+        let tableswitch = [
+            &[TABLESWITCH, 0, 0, 0][..],
+            &0i32.to_be_bytes(),
+            &i32::MIN.to_be_bytes(),
+            &i32::MAX.to_be_bytes(),
+        ]
+        .concat();
+        let lookupswitch = [&[LOOKUPSWITCH, 0, 0, 0][..], &0i32.to_be_bytes(), &i32::MAX.to_be_bytes()].concat();
+
+        for (code, name) in [(tableswitch, "tableswitch"), (lookupswitch, "lookupswitch")] {
+            let err = SwitchTable::parse(&mut Cursor::new(&code), 0).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+            assert!(instruction_length(&code, 0).is_err());
+            assert!(disassemble(&code, &class.constant_pool).starts_with(&format!("       0: {name} <")));
+        }
+    }
+}
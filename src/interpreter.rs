@@ -1,131 +1,4173 @@
-use core::panic;
-use std::io::Cursor;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Cursor, Write},
+    rc::Rc,
+};
+
+use log::{trace, warn};
 
 use crate::{
     bytes::ByteParsable,
-    class::{AttributeKind, ClassFile, ConstantPool, ConstantPoolInfo},
+    class::{
+        AttributeKind, ClassFile, ConstantPool, ConstantPoolInfo, MethodAccessFlags, MethodInfo,
+        NotRunnable,
+    },
+    class_loader::ClassLoader,
+    descriptor::{parse_field_descriptor, parse_method_descriptor, FieldType, ReturnType},
+    heap::{Heap, HeapObject},
+    instruction::instruction_length,
+    verifier::{verify, VerifyError},
 };
 
 #[allow(non_snake_case, non_upper_case_globals, dead_code)]
 mod OpCodeType {
     pub const getstatic: u8 = 0xb2;
+    pub const putstatic: u8 = 0xb3;
+    pub const getfield: u8 = 0xb4;
+    pub const putfield: u8 = 0xb5;
     pub const invokevirtual: u8 = 0xb6;
+    pub const invokespecial: u8 = 0xb7;
+    pub const invokestatic: u8 = 0xb8;
+    pub const new: u8 = 0xbb;
+    pub const newarray: u8 = 0xbc;
+    pub const anewarray: u8 = 0xbd;
+    pub const arraylength: u8 = 0xbe;
+    pub const aconst_null: u8 = 0x01;
+    pub const iconst_m1: u8 = 0x02;
+    pub const iconst_5: u8 = 0x08;
+    pub const lconst_0: u8 = 0x09;
+    pub const lconst_1: u8 = 0x0a;
+    pub const fconst_0: u8 = 0x0b;
+    pub const fconst_2: u8 = 0x0d;
+    pub const dconst_0: u8 = 0x0e;
+    pub const dconst_1: u8 = 0x0f;
+    pub const bipush: u8 = 0x10;
+    pub const sipush: u8 = 0x11;
     pub const ldc: u8 = 0x12;
+    pub const ldc_w: u8 = 0x13;
+    pub const ldc2_w: u8 = 0x14;
+    pub const iload: u8 = 0x15;
+    pub const lload: u8 = 0x16;
+    pub const fload: u8 = 0x17;
+    pub const dload: u8 = 0x18;
+    pub const aload: u8 = 0x19;
+    pub const iload_0: u8 = 0x1a;
+    pub const lload_0: u8 = 0x1e;
+    pub const fload_0: u8 = 0x22;
+    pub const dload_0: u8 = 0x26;
+    pub const aload_0: u8 = 0x2a;
+    pub const aload_3: u8 = 0x2d;
+    pub const iaload: u8 = 0x2e;
+    pub const laload: u8 = 0x2f;
+    pub const faload: u8 = 0x30;
+    pub const daload: u8 = 0x31;
+    pub const aaload: u8 = 0x32;
+    pub const baload: u8 = 0x33;
+    pub const caload: u8 = 0x34;
+    pub const saload: u8 = 0x35;
+    pub const istore: u8 = 0x36;
+    pub const lstore: u8 = 0x37;
+    pub const fstore: u8 = 0x38;
+    pub const dstore: u8 = 0x39;
+    pub const astore: u8 = 0x3a;
+    pub const istore_0: u8 = 0x3b;
+    pub const lstore_0: u8 = 0x3f;
+    pub const fstore_0: u8 = 0x43;
+    pub const dstore_0: u8 = 0x47;
+    pub const astore_0: u8 = 0x4b;
+    pub const astore_3: u8 = 0x4e;
+    pub const iastore: u8 = 0x4f;
+    pub const lastore: u8 = 0x50;
+    pub const fastore: u8 = 0x51;
+    pub const dastore: u8 = 0x52;
+    pub const aastore: u8 = 0x53;
+    pub const bastore: u8 = 0x54;
+    pub const castore: u8 = 0x55;
+    pub const sastore: u8 = 0x56;
+    pub const iadd: u8 = 0x60;
+    pub const ladd: u8 = 0x61;
+    pub const dadd: u8 = 0x63;
+    pub const isub: u8 = 0x64;
+    pub const lsub: u8 = 0x65;
+    pub const dsub: u8 = 0x67;
+    pub const imul: u8 = 0x68;
+    pub const idiv: u8 = 0x6c;
+    pub const irem: u8 = 0x70;
+    pub const ineg: u8 = 0x74;
+    pub const ishl: u8 = 0x78;
+    pub const ishr: u8 = 0x7a;
+    pub const iushr: u8 = 0x7c;
+    pub const iand: u8 = 0x7e;
+    pub const ior: u8 = 0x80;
+    pub const ixor: u8 = 0x82;
+    pub const iinc: u8 = 0x84;
+    pub const ifeq: u8 = 0x99;
+    pub const ifne: u8 = 0x9a;
+    pub const iflt: u8 = 0x9b;
+    pub const ifge: u8 = 0x9c;
+    pub const ifgt: u8 = 0x9d;
+    pub const ifle: u8 = 0x9e;
+    pub const if_icmpeq: u8 = 0x9f;
+    pub const if_icmpne: u8 = 0xa0;
+    pub const if_icmplt: u8 = 0xa1;
+    pub const if_icmpge: u8 = 0xa2;
+    pub const if_icmpgt: u8 = 0xa3;
+    pub const if_icmple: u8 = 0xa4;
+    pub const goto: u8 = 0xa7;
+    pub const pop: u8 = 0x57;
+    pub const pop2: u8 = 0x58;
+    pub const dup: u8 = 0x59;
+    pub const dup2: u8 = 0x5c;
+    pub const ireturn: u8 = 0xac;
+    pub const lreturn: u8 = 0xad;
+    pub const freturn: u8 = 0xae;
+    pub const dreturn: u8 = 0xaf;
+    pub const areturn: u8 = 0xb0;
+    pub const r#return: u8 = 0xb1;
+    pub const athrow: u8 = 0xbf;
+    pub const wide: u8 = 0xc4;
 }
 
-#[allow(dead_code)]
-pub struct Instance {
-    class_name: String,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamKind {
+    Out,
+    Err,
 }
 
+/**
+ * A single value on the operand stack. Every value takes up exactly one entry, including the
+ * category 2 types (`long` and `double`) that the spec counts as two slots, so instructions
+ * that work on raw slots (ex. `dup2`) have to check `category()` to know how many entries to use.
+ */
+#[derive(Debug, Clone)]
 pub enum OperandStackEntry {
-    ClassInstance(Instance),
+    /** The `null` reference */
+    Null,
+    /** A reference to an object, as its handle in the interpreter's `Heap` */
+    Reference(usize),
+    PrintStream(StreamKind),
+    /** A `java/lang/Class` object, as the internal name of the class (ex. `com/example/Foo`, `[I`) */
+    Class(String),
     Int(i32),
     Float(f32),
+    Long(i64),
+    Double(f64),
     String(String),
 }
 
-pub fn run_main(class: &ClassFile) -> std::io::Result<()> {
-    let Some(main) = class.get_main_method() else {
-        panic!("No main method found in class {}", class.get_this_class_name())
-    };
+impl OperandStackEntry {
+    /**
+     * The computational type category of the value: 2 for `long`/`double` and 1 for everything else
+     */
+    pub fn category(&self) -> u8 {
+        match self {
+            OperandStackEntry::Long(_) | OperandStackEntry::Double(_) => 2,
+            _ => 1,
+        }
+    }
 
-    let code_attribute = main.get_code();
+    /**
+     * The value a field of the given type holds before it is assigned (`0`, `0.0` or `null`)
+     */
+    pub fn default_for(field_type: &FieldType) -> OperandStackEntry {
+        match field_type {
+            FieldType::Boolean
+            | FieldType::Byte
+            | FieldType::Char
+            | FieldType::Short
+            | FieldType::Int => OperandStackEntry::Int(0),
+            FieldType::Float => OperandStackEntry::Float(0.0),
+            FieldType::Long => OperandStackEntry::Long(0),
+            FieldType::Double => OperandStackEntry::Double(0.0),
+            FieldType::Object(_) | FieldType::Array(_) => OperandStackEntry::Null,
+        }
+    }
 
-    let AttributeKind::Code { max_stack: _, max_locals: _, code, exception_table: _, attributes: _ } = &code_attribute.attribute else {
-        panic!("Code attribute")
-    };
+    /**
+     * Whether the value can be stored in a field or local of the given type. `boolean`, `byte`,
+     * `char` and `short` are all represented as `Int` on the operand stack.
+     */
+    pub fn matches_type(&self, field_type: &FieldType) -> bool {
+        match field_type {
+            FieldType::Boolean
+            | FieldType::Byte
+            | FieldType::Char
+            | FieldType::Short
+            | FieldType::Int => matches!(self, OperandStackEntry::Int(_)),
+            FieldType::Float => matches!(self, OperandStackEntry::Float(_)),
+            FieldType::Long => matches!(self, OperandStackEntry::Long(_)),
+            FieldType::Double => matches!(self, OperandStackEntry::Double(_)),
+            FieldType::Object(_) | FieldType::Array(_) => matches!(
+                self,
+                OperandStackEntry::Null
+                    | OperandStackEntry::Reference(_)
+                    | OperandStackEntry::PrintStream(_)
+                    | OperandStackEntry::Class(_)
+                    | OperandStackEntry::String(_)
+            ),
+        }
+    }
+}
 
-    let mut byte_code = Cursor::new(code);
-    let len = byte_code.get_ref().len();
+/**
+ * The operand stack of a method invocation. The stack grows as needed, but in strict mode it
+ * refuses to hold more slots than the `max_stack` declared by the method's `Code` attribute.
+ *
+ * Every value is a single entry, including a `long` or `double`. The JVM counts those as two
+ * slots, so only the depth (which `max_stack` is checked against) counts them twice. The halves
+ * of a category 2 value can't be addressed on their own, which is why `pop`, `dup` and the
+ * other instructions that work on slots check the category of what they would split.
+ */
+#[derive(Debug)]
+pub struct OperandStack {
+    entries: Vec<OperandStackEntry>,
+    /** Current depth in slots, with category 2 values counting twice */
+    depth: usize,
+    max_stack: u16,
+    strict: bool,
+}
 
-    let mut operand_stack: Vec<OperandStackEntry> = Vec::new();
+impl OperandStack {
+    pub fn new(max_stack: u16, strict: bool) -> OperandStack {
+        OperandStack {
+            entries: Vec::new(),
+            depth: 0,
+            max_stack,
+            strict,
+        }
+    }
 
-    while (byte_code.position() as usize) < len - 1 {
-        let instruction = byte_code.parse_u1()?;
+    pub fn push(&mut self, entry: OperandStackEntry) -> Result<(), InterpreterError> {
+        let depth = self.depth + entry.category() as usize;
 
-        // println!("Read byte: 0x{:02x?}", instruction);
+        if self.strict && depth > self.max_stack as usize {
+            return Err(InterpreterError::StackOverflow {
+                max_stack: self.max_stack,
+            });
+        }
 
-        match instruction {
-            OpCodeType::getstatic => {
-                let field_ref_index = byte_code.parse_u2()?;
+        self.depth = depth;
+        self.entries.push(entry);
 
-                let field_ref = class.constant_pool.get_value(field_ref_index);
+        Ok(())
+    }
 
-                let ConstantPoolInfo::Fieldref { class_index, name_and_type_index } = field_ref else {
-                    panic!("Expected field ref to be of type Fieldref")
-                };
+    pub fn pop(&mut self) -> Result<OperandStackEntry, InterpreterError> {
+        let entry = self.entries.pop().ok_or(InterpreterError::StackUnderflow)?;
 
-                let _field_class = class
-                    .constant_pool
-                    .get_class_name_from_index(*class_index)
-                    .expect("Expected field to have valid class index");
+        self.depth -= entry.category() as usize;
 
-                let name_and_type = class
-                    .constant_pool
-                    .get_name_and_type(*name_and_type_index)
-                    .expect("Expected field to have valid name_and_type index");
-
-                operand_stack.push(OperandStackEntry::ClassInstance(Instance {
-                    class_name: name_and_type.1.clone(),
-                }));
-
-                // println!("Class = {field_class}");
-                // println!("Name and Type = {name_and_type:?}");
-            }
-            OpCodeType::ldc => {
-                let constant_index = byte_code.parse_u1()?;
-
-                match class.constant_pool.get_value(constant_index as u16) {
-                    ConstantPoolInfo::String { string_index } => {
-                        operand_stack.push(OperandStackEntry::String(
-                            class
-                                .constant_pool
-                                .get_utf8_from_index(*string_index)
-                                .expect("Expected string_index to be utf-8")
-                                .clone(),
-                        ))
-                    }
-                    _ => panic!("Unexpected constant type"),
+        Ok(entry)
+    }
+
+    pub fn pop_int(&mut self) -> Result<i32, InterpreterError> {
+        match self.pop()? {
+            OperandStackEntry::Int(value) => Ok(value),
+            found => Err(InterpreterError::OperandTypeMismatch {
+                expected: "int",
+                found,
+            }),
+        }
+    }
+
+    pub fn pop_long(&mut self) -> Result<i64, InterpreterError> {
+        match self.pop()? {
+            OperandStackEntry::Long(value) => Ok(value),
+            found => Err(InterpreterError::OperandTypeMismatch {
+                expected: "long",
+                found,
+            }),
+        }
+    }
+
+    pub fn pop_double(&mut self) -> Result<f64, InterpreterError> {
+        match self.pop()? {
+            OperandStackEntry::Double(value) => Ok(value),
+            found => Err(InterpreterError::OperandTypeMismatch {
+                expected: "double",
+                found,
+            }),
+        }
+    }
+
+    /**
+     * Pops the top `n` entries, returning them in the order they were pushed
+     */
+    pub fn pop_n(&mut self, n: usize) -> Result<Vec<OperandStackEntry>, InterpreterError> {
+        if n > self.entries.len() {
+            return Err(InterpreterError::StackUnderflow);
+        }
+
+        let entries = self.entries.split_off(self.entries.len() - n);
+
+        self.depth -= entries
+            .iter()
+            .map(|entry| entry.category() as usize)
+            .sum::<usize>();
+
+        Ok(entries)
+    }
+
+    /**
+     * Returns the entry `n` entries below the top of the stack (0 is the top)
+     */
+    pub fn peek(&self, n: usize) -> Result<&OperandStackEntry, InterpreterError> {
+        self.entries
+            .len()
+            .checked_sub(n + 1)
+            .map(|index| &self.entries[index])
+            .ok_or(InterpreterError::StackUnderflow)
+    }
+
+    /**
+     * Discards every entry, like the JVM does before jumping to an exception handler
+     */
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.depth = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/**
+ * The local variables of a method invocation, sized to the `max_locals` of its `Code` attribute.
+ * Like the operand stack, a `long` or `double` is held in a single entry, but it still occupies
+ * two indices so that the indices match the ones used by the bytecode.
+ */
+#[derive(Debug)]
+pub struct LocalVariables {
+    slots: Vec<Option<OperandStackEntry>>,
+}
+
+impl LocalVariables {
+    pub fn new(max_locals: u16) -> LocalVariables {
+        LocalVariables {
+            slots: vec![None; max_locals as usize],
+        }
+    }
+
+    /**
+     * Creates the locals of an invocation with its arguments (and receiver, for an instance
+     * method) stored from index 0
+     */
+    pub fn with_arguments(
+        max_locals: u16,
+        arguments: Vec<OperandStackEntry>,
+    ) -> Result<LocalVariables, InterpreterError> {
+        let mut locals = LocalVariables::new(max_locals);
+        let mut index = 0;
+
+        for argument in arguments {
+            let category = argument.category() as u16;
+
+            locals.store(index, argument)?;
+            index += category;
+        }
+
+        Ok(locals)
+    }
+
+    pub fn load(&self, index: u16) -> Result<&OperandStackEntry, InterpreterError> {
+        match self.slots.get(index as usize) {
+            Some(Some(entry)) => Ok(entry),
+            Some(None) => Err(InterpreterError::UninitializedLocal { index }),
+            None => Err(InterpreterError::LocalOutOfRange {
+                index,
+                max_locals: self.slots.len() as u16,
+            }),
+        }
+    }
+
+    pub fn store(&mut self, index: u16, entry: OperandStackEntry) -> Result<(), InterpreterError> {
+        let max_locals = self.slots.len() as u16;
+        let category = entry.category() as u16;
+
+        if index as usize + category as usize > self.slots.len() {
+            return Err(InterpreterError::LocalOutOfRange { index, max_locals });
+        }
+
+        // The second index of a category 2 value can't be read on its own
+        if category == 2 {
+            self.slots[index as usize + 1] = None;
+        }
+
+        // Overwriting the second index of a category 2 value invalidates the whole value
+        if let Some(Some(previous)) = (index as usize).checked_sub(1).map(|previous| &self.slots[previous]) {
+            if previous.category() == 2 {
+                self.slots[index as usize - 1] = None;
+            }
+        }
+
+        self.slots[index as usize] = Some(entry);
+
+        Ok(())
+    }
+
+    /**
+     * Adds `increment` to the `int` at `index` (`iinc`), wrapping around on overflow
+     */
+    pub fn increment(&mut self, index: u16, increment: i32) -> Result<(), InterpreterError> {
+        let value = match self.load(index)? {
+            OperandStackEntry::Int(value) => *value,
+            found => {
+                return Err(InterpreterError::OperandTypeMismatch {
+                    expected: "int",
+                    found: found.clone(),
+                })
+            }
+        };
+
+        self.store(index, OperandStackEntry::Int(value.wrapping_add(increment)))
+    }
+}
+
+/**
+ * The state of a single method invocation: its position in the code, its operand stack and its
+ * local variables. The frame keeps its class alive, so it does not borrow from the interpreter.
+ */
+#[derive(Debug)]
+pub struct Frame {
+    class: Rc<ClassFile>,
+    method_index: usize,
+    /** Index of the method's `Code` attribute, so the code can be reached without a name lookup */
+    code_index: usize,
+    /** Position of the next byte to read from the code */
+    pub pc: u64,
+    /** Start of the instruction being executed, which is what stack traces point at */
+    instruction_pc: u64,
+    pub operand_stack: OperandStack,
+    pub locals: LocalVariables,
+}
+
+impl Frame {
+    /**
+     * Creates the frame of an invocation of `method`, which has to be one of `class`'s methods
+     * and have a `Code` attribute
+     */
+    pub fn new(
+        class: Rc<ClassFile>,
+        method: &MethodInfo,
+        arguments: Vec<OperandStackEntry>,
+        strict_stack: bool,
+    ) -> Result<Frame, InterpreterError> {
+        let method_index = class
+            .methods
+            .iter()
+            .position(|candidate| std::ptr::eq(candidate, method))
+            .expect("Expected method to belong to the class of the frame");
+
+        let code_index = method
+            .attributes
+            .iter()
+            .position(|attribute| attribute.attribute_name == "Code")
+            .expect("Expected MethodInfo to have 'Code' attribute");
+
+        let AttributeKind::Code { max_stack, max_locals, code, .. } = &method.attributes[code_index].attribute else {
+            panic!("Expected 'Code' attribute to be parsed as code")
+        };
+
+        if code.is_empty() {
+            return Err(InterpreterError::EmptyCode {
+                method: format!("{}.{}{}", class.get_this_class_name(), method.name, method.descriptor),
+            });
+        }
+
+        // `boolean` parameters are narrowed like stores into `boolean` fields. The receiver of an
+        // instance method comes before the parameters of its descriptor.
+        let mut arguments = arguments;
+
+        if method.descriptor.contains('Z') {
+            let params = parse_method_descriptor(&method.descriptor)
+                .expect("Expected method to have a valid descriptor")
+                .params;
+            let receivers = arguments.len().saturating_sub(params.len());
+
+            for (argument, param) in arguments.iter_mut().skip(receivers).zip(&params) {
+                *argument = narrow_boolean(param, argument.clone());
+            }
+        }
+
+        Ok(Frame {
+            operand_stack: OperandStack::new(*max_stack, strict_stack),
+            locals: LocalVariables::with_arguments(*max_locals, arguments)?,
+            class,
+            method_index,
+            code_index,
+            pc: 0,
+            instruction_pc: 0,
+        })
+    }
+
+    pub fn class(&self) -> &Rc<ClassFile> {
+        &self.class
+    }
+
+    pub fn method(&self) -> &MethodInfo {
+        &self.class.methods[self.method_index]
+    }
+
+    pub fn code(&self) -> &[u8] {
+        match &self.method().attributes[self.code_index].attribute {
+            AttributeKind::Code { code, .. } => code.code_bytes(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn push(&mut self, entry: OperandStackEntry) -> Result<(), InterpreterError> {
+        self.operand_stack.push(entry)
+    }
+
+    pub fn pop(&mut self) -> Result<OperandStackEntry, InterpreterError> {
+        self.operand_stack.pop()
+    }
+
+    pub fn pop_int(&mut self) -> Result<i32, InterpreterError> {
+        self.operand_stack.pop_int()
+    }
+
+    /**
+     * Reads the operands of the current instruction from the code at `pc` and moves past them
+     */
+    fn read<T>(&mut self, parse: impl FnOnce(&mut Cursor<&[u8]>) -> io::Result<T>) -> io::Result<T> {
+        let (value, pc) = {
+            let mut cursor = Cursor::new(self.code());
+            cursor.set_position(self.pc);
+
+            (parse(&mut cursor)?, cursor.position())
+        };
+
+        self.pc = pc;
+
+        Ok(value)
+    }
+
+    fn read_u1(&mut self) -> io::Result<u8> {
+        self.read(|cursor| cursor.parse_u1())
+    }
+
+    fn read_u2(&mut self) -> io::Result<u16> {
+        self.read(|cursor| cursor.parse_u2())
+    }
+
+    fn read_i1(&mut self) -> io::Result<i8> {
+        self.read(|cursor| cursor.parse_i1())
+    }
+
+    fn read_i2(&mut self) -> io::Result<i16> {
+        self.read(|cursor| cursor.parse_i2())
+    }
+}
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    Io(io::Error),
+    /** A push would exceed the `max_stack` of the current method (only checked in strict mode) */
+    StackOverflow { max_stack: u16 },
+    /** A value was popped from an empty operand stack */
+    StackUnderflow,
+    /** An instruction popped a value of the wrong type (ex. `ladd` on an `int`) */
+    OperandTypeMismatch {
+        expected: &'static str,
+        found: OperandStackEntry,
+    },
+    /** A local variable index is at or above the method's `max_locals` */
+    LocalOutOfRange { index: u16, max_locals: u16 },
+    /** A local variable was loaded before anything was stored in it */
+    UninitializedLocal { index: u16 },
+    NotRunnable(NotRunnable),
+    /** The interpreter does not implement this opcode yet */
+    UnimplementedOpcode { opcode: u8, pc: u64 },
+    /** A field access moved a value that does not match the field's descriptor */
+    FieldTypeMismatch { field: String, descriptor: String },
+    /** A return instruction does not match the method's return type (only checked with `strict_returns`) */
+    ReturnTypeMismatch {
+        method: String,
+        return_type: ReturnType,
+        opcode: u8,
+    },
+    /** A class failed `verifier::verify` (only checked when `InterpreterConfig::verify` is set) */
+    VerifyFailed { class_name: String, errors: Vec<VerifyError> },
+    /** The interpreter loop was asked to run without a frame to execute */
+    EmptyCallStack,
+    /** A method's `Code` attribute has no instructions, which the spec does not allow */
+    EmptyCode { method: String },
+    /** Execution reached the end of a method's code without a return instruction */
+    FallsOffEnd { method: String },
+    /** An array was indexed outside of `0..length` (`ArrayIndexOutOfBoundsException` in Java) */
+    ArrayIndexOutOfBounds { index: i32, length: usize },
+    /** An array was created with a negative length (`NegativeArraySizeException` in Java) */
+    NegativeArraySize { length: i32 },
+    /** An exception was thrown and no frame of the run had a handler for it */
+    UncaughtException { class_name: String },
+    /** A static field's `ConstantValue` does not point at a constant a field can hold */
+    InvalidConstantValue { field: String, index: u16 },
+    /** An `ldc`, `ldc_w` or `ldc2_w` points at a constant that instruction cannot load */
+    InvalidLoadConstant { index: u16, opcode: u8 },
+    /** An invoked method is not declared by its class (`NoSuchMethodError` in Java) */
+    NoSuchMethod { method: String },
+    /** An accessed field is not declared by its class or a superclass (`NoSuchFieldError` in Java) */
+    NoSuchField { field: String },
+    /** A `native` method was invoked without a registered implementation (`UnsatisfiedLinkError` in Java) */
+    UnsatisfiedLink { method: String },
+    /**
+     * A static method was invoked as an instance method or the other way around
+     * (`IncompatibleClassChangeError` in Java)
+     */
+    IncompatibleClassChange { method: String, expected_static: bool },
+    /** A registered native returned a value for a `void` method, or nothing for a non-`void` one */
+    NativeReturnMismatch { method: String },
+    /** A method of a JDK class the interpreter models itself (ex. `java/lang/String`) that it does not implement */
+    UnsupportedIntrinsic { method: String },
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::Io(err) => write!(f, "{err}"),
+            InterpreterError::StackOverflow { max_stack } => {
+                write!(f, "Operand stack overflow (max_stack is {max_stack})")
+            }
+            InterpreterError::StackUnderflow => write!(f, "Operand stack underflow"),
+            InterpreterError::OperandTypeMismatch { expected, found } => {
+                write!(f, "Expected a {expected} on the operand stack but found {found:?}")
+            }
+            InterpreterError::LocalOutOfRange { index, max_locals } => write!(
+                f,
+                "Local variable {index} is out of range (max_locals is {max_locals})"
+            ),
+            InterpreterError::UninitializedLocal { index } => {
+                write!(f, "Local variable {index} was read before it was assigned")
+            }
+            InterpreterError::NotRunnable(reason) => write!(f, "{reason}"),
+            InterpreterError::UnimplementedOpcode { opcode, pc } => {
+                write!(f, "Instruction 0x{opcode:02x} at {pc} is not yet implemented")
+            }
+            InterpreterError::FieldTypeMismatch { field, descriptor } => {
+                write!(f, "Value does not match the type {descriptor} of field {field}")
+            }
+            InterpreterError::ReturnTypeMismatch {
+                method,
+                return_type,
+                opcode,
+            } => write!(
+                f,
+                "Method {method} returns {return_type} but uses the return instruction 0x{opcode:02x}"
+            ),
+            InterpreterError::VerifyFailed { class_name, errors } => {
+                write!(f, "Class {class_name} failed verification:")?;
+
+                for error in errors {
+                    write!(f, "\n    {error}")?;
                 }
+
+                Ok(())
             }
-            OpCodeType::invokevirtual => {
-                let method_index = byte_code.parse_u2()?;
+            InterpreterError::EmptyCallStack => write!(f, "There is no frame on the call stack to execute"),
+            InterpreterError::EmptyCode { method } => write!(f, "Method {method} has no instructions"),
+            InterpreterError::FallsOffEnd { method } => {
+                write!(f, "Method {method} ran past the end of its code without returning")
+            }
+            InterpreterError::ArrayIndexOutOfBounds { index, length } => {
+                write!(f, "Index {index} out of bounds for length {length}")
+            }
+            InterpreterError::NegativeArraySize { length } => {
+                write!(f, "Cannot create an array of negative length {length}")
+            }
+            InterpreterError::UncaughtException { class_name } => {
+                write!(f, "{}", class_name.replace('/', "."))
+            }
+            InterpreterError::InvalidConstantValue { field, index } => {
+                write!(f, "ConstantValue #{index} of field {field} is not an int, float, long, double or String constant")
+            }
+            InterpreterError::InvalidLoadConstant { index, opcode } => {
+                write!(f, "Constant #{index} cannot be loaded by the instruction 0x{opcode:02x}")
+            }
+            InterpreterError::NoSuchMethod { method } => write!(f, "Could not find method {method}"),
+            InterpreterError::NoSuchField { field } => write!(f, "Could not find field {field}"),
+            InterpreterError::UnsatisfiedLink { method } => {
+                write!(f, "No native implementation registered for {method}")
+            }
+            InterpreterError::IncompatibleClassChange {
+                method,
+                expected_static,
+            } => {
+                let kind = if *expected_static { "static" } else { "an instance method" };
 
-                let method_ref = class.constant_pool.get_value(method_index);
+                write!(f, "Expected {method} to be {kind}")
+            }
+            InterpreterError::NativeReturnMismatch { method } => {
+                write!(f, "Native {method} returned a value not matching its descriptor")
+            }
+            InterpreterError::UnsupportedIntrinsic { method } => {
+                write!(f, "{method} is not supported by the interpreter")
+            }
+        }
+    }
+}
 
-                let ConstantPoolInfo::Methodref { class_index, name_and_type_index } = method_ref else {
-                        panic!("Expected field ref to be of type Fieldref")
-                    };
+impl std::error::Error for InterpreterError {}
 
-                let field_class = class
-                    .constant_pool
-                    .get_class_name_from_index(*class_index)
-                    .expect("Expected field to have valid class index");
+impl From<NotRunnable> for InterpreterError {
+    fn from(reason: NotRunnable) -> Self {
+        InterpreterError::NotRunnable(reason)
+    }
+}
 
-                let name_and_type = class
-                    .constant_pool
-                    .get_name_and_type(*name_and_type_index)
-                    .expect("Expected field to have valid name_and_type index");
-
-                // println!("Class = {field_class}");
-                // println!("Name and Type = {name_and_type:?}");
-                
-                if field_class == "java/io/PrintStream"
-                && name_and_type.0 == "println"
-                && name_and_type.1 == "(Ljava/lang/String;)V"
-                {
-                    let OperandStackEntry::String(string) = operand_stack.pop().unwrap() else {panic!("Expected operand stack to contain string")};
-                    let _instance = operand_stack.pop();
+impl From<io::Error> for InterpreterError {
+    fn from(err: io::Error) -> Self {
+        InterpreterError::Io(err)
+    }
+}
+
+/**
+ * One line of a Java style stack trace (ex. `com.example.Foo.bar(Foo.java:42)`)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTraceElement {
+    pub class_name: String,
+    pub method_name: String,
+    pub source_file: Option<String>,
+    pub line_number: Option<u16>,
+}
+
+impl fmt::Display for StackTraceElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.class_name.replace('/', "."), self.method_name)?;
+
+        match (&self.source_file, self.line_number) {
+            (Some(source_file), Some(line_number)) => write!(f, "({source_file}:{line_number})"),
+            _ => write!(f, "(Unknown Source)"),
+        }
+    }
+}
+
+/**
+ * What the interpreter loop does after executing one instruction of the top frame
+ */
+enum Step {
+    /** Continue with the next instruction of the same frame */
+    Next,
+    /** Suspend the frame and start running the invoked method's frame */
+    Invoke(Frame),
+    /** Pop the frame and hand its return value (if any) to the caller's operand stack */
+    Return(Option<OperandStackEntry>),
+    /** Unwind to the closest handler of the exception with this heap handle */
+    Throw(usize),
+}
+
+/**
+ * A Rust implementation of a `native` method. It receives the method arguments in declaration
+ * order and returns the value to push onto the caller's operand stack (`None` for `void`).
+ */
+pub type NativeMethod = Box<dyn Fn(&[OperandStackEntry]) -> Option<OperandStackEntry>>;
+
+/**
+ * A registered native, shared between the registry and the call sites resolved to it
+ */
+type SharedNativeMethod = Rc<dyn Fn(&[OperandStackEntry]) -> Option<OperandStackEntry>>;
+
+/**
+ * What a method ref constant resolved to
+ */
+#[derive(Clone)]
+enum MethodTarget {
+    Native(SharedNativeMethod),
+    Interpreted {
+        class: Rc<ClassFile>,
+        method_index: usize,
+    },
+}
+
+/**
+ * A method ref constant after its first invocation (the JVM's "resolved constant pool" entry),
+ * with the parts of the descriptor an invocation needs
+ */
+#[derive(Clone)]
+struct ResolvedMethod {
+    target: MethodTarget,
+    /** Number of operand stack entries the invocation pops, including the receiver of an instance method */
+    parameter_count: usize,
+    returns_value: bool,
+}
+
+/**
+ * What to do when the interpreter reaches an opcode it does not implement
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UnimplementedPolicy {
+    /** Stop with `InterpreterError::UnimplementedOpcode` */
+    #[default]
+    Halt,
+    /**
+     * Log a warning and skip over the instruction and its operands. The skipped instruction's
+     * stack effect is not applied, so the operand stack can be out of sync afterwards and later
+     * instructions may fail or misbehave. Only meant for surveying what a program needs.
+     */
+    WarnAndSkip,
+}
+
+#[derive(Debug, Clone)]
+pub struct InterpreterConfig {
+    /**
+     * Count how many times each opcode is executed (see `Interpreter::opcode_counts`)
+     */
+    pub record_opcode_counts: bool,
+    /**
+     * Fail with `InterpreterError::StackOverflow` when a method pushes more than its declared `max_stack`
+     */
+    pub strict_stack: bool,
+    /**
+     * Fail with `InterpreterError::ReturnTypeMismatch` when a method returns with an instruction
+     * that does not match the return type in its descriptor (ex. `areturn` in a `()I` method)
+     */
+    pub strict_returns: bool,
+    pub on_unimplemented: UnimplementedPolicy,
+    /**
+     * Run `verifier::verify` on every class as it is initialized
+     */
+    pub verify: bool,
+    /**
+     * Written after the text of every `println` (Java's `line.separator`). Defaults to `\n`.
+     */
+    pub line_separator: String,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        InterpreterConfig {
+            record_opcode_counts: false,
+            strict_stack: false,
+            strict_returns: false,
+            on_unimplemented: UnimplementedPolicy::default(),
+            verify: false,
+            line_separator: String::from("\n"),
+        }
+    }
+}
+
+pub struct Interpreter {
+    config: InterpreterConfig,
+    class_loader: ClassLoader,
+    /**
+     * Native method implementations keyed by (class name, method name, descriptor)
+     */
+    natives: HashMap<(String, String, String), SharedNativeMethod>,
+    /**
+     * Method refs that have already been invoked, keyed by the class whose constant pool holds
+     * them and their constant index. Classes are never unloaded (the class loader keeps them
+     * alive), so their address identifies them for the lifetime of the interpreter.
+     */
+    resolved_methods: HashMap<(*const ClassFile, u16), ResolvedMethod>,
+    /**
+     * Values of static fields, keyed by the declaring class and then by the field name.
+     * A class gets an entry here as soon as its initialization starts.
+     */
+    static_fields: HashMap<String, HashMap<String, OperandStackEntry>>,
+    opcode_counts: HashMap<u8, u64>,
+    /**
+     * The frames that were executing when the last run failed, innermost last, kept so the
+     * trace of the failure can still be read
+     */
+    failed_frames: Vec<Frame>,
+    /** Every object created by `new` (and the receiver of an instance `main`) */
+    heap: Heap,
+    /** Where `System.out` writes to (the process stdout by default) */
+    stdout: Box<dyn Write>,
+    /** Where `System.err` writes to (the process stderr by default) */
+    stderr: Box<dyn Write>,
+}
+
+impl Interpreter {
+    pub fn new(class_loader: ClassLoader) -> Interpreter {
+        Interpreter::with_config(class_loader, InterpreterConfig::default())
+    }
+
+    pub fn with_config(class_loader: ClassLoader, config: InterpreterConfig) -> Interpreter {
+        let mut natives: HashMap<_, SharedNativeMethod> = HashMap::new();
+
+        // Every constructor ends up calling this, but java/lang/Object is not on the class path
+        natives.insert(
+            (
+                String::from("java/lang/Object"),
+                String::from("<init>"),
+                String::from("()V"),
+            ),
+            Rc::new(|_| None),
+        );
+
+        Interpreter {
+            config,
+            class_loader,
+            natives,
+            resolved_methods: HashMap::new(),
+            static_fields: HashMap::new(),
+            opcode_counts: HashMap::new(),
+            failed_frames: Vec::new(),
+            heap: Heap::new(),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+        }
+    }
+
+    /**
+     * Redirects `System.out`. Text is written as UTF-8.
+     */
+    pub fn set_stdout(&mut self, sink: Box<dyn Write>) {
+        self.stdout = sink;
+    }
+
+    /**
+     * Redirects `System.err`. Text is written as UTF-8.
+     */
+    pub fn set_stderr(&mut self, sink: Box<dyn Write>) {
+        self.stderr = sink;
+    }
+
+    /**
+     * The objects created so far, for looking up what an `OperandStackEntry::Reference` refers to
+     */
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /**
+     * How many times each opcode has been executed so far. Always empty unless
+     * `InterpreterConfig::record_opcode_counts` is set.
+     */
+    pub fn opcode_counts(&self) -> &HashMap<u8, u64> {
+        &self.opcode_counts
+    }
+
+    /**
+     * Registers a Rust implementation for a `native` method. Natives are looked up before the
+     * declaring class is loaded, so they can also stand in for JDK methods that are not on
+     * the class path (ex. `java/lang/System.currentTimeMillis`).
+     */
+    pub fn register_native(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        implementation: NativeMethod,
+    ) {
+        self.natives.insert(
+            (class.to_string(), name.to_string(), descriptor.to_string()),
+            Rc::from(implementation),
+        );
+
+        // A call site may already have resolved to the method this native replaces
+        self.resolved_methods.clear();
+    }
+
+    /**
+     * Renders the methods that were executing when `run_main` failed, innermost first
+     */
+    pub fn current_stack_trace(&self) -> Vec<StackTraceElement> {
+        self.failed_frames
+            .iter()
+            .rev()
+            .map(|frame| StackTraceElement {
+                class_name: frame.class.get_this_class_name().clone(),
+                method_name: frame.method().name.clone(),
+                source_file: frame.class.source_file().cloned(),
+                line_number: frame.method().line_number_at(frame.instruction_pc as u16),
+            })
+            .collect()
+    }
+
+    pub fn run_main(&mut self, class_name: &str) -> Result<(), InterpreterError> {
+        self.failed_frames.clear();
+
+        let class = self.initialize_class(class_name)?;
+
+        let main = class.entry_point()?;
+
+        // An instance main (JEP 445) needs a receiver, so the class is constructed first
+        let mut arguments = if main.is_static() {
+            Vec::new()
+        } else {
+            let init = class
+                .get_method("<init>", "()V")
+                .expect("Expected entry_point to check for a no-argument constructor");
+
+            let receiver = OperandStackEntry::Reference(self.allocate_instance(&class));
+
+            self.execute(&class, init, vec![receiver.clone()])?;
+
+            vec![receiver]
+        };
+
+        // No command line arguments are passed, so `main(String[])` gets an empty array
+        if main.descriptor == "([Ljava/lang/String;)V" {
+            let args = HeapObject::new_array(FieldType::Object(String::from("java/lang/String")), 0);
+
+            arguments.push(OperandStackEntry::Reference(self.heap.allocate(args)));
+        }
+
+        self.execute(&class, main, arguments)?;
+
+        Ok(())
+    }
+
+    /**
+     * Loads a class through the class loader and runs its `<clinit>` the first time it is used
+     */
+    fn initialize_class(&mut self, class_name: &str) -> Result<Rc<ClassFile>, InterpreterError> {
+        let class = self.class_loader.load_class(class_name)?;
+
+        if self.static_fields.contains_key(class_name) {
+            return Ok(class);
+        }
+
+        if self.config.verify {
+            verify(&class).map_err(|errors| InterpreterError::VerifyFailed {
+                class_name: class_name.to_string(),
+                errors,
+            })?;
+        }
+
+        // Static fields with a ConstantValue attribute are assigned before <clinit> runs
+        let mut statics = HashMap::new();
+
+        for field in class.fields.iter().filter(|field| field.is_static()) {
+            let Some(constant_value_index) = field.constant_value_index() else {
+                continue;
+            };
+
+            let invalid = || InterpreterError::InvalidConstantValue {
+                field: format!("{class_name}.{}", field.name),
+                index: constant_value_index,
+            };
+
+            let value = match class.constant_pool.try_get_value(constant_value_index) {
+                Some(ConstantPoolInfo::Integer { value }) => OperandStackEntry::Int(*value),
+                Some(ConstantPoolInfo::Float { value }) => OperandStackEntry::Float(*value),
+                Some(ConstantPoolInfo::Long { value }) => OperandStackEntry::Long(*value),
+                Some(ConstantPoolInfo::Double { value }) => OperandStackEntry::Double(*value),
+                Some(ConstantPoolInfo::String { string_index }) => OperandStackEntry::String(
+                    class
+                        .constant_pool
+                        .get_utf8_from_index(*string_index)
+                        .map_err(|_| invalid())?
+                        .clone(),
+                ),
+                _ => return Err(invalid()),
+            };
 
-                    println!("{string}")
+            statics.insert(field.name.clone(), value);
+        }
+
+        // Marked before running <clinit> so that a class referring to its own statics
+        // (or a cycle between classes) does not initialize it twice
+        self.static_fields.insert(class_name.to_string(), statics);
+
+        if let Some(clinit) = class.get_method("<clinit>", "()V") {
+            self.execute(&class, clinit, Vec::new())?;
+        }
+
+        Ok(class)
+    }
+
+    /**
+     * Creates an object of `class` with every instance field it declares or inherits set to the
+     * default value of its type, which is what `<init>` sees before it assigns anything.
+     * Superclasses that can't be loaded are skipped with a warning. A field hidden by a subclass
+     * field of the same name only exists once. Returns the handle of the new object.
+     */
+    fn allocate_instance(&mut self, class: &Rc<ClassFile>) -> usize {
+        let mut hierarchy = vec![class.clone()];
+
+        while let Some(super_class) = hierarchy.last().and_then(|class| class.super_class_ref()) {
+            if super_class.name == "java/lang/Object" {
+                break;
+            }
+
+            match self.class_loader.load_class(&super_class.name) {
+                Ok(super_class) => hierarchy.push(super_class),
+                Err(err) => {
+                    warn!(
+                        "Could not load {} while allocating {}: {err}",
+                        super_class.name,
+                        class.get_this_class_name()
+                    );
+                    break;
                 }
             }
-            _ => {
-                todo!("Instruction 0x{instruction:02x?} is not yet implemented")
+        }
+
+        let mut fields = HashMap::new();
+
+        // Superclass fields first, so the subclass wins when a field is hidden
+        for class in hierarchy.iter().rev() {
+            for field in class.fields.iter().filter(|field| !field.is_static()) {
+                let field_type = parse_field_descriptor(&field.descriptor)
+                    .expect("Expected field to have a valid descriptor");
+
+                fields.insert(field.name.clone(), OperandStackEntry::default_for(&field_type));
             }
         }
+
+        self.heap.allocate(HeapObject::Object {
+            class_name: class.get_this_class_name().clone(),
+            fields,
+        })
+    }
+
+    /**
+     * Creates an array of `length` elements of `element_type`, all set to the default value of
+     * the type. Returns the handle of the new array.
+     */
+    fn allocate_array(&mut self, element_type: FieldType, length: i32) -> Result<usize, InterpreterError> {
+        if length < 0 {
+            return Err(InterpreterError::NegativeArraySize { length });
+        }
+
+        Ok(self.heap.allocate(HeapObject::new_array(element_type, length as usize)))
+    }
+
+    /**
+     * The instance fields of the object `handle` refers to. Fails if the handle is an array or
+     * not a live heap object, since neither has fields for `getfield`/`putfield` to access
+     */
+    fn object_fields(&self, handle: usize) -> Result<&HashMap<String, OperandStackEntry>, InterpreterError> {
+        match self.heap.get(handle) {
+            Some(HeapObject::Object { fields, .. }) => Ok(fields),
+            _ => Err(InterpreterError::OperandTypeMismatch {
+                expected: "object reference",
+                found: OperandStackEntry::Reference(handle),
+            }),
+        }
     }
 
-    Ok(())
+    /**
+     * The elements of the array `handle` refers to
+     */
+    fn array_elements(&self, handle: usize) -> &[OperandStackEntry] {
+        match self.heap.get(handle) {
+            Some(HeapObject::Array { elements, .. }) => elements,
+            _ => panic!("Expected reference to be a valid array handle"),
+        }
+    }
+
+    /**
+     * `System.arraycopy(src, srcPos, dest, destPos, length)`. The elements are copied as if
+     * through a temporary array, so overlapping ranges of the same array are handled like the
+     * JDK does. Returns the exception to throw for a null array, an out of bounds range or
+     * arrays whose element types don't match.
+     */
+    fn array_copy(&mut self, arguments: &[OperandStackEntry]) -> Result<Option<usize>, InterpreterError> {
+        let [source, source_position, destination, destination_position, length] = arguments else {
+            return Err(InterpreterError::StackUnderflow);
+        };
+
+        let (source, destination) = match (source, destination) {
+            (OperandStackEntry::Reference(source), OperandStackEntry::Reference(destination)) => {
+                (*source, *destination)
+            }
+            (OperandStackEntry::Null, _) | (_, OperandStackEntry::Null) => {
+                return Ok(Some(self.allocate_builtin_exception("java/lang/NullPointerException")))
+            }
+            (OperandStackEntry::Reference(_), found) | (found, _) => {
+                return Err(InterpreterError::OperandTypeMismatch {
+                    expected: "array reference",
+                    found: found.clone(),
+                })
+            }
+        };
+
+        let int = |entry: &OperandStackEntry| match entry {
+            OperandStackEntry::Int(value) => Ok(*value),
+            found => Err(InterpreterError::OperandTypeMismatch {
+                expected: "int",
+                found: found.clone(),
+            }),
+        };
+
+        let (source_position, destination_position, length) =
+            (int(source_position)?, int(destination_position)?, int(length)?);
+
+        let (
+            Some(HeapObject::Array { element_type: source_type, elements: source_elements }),
+            Some(HeapObject::Array { element_type: destination_type, .. }),
+        ) = (self.heap.get(source), self.heap.get(destination))
+        else {
+            return Ok(Some(self.allocate_builtin_exception("java/lang/ArrayStoreException")));
+        };
+
+        // Primitive arrays can only be copied into arrays of the same type. Reference arrays
+        // are not checked element by element.
+        let is_reference = |field_type: &FieldType| matches!(field_type, FieldType::Object(_) | FieldType::Array(_));
+
+        if source_type != destination_type && !(is_reference(source_type) && is_reference(destination_type)) {
+            return Ok(Some(self.allocate_builtin_exception("java/lang/ArrayStoreException")));
+        }
+
+        let range = |position: i32, array_length: usize| {
+            let start = usize::try_from(position).ok()?;
+            let end = start.checked_add(usize::try_from(length).ok()?)?;
+
+            (end <= array_length).then_some(start..end)
+        };
+
+        let destination_length = self.array_elements(destination).len();
+
+        let (Some(source_range), Some(destination_range)) = (
+            range(source_position, source_elements.len()),
+            range(destination_position, destination_length),
+        ) else {
+            return Ok(Some(self.allocate_builtin_exception("java/lang/ArrayIndexOutOfBoundsException")));
+        };
+
+        let copied = source_elements[source_range].to_vec();
+
+        let Some(HeapObject::Array { elements, .. }) = self.heap.get_mut(destination) else {
+            panic!("Expected reference to be a valid array handle")
+        };
+
+        elements[destination_range].clone_from_slice(&copied);
+
+        Ok(None)
+    }
+
+    /**
+     * Resolves the method a method ref constant of `class` refers to, initializing the class
+     * that declares it, and remembers the result so later invocations through the same constant
+     * skip the lookup by name. Nothing is remembered when resolution fails.
+     */
+    fn resolve_method(
+        &mut self,
+        class: &Rc<ClassFile>,
+        method_ref_index: u16,
+        is_static: bool,
+    ) -> Result<ResolvedMethod, InterpreterError> {
+        let key = (Rc::as_ptr(class), method_ref_index);
+
+        if let Some(resolved) = self.resolved_methods.get(&key) {
+            return Ok(resolved.clone());
+        }
+
+        let (method_class, name, descriptor) = class
+            .constant_pool
+            .get_method_ref(method_ref_index)
+            .expect("Expected method ref to be a valid Methodref");
+
+        let method_descriptor = parse_method_descriptor(&descriptor)
+            .expect("Expected method to have a valid descriptor");
+
+        let method = || format!("{method_class}.{name}{descriptor}");
+
+        // The method can be declared by a superclass of the one the method ref names (JVMS
+        // §5.4.3.3), and it is the declaring class that is remembered and initialized
+        let mut current = Some(method_class.clone());
+
+        let target = loop {
+            let Some(class_name) = current else {
+                return Err(InterpreterError::NoSuchMethod { method: method() });
+            };
+
+            let natives_key = (class_name, name.clone(), descriptor.clone());
+
+            if let Some(native) = self.natives.get(&natives_key) {
+                break MethodTarget::Native(native.clone());
+            }
+
+            let (class_name, ..) = natives_key;
+
+            // java/lang/Object is not on the class path, so only its natives can be found
+            if class_name == "java/lang/Object" {
+                return Err(InterpreterError::NoSuchMethod { method: method() });
+            }
+
+            let candidate = self.class_loader.load_class(&class_name)?;
+
+            let Some(method_index) = candidate.get_method_index(&name, &descriptor) else {
+                current = candidate.get_super_class_name().cloned();
+                continue;
+            };
+
+            let target = &candidate.methods[method_index];
+
+            if target.access_flags.contains(&MethodAccessFlags::Native) {
+                return Err(InterpreterError::UnsatisfiedLink { method: method() });
+            }
+
+            if target.is_static() != is_static {
+                return Err(InterpreterError::IncompatibleClassChange {
+                    method: method(),
+                    expected_static: is_static,
+                });
+            }
+
+            break MethodTarget::Interpreted {
+                class: self.initialize_class(&class_name)?,
+                method_index,
+            };
+        };
+
+        let resolved = ResolvedMethod {
+            target,
+            parameter_count: method_descriptor.params.len() + usize::from(!is_static),
+            returns_value: matches!(method_descriptor.return_type, ReturnType::Value(_)),
+        };
+
+        self.resolved_methods.insert(key, resolved.clone());
+
+        Ok(resolved)
+    }
+
+    /**
+     * Finds the method an `invokevirtual` runs for a receiver of class `receiver_class`, which
+     * is the first declaration of `name` and `descriptor` found walking up from the receiver's
+     * class. Natives registered for a class in the chain take precedence over its bytecode.
+     */
+    fn select_virtual_method(
+        &mut self,
+        receiver_class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<MethodTarget, InterpreterError> {
+        let mut current = Some(receiver_class.to_string());
+
+        while let Some(class_name) = current {
+            let key = (class_name, name.to_string(), descriptor.to_string());
+
+            if let Some(native) = self.natives.get(&key) {
+                return Ok(MethodTarget::Native(native.clone()));
+            }
+
+            // java/lang/Object is not on the class path, so only its natives can be found
+            if key.0 == "java/lang/Object" {
+                break;
+            }
+
+            let class = self.initialize_class(&key.0)?;
+
+            if let Some(method_index) = class.get_method_index(name, descriptor) {
+                let method = &class.methods[method_index];
+
+                if !method.is_static() && !method.is_abstract() {
+                    return Ok(MethodTarget::Interpreted { class, method_index });
+                }
+            }
+
+            current = class.get_super_class_name().cloned();
+        }
+
+        Err(InterpreterError::NoSuchMethod {
+            method: format!("{receiver_class}.{name}{descriptor}"),
+        })
+    }
+
+    /**
+     * Pops the arguments (and receiver) of a resolved method off `frame` and calls it. Natives
+     * run right away, while an interpreted method is returned as the frame to run next.
+     */
+    fn invoke(
+        &mut self,
+        frame: &mut Frame,
+        method_ref_index: u16,
+        resolved: ResolvedMethod,
+    ) -> Result<Step, InterpreterError> {
+        let arguments = frame.operand_stack.pop_n(resolved.parameter_count)?;
+
+        match resolved.target {
+            MethodTarget::Native(native) => {
+                match (native(&arguments), resolved.returns_value) {
+                    (Some(value), true) => frame.push(value)?,
+                    (None, false) => {}
+                    _ => {
+                        let (method_class, name, descriptor) = frame
+                            .class
+                            .constant_pool
+                            .get_method_ref(method_ref_index)
+                            .expect("Expected method ref to be a valid Methodref");
+
+                        return Err(InterpreterError::NativeReturnMismatch {
+                            method: format!("{method_class}.{name}{descriptor}"),
+                        });
+                    }
+                }
+
+                Ok(Step::Next)
+            }
+            MethodTarget::Interpreted { class, method_index } => {
+                let target = &class.methods[method_index];
+
+                Ok(Step::Invoke(Frame::new(
+                    class.clone(),
+                    target,
+                    arguments,
+                    self.config.strict_stack,
+                )?))
+            }
+        }
+    }
+
+    /**
+     * Runs a method to completion and returns the value it returned, if any. `arguments` (with
+     * the receiver first for an instance method) become its initial local variables.
+     */
+    fn execute(
+        &mut self,
+        class: &Rc<ClassFile>,
+        method: &MethodInfo,
+        arguments: Vec<OperandStackEntry>,
+    ) -> Result<Option<OperandStackEntry>, InterpreterError> {
+        let frame = Frame::new(class.clone(), method, arguments, self.config.strict_stack)?;
+        let mut frames = vec![frame];
+
+        let result = self.run_frames(&mut frames);
+
+        if result.is_err() {
+            // A run nested inside this one (ex. a <clinit> started by a getstatic) records its
+            // frames first, and they belong above the frames of this run
+            self.failed_frames.splice(0..0, frames);
+        }
+
+        result
+    }
+
+    /**
+     * Drives the frames of one run until the bottom frame returns. Invocations push a frame and
+     * returns pop one, so the depth of Java recursion is not limited by the native stack.
+     * Frames are left in place when an instruction fails.
+     */
+    fn run_frames(
+        &mut self,
+        frames: &mut Vec<Frame>,
+    ) -> Result<Option<OperandStackEntry>, InterpreterError> {
+        loop {
+            let frame = frames.last_mut().ok_or(InterpreterError::EmptyCallStack)?;
+
+            match self.step(frame)? {
+                Step::Next => {}
+                Step::Invoke(callee) => frames.push(callee),
+                Step::Return(value) => {
+                    frames.pop();
+
+                    let Some(caller) = frames.last_mut() else {
+                        return Ok(value);
+                    };
+
+                    if let Some(value) = value {
+                        caller.push(value)?;
+                    }
+                }
+                Step::Throw(exception) => self.unwind(frames, exception)?,
+            }
+        }
+    }
+
+    /**
+     * Transfers control to the closest handler of `exception`, searching the frames from the top
+     * and discarding the ones above the frame that catches it. When no frame does, the frames
+     * are left in place for the stack trace and the run fails with `UncaughtException`.
+     */
+    fn unwind(&mut self, frames: &mut Vec<Frame>, exception: usize) -> Result<(), InterpreterError> {
+        let class_name = self
+            .heap
+            .get(exception)
+            .expect("Expected exception to be a valid heap handle")
+            .class_name();
+
+        for depth in (0..frames.len()).rev() {
+            let frame = &frames[depth];
+            let code = &frame.method().attributes[frame.code_index].attribute;
+
+            let handlers: Vec<_> = code
+                .handlers_covering(frame.instruction_pc as u16)
+                .map(|handler| {
+                    let catch_class = handler.catch_class_name(&frame.class.constant_pool).cloned();
+
+                    (handler.handler_pc(), catch_class)
+                })
+                .collect();
+
+            for (handler_pc, catch_class) in handlers {
+                let caught = match catch_class {
+                    Some(catch_class) => self.is_exception_subclass(&class_name, &catch_class),
+                    // A catch-all handler (ex. `finally`)
+                    None => true,
+                };
+
+                if caught {
+                    frames.truncate(depth + 1);
+
+                    let frame = frames.last_mut().ok_or(InterpreterError::EmptyCallStack)?;
+
+                    frame.operand_stack.clear();
+                    frame.push(OperandStackEntry::Reference(exception))?;
+                    frame.pc = handler_pc as u64;
+
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(InterpreterError::UncaughtException { class_name })
+    }
+
+    /**
+     * Whether the exception class `class_name` is `catch_class` or one of its subclasses. The
+     * JDK exceptions the interpreter throws itself are not on the class path, so their
+     * superclasses come from `builtin_exception_superclass` instead of the class loader.
+     */
+    fn is_exception_subclass(&mut self, class_name: &str, catch_class: &str) -> bool {
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current {
+            if name == catch_class {
+                return true;
+            }
+
+            current = match builtin_exception_superclass(&name) {
+                Some(super_name) => Some(super_name.to_string()),
+                None => match self.class_loader.load_class(&name) {
+                    Ok(class) => class.get_super_class_name().cloned(),
+                    Err(err) => {
+                        warn!("Could not load {name} while matching a handler for {class_name}: {err}");
+                        None
+                    }
+                },
+            };
+        }
+
+        false
+    }
+
+    /**
+     * Creates an instance of one of the JDK exceptions the interpreter throws itself (ex.
+     * `java/lang/NullPointerException`). They are not on the class path, so the object has no
+     * fields. Returns the handle of the new object.
+     */
+    fn allocate_builtin_exception(&mut self, class_name: &str) -> usize {
+        self.heap.allocate(HeapObject::Object {
+            class_name: class_name.to_string(),
+            fields: HashMap::new(),
+        })
+    }
+
+    /**
+     * Intrinsics for the `java/lang/String` methods that are called on strings modeled as
+     * `OperandStackEntry::String`. Indices and lengths are in UTF-16 code units like in Java,
+     * so a character outside the BMP counts as 2.
+     */
+    fn invoke_string(
+        &mut self,
+        frame: &mut Frame,
+        string: &str,
+        name: &str,
+        descriptor: &str,
+        arguments: &[OperandStackEntry],
+    ) -> Result<Step, InterpreterError> {
+        let result = match (name, descriptor, arguments) {
+            ("length", "()I", []) => OperandStackEntry::Int(string.encode_utf16().count() as i32),
+            ("charAt", "(I)C", [OperandStackEntry::Int(index)]) => {
+                let Some(code_unit) = usize::try_from(*index)
+                    .ok()
+                    .and_then(|index| string.encode_utf16().nth(index))
+                else {
+                    return Ok(Step::Throw(
+                        self.allocate_builtin_exception("java/lang/StringIndexOutOfBoundsException"),
+                    ));
+                };
+
+                OperandStackEntry::Int(code_unit as i32)
+            }
+            ("equals", "(Ljava/lang/Object;)Z", [other]) => {
+                let equal = matches!(other, OperandStackEntry::String(other) if other == string);
+
+                OperandStackEntry::Int(equal as i32)
+            }
+            _ => {
+                return Err(InterpreterError::UnsupportedIntrinsic {
+                    method: format!("java/lang/String.{name}{descriptor}"),
+                })
+            }
+        };
+
+        frame.push(result)?;
+
+        Ok(Step::Next)
+    }
+
+    /**
+     * Executes the instruction at `pc` of `frame`
+     */
+    fn step(&mut self, frame: &mut Frame) -> Result<Step, InterpreterError> {
+        let class = frame.class.clone();
+        let method = &class.methods[frame.method_index];
+
+        let pc = frame.pc;
+
+        // Every method has to end in a return or a jump, like `verifier::verify` checks
+        if pc as usize >= frame.code().len() {
+            return Err(InterpreterError::FallsOffEnd {
+                method: format!("{}.{}{}", class.get_this_class_name(), method.name, method.descriptor),
+            });
+        }
+
+        frame.instruction_pc = pc;
+
+        let instruction = frame.read_u1()?;
+        let mut next = Step::Next;
+
+        if self.config.record_opcode_counts {
+            *self.opcode_counts.entry(instruction).or_insert(0) += 1;
+        }
+
+        match instruction {
+            OpCodeType::getstatic => {
+                let field_ref_index = frame.read_u2()?;
+
+                // The JDK classes are not available on the class path, so the standard
+                // streams are modeled directly instead of loading java/lang/System. This is
+                // checked without resolving the field ref into owned strings because it
+                // runs on every print.
+                if let Some(stream) = system_stream(&class.constant_pool, field_ref_index) {
+                    frame.push(OperandStackEntry::PrintStream(stream))?;
+                } else {
+                    let (field_class, field_name, field_descriptor) = class
+                        .constant_pool
+                        .get_field_ref(field_ref_index)
+                        .expect("Expected field ref to be a valid Fieldref");
+
+                    // Only System.out and System.err are modeled, and the real class can't be loaded
+                    if field_class == "java/lang/System" {
+                        return Err(InterpreterError::UnsupportedIntrinsic {
+                            method: format!("java/lang/System.{field_name}"),
+                        });
+                    }
+
+                    self.initialize_class(&field_class)?;
+
+                    // Fields that were never assigned hold the default value of their type
+                    let value = match self.static_fields[&field_class].get(&field_name) {
+                        Some(value) => value.clone(),
+                        None => {
+                            let field_type = parse_field_descriptor(&field_descriptor)
+                                .expect("Expected field to have a valid descriptor");
+
+                            OperandStackEntry::default_for(&field_type)
+                        }
+                    };
+
+                    frame.push(value)?;
+                }
+            }
+            OpCodeType::putstatic => {
+                let field_ref_index = frame.read_u2()?;
+
+                let (field_class, field_name, field_descriptor) = class
+                    .constant_pool
+                    .get_field_ref(field_ref_index)
+                    .expect("Expected field ref to be a valid Fieldref");
+
+                let value = check_field_type(&field_class, &field_name, &field_descriptor, frame.pop()?)?;
+
+                self.initialize_class(&field_class)?;
+
+                self.static_fields
+                    .get_mut(&field_class)
+                    .expect("Expected class to be initialized")
+                    .insert(field_name, value);
+            }
+            OpCodeType::getfield => {
+                let field_ref_index = frame.read_u2()?;
+
+                let (field_class, field_name, field_descriptor) = class
+                    .constant_pool
+                    .get_field_ref(field_ref_index)
+                    .expect("Expected field ref to be a valid Fieldref");
+
+                let handle = match frame.pop()? {
+                    OperandStackEntry::Reference(handle) => handle,
+                    OperandStackEntry::Null => {
+                        return Ok(Step::Throw(
+                            self.allocate_builtin_exception("java/lang/NullPointerException"),
+                        ))
+                    }
+                    found => {
+                        return Err(InterpreterError::OperandTypeMismatch {
+                            expected: "object reference",
+                            found,
+                        })
+                    }
+                };
+
+                let value = self.object_fields(handle)?.get(&field_name).cloned();
+
+                // A field is only missing if a superclass could not be loaded when the object was
+                // allocated, so it still holds the default value of its type if it exists at all
+                let value = match value {
+                    Some(value) => value,
+                    None => {
+                        if self.class_loader.resolve_field(&field_class, &field_name).is_none() {
+                            return Err(InterpreterError::NoSuchField {
+                                field: format!("{field_class}.{field_name}"),
+                            });
+                        }
+
+                        let field_type = parse_field_descriptor(&field_descriptor)
+                            .expect("Expected field to have a valid descriptor");
+
+                        OperandStackEntry::default_for(&field_type)
+                    }
+                };
+
+                frame.push(value)?;
+            }
+            OpCodeType::putfield => {
+                let field_ref_index = frame.read_u2()?;
+
+                let (field_class, field_name, field_descriptor) = class
+                    .constant_pool
+                    .get_field_ref(field_ref_index)
+                    .expect("Expected field ref to be a valid Fieldref");
+
+                let value = check_field_type(&field_class, &field_name, &field_descriptor, frame.pop()?)?;
+
+                let handle = match frame.pop()? {
+                    OperandStackEntry::Reference(handle) => handle,
+                    OperandStackEntry::Null => {
+                        return Ok(Step::Throw(
+                            self.allocate_builtin_exception("java/lang/NullPointerException"),
+                        ))
+                    }
+                    found => {
+                        return Err(InterpreterError::OperandTypeMismatch {
+                            expected: "object reference",
+                            found,
+                        })
+                    }
+                };
+
+                if !self.object_fields(handle)?.contains_key(&field_name)
+                    && self.class_loader.resolve_field(&field_class, &field_name).is_none()
+                {
+                    return Err(InterpreterError::NoSuchField {
+                        field: format!("{field_class}.{field_name}"),
+                    });
+                }
+
+                let Some(HeapObject::Object { fields, .. }) = self.heap.get_mut(handle) else {
+                    unreachable!("Expected object_fields to have checked the handle")
+                };
+
+                fields.insert(field_name, value);
+            }
+            OpCodeType::aconst_null => frame.push(OperandStackEntry::Null)?,
+            OpCodeType::bipush => {
+                // The operand is a signed byte, so it has to be sign extended (0xff is -1, not 255)
+                let value = frame.read_i1()?;
+
+                frame.push(OperandStackEntry::Int(value as i32))?;
+            }
+            OpCodeType::sipush => {
+                let value = frame.read_i2()?;
+
+                frame.push(OperandStackEntry::Int(value as i32))?;
+            }
+            // iconst_m1 is -1, so iconst_<n> is n places after it
+            OpCodeType::iconst_m1..=OpCodeType::iconst_5 => {
+                let value = instruction as i32 - OpCodeType::iconst_m1 as i32 - 1;
+
+                frame.push(OperandStackEntry::Int(value))?;
+            }
+            OpCodeType::lconst_0..=OpCodeType::lconst_1 => {
+                frame.push(OperandStackEntry::Long((instruction - OpCodeType::lconst_0) as i64))?;
+            }
+            OpCodeType::fconst_0..=OpCodeType::fconst_2 => {
+                frame.push(OperandStackEntry::Float((instruction - OpCodeType::fconst_0) as f32))?;
+            }
+            OpCodeType::dconst_0..=OpCodeType::dconst_1 => {
+                frame.push(OperandStackEntry::Double((instruction - OpCodeType::dconst_0) as f64))?;
+            }
+            OpCodeType::ldc | OpCodeType::ldc_w => {
+                let constant_index = match instruction {
+                    OpCodeType::ldc => frame.read_u1()? as u16,
+                    _ => frame.read_u2()?,
+                };
+
+                let value = match class.constant_pool.get_value(constant_index) {
+                    ConstantPoolInfo::Integer { value } => OperandStackEntry::Int(*value),
+                    ConstantPoolInfo::Float { value } => OperandStackEntry::Float(*value),
+                    ConstantPoolInfo::String { string_index } => OperandStackEntry::String(
+                        class
+                            .constant_pool
+                            .get_utf8_from_index(*string_index)
+                            .expect("Expected string_index to be utf-8")
+                            .clone(),
+                    ),
+                    // Class literals (ex. `Foo.class`). The class is not loaded, since it is
+                    // often a JDK or array class that isn't on the class path
+                    ConstantPoolInfo::Class { .. } => OperandStackEntry::Class(
+                        class
+                            .constant_pool
+                            .get_class_name_from_index(constant_index)
+                            .expect("Expected Class constant to have a valid name")
+                            .clone(),
+                    ),
+                    // Both are loadable since class file version 51, but resolving them needs
+                    // java.lang.invoke, which the interpreter does not model
+                    ConstantPoolInfo::MethodHandle { .. } | ConstantPoolInfo::MethodType { .. } => {
+                        return Err(InterpreterError::UnsupportedIntrinsic {
+                            method: format!("ldc of constant #{constant_index}"),
+                        })
+                    }
+                    _ => {
+                        return Err(InterpreterError::InvalidLoadConstant {
+                            index: constant_index,
+                            opcode: instruction,
+                        })
+                    }
+                };
+
+                frame.push(value)?;
+            }
+            OpCodeType::ldc2_w => {
+                let constant_index = frame.read_u2()?;
+
+                let value = match class.constant_pool.get_value(constant_index) {
+                    ConstantPoolInfo::Long { value } => OperandStackEntry::Long(*value),
+                    ConstantPoolInfo::Double { value } => OperandStackEntry::Double(*value),
+                    _ => {
+                        return Err(InterpreterError::InvalidLoadConstant {
+                            index: constant_index,
+                            opcode: instruction,
+                        })
+                    }
+                };
+
+                frame.push(value)?;
+            }
+            OpCodeType::iload..=OpCodeType::aload => {
+                let index = frame.read_u1()?;
+
+                frame.push(frame.locals.load(index as u16)?.clone())?;
+            }
+            // `iload_0` to `aload_3` come in groups of four, one for each index
+            OpCodeType::iload_0..=OpCodeType::aload_3 => {
+                let index = (instruction - OpCodeType::iload_0) % 4;
+
+                frame.push(frame.locals.load(index as u16)?.clone())?;
+            }
+            OpCodeType::istore..=OpCodeType::astore => {
+                let index = frame.read_u1()?;
+
+                let value = frame.pop()?;
+
+                frame.locals.store(index as u16, value)?;
+            }
+            OpCodeType::istore_0..=OpCodeType::astore_3 => {
+                let index = (instruction - OpCodeType::istore_0) % 4;
+
+                let value = frame.pop()?;
+
+                frame.locals.store(index as u16, value)?;
+            }
+            OpCodeType::iadd
+            | OpCodeType::isub
+            | OpCodeType::imul
+            | OpCodeType::ishl
+            | OpCodeType::ishr
+            | OpCodeType::iushr
+            | OpCodeType::iand
+            | OpCodeType::ior
+            | OpCodeType::ixor => {
+                let value2 = frame.pop_int()?;
+                let value1 = frame.pop_int()?;
+
+                // Overflow wraps around and shifts only use the low 5 bits of the distance, like in Java
+                let result = match instruction {
+                    OpCodeType::iadd => value1.wrapping_add(value2),
+                    OpCodeType::isub => value1.wrapping_sub(value2),
+                    OpCodeType::imul => value1.wrapping_mul(value2),
+                    OpCodeType::ishl => value1.wrapping_shl(value2 as u32),
+                    OpCodeType::ishr => value1.wrapping_shr(value2 as u32),
+                    OpCodeType::iushr => (value1 as u32).wrapping_shr(value2 as u32) as i32,
+                    OpCodeType::iand => value1 & value2,
+                    OpCodeType::ior => value1 | value2,
+                    _ => value1 ^ value2,
+                };
+
+                frame.push(OperandStackEntry::Int(result))?;
+            }
+            OpCodeType::idiv | OpCodeType::irem => {
+                let value2 = frame.pop_int()?;
+                let value1 = frame.pop_int()?;
+
+                if value2 == 0 {
+                    next = Step::Throw(self.allocate_builtin_exception("java/lang/ArithmeticException"));
+                } else {
+                    // `i32::MIN / -1` overflows back to `i32::MIN` in Java
+                    let result = match instruction {
+                        OpCodeType::idiv => value1.wrapping_div(value2),
+                        _ => value1.wrapping_rem(value2),
+                    };
+
+                    frame.push(OperandStackEntry::Int(result))?;
+                }
+            }
+            OpCodeType::ineg => {
+                let value = frame.pop_int()?;
+
+                frame.push(OperandStackEntry::Int(value.wrapping_neg()))?;
+            }
+            OpCodeType::ladd => {
+                let value2 = frame.operand_stack.pop_long()?;
+                let value1 = frame.operand_stack.pop_long()?;
+
+                // Overflow wraps around like in Java
+                frame.push(OperandStackEntry::Long(value1.wrapping_add(value2)))?;
+            }
+            OpCodeType::lsub => {
+                let value2 = frame.operand_stack.pop_long()?;
+                let value1 = frame.operand_stack.pop_long()?;
+
+                frame.push(OperandStackEntry::Long(value1.wrapping_sub(value2)))?;
+            }
+            OpCodeType::dadd => {
+                let value2 = frame.operand_stack.pop_double()?;
+                let value1 = frame.operand_stack.pop_double()?;
+
+                frame.push(OperandStackEntry::Double(value1 + value2))?;
+            }
+            OpCodeType::dsub => {
+                let value2 = frame.operand_stack.pop_double()?;
+                let value1 = frame.operand_stack.pop_double()?;
+
+                frame.push(OperandStackEntry::Double(value1 - value2))?;
+            }
+            OpCodeType::iinc => {
+                let index = frame.read_u1()?;
+                let increment = frame.read_i1()?;
+
+                frame.locals.increment(index as u16, increment as i32)?;
+            }
+            OpCodeType::wide => {
+                // Widens the local variable index (and the constant of `iinc`) of the
+                // instruction that follows to 16 bits
+                let opcode = frame.read_u1()?;
+                let index = frame.read_u2()?;
+
+                match opcode {
+                    OpCodeType::iinc => {
+                        let increment = frame.read_i2()?;
+
+                        frame.locals.increment(index, increment as i32)?;
+                    }
+                    OpCodeType::iload..=OpCodeType::aload => {
+                        frame.push(frame.locals.load(index)?.clone())?;
+                    }
+                    OpCodeType::istore..=OpCodeType::astore => {
+                        let value = frame.pop()?;
+
+                        frame.locals.store(index, value)?;
+                    }
+                    _ => return Err(InterpreterError::UnimplementedOpcode { opcode, pc }),
+                }
+            }
+            OpCodeType::ifeq..=OpCodeType::ifle => {
+                let offset = frame.read_i2()?;
+                let value = frame.pop_int()?;
+
+                let taken = match instruction {
+                    OpCodeType::ifeq => value == 0,
+                    OpCodeType::ifne => value != 0,
+                    OpCodeType::iflt => value < 0,
+                    OpCodeType::ifge => value >= 0,
+                    OpCodeType::ifgt => value > 0,
+                    _ => value <= 0,
+                };
+
+                if taken {
+                    frame.pc = branch_target(pc, offset as i32);
+                }
+            }
+            OpCodeType::if_icmpeq..=OpCodeType::if_icmple => {
+                let offset = frame.read_i2()?;
+                let value2 = frame.pop_int()?;
+                let value1 = frame.pop_int()?;
+
+                let taken = match instruction {
+                    OpCodeType::if_icmpeq => value1 == value2,
+                    OpCodeType::if_icmpne => value1 != value2,
+                    OpCodeType::if_icmplt => value1 < value2,
+                    OpCodeType::if_icmpge => value1 >= value2,
+                    OpCodeType::if_icmpgt => value1 > value2,
+                    _ => value1 <= value2,
+                };
+
+                if taken {
+                    frame.pc = branch_target(pc, offset as i32);
+                }
+            }
+            OpCodeType::goto => {
+                let offset = frame.read_i2()?;
+
+                frame.pc = branch_target(pc, offset as i32);
+            }
+            OpCodeType::pop => {
+                category_1(frame.operand_stack.peek(0)?)?;
+
+                frame.pop()?;
+            }
+            OpCodeType::pop2 => {
+                // One category 2 value or two category 1 values
+                if frame.operand_stack.peek(0)?.category() == 1 {
+                    category_1(frame.operand_stack.peek(1)?)?;
+
+                    frame.pop()?;
+                }
+
+                frame.pop()?;
+            }
+            OpCodeType::dup => {
+                category_1(frame.operand_stack.peek(0)?)?;
+
+                frame.push(frame.operand_stack.peek(0)?.clone())?;
+            }
+            OpCodeType::dup2 => {
+                // One category 2 value or two category 1 values
+                let duplicated = if frame.operand_stack.peek(0)?.category() == 2 {
+                    vec![frame.operand_stack.peek(0)?.clone()]
+                } else {
+                    category_1(frame.operand_stack.peek(1)?)?;
+
+                    vec![frame.operand_stack.peek(1)?.clone(), frame.operand_stack.peek(0)?.clone()]
+                };
+
+                for entry in duplicated {
+                    frame.push(entry)?;
+                }
+            }
+            OpCodeType::invokestatic => {
+                let method_index = frame.read_u2()?;
+
+                if is_array_copy(&class.constant_pool, method_index) {
+                    let arguments = frame.operand_stack.pop_n(5)?;
+
+                    if let Some(exception) = self.array_copy(&arguments)? {
+                        next = Step::Throw(exception);
+                    }
+                } else {
+                    let resolved = self.resolve_method(&class, method_index, true)?;
+
+                    next = self.invoke(frame, method_index, resolved)?;
+                }
+            }
+            OpCodeType::invokespecial => {
+                // Constructors, private methods and super calls, which are not dispatched on the
+                // receiver's class
+                let method_index = frame.read_u2()?;
+
+                let resolved = self.resolve_method(&class, method_index, false)?;
+
+                next = self.invoke(frame, method_index, resolved)?;
+            }
+            OpCodeType::new => {
+                let class_index = frame.read_u2()?;
+
+                let class_name = class
+                    .constant_pool
+                    .get_class_name_from_index(class_index)
+                    .expect("Expected new to reference a Class constant");
+
+                let instance_class = self.initialize_class(class_name)?;
+
+                let handle = self.allocate_instance(&instance_class);
+
+                frame.push(OperandStackEntry::Reference(handle))?;
+            }
+            OpCodeType::newarray => {
+                let atype = frame.read_u1()?;
+                let length = frame.pop_int()?;
+
+                let element_type = match atype {
+                    4 => FieldType::Boolean,
+                    5 => FieldType::Char,
+                    6 => FieldType::Float,
+                    7 => FieldType::Double,
+                    8 => FieldType::Byte,
+                    9 => FieldType::Short,
+                    10 => FieldType::Int,
+                    11 => FieldType::Long,
+                    _ => panic!("Unexpected newarray type {atype}"),
+                };
+
+                let handle = self.allocate_array(element_type, length)?;
+
+                frame.push(OperandStackEntry::Reference(handle))?;
+            }
+            OpCodeType::anewarray => {
+                let class_index = frame.read_u2()?;
+                let length = frame.pop_int()?;
+
+                let class_name = class
+                    .constant_pool
+                    .get_class_name_from_index(class_index)
+                    .expect("Expected anewarray to reference a Class constant");
+
+                // Array classes are named by their descriptor, so `[I` is an `int[][]` component.
+                // The component class is not loaded, since most of them are JDK classes
+                let element_type = if class_name.starts_with('[') {
+                    parse_field_descriptor(class_name)
+                        .expect("Expected array class to have a valid descriptor")
+                } else {
+                    FieldType::Object(class_name.clone())
+                };
+
+                let handle = self.allocate_array(element_type, length)?;
+
+                frame.push(OperandStackEntry::Reference(handle))?;
+            }
+            OpCodeType::arraylength => {
+                let Some(handle) = pop_array(frame)? else {
+                    return Ok(Step::Throw(self.allocate_builtin_exception("java/lang/NullPointerException")));
+                };
+
+                let length = self.array_elements(handle).len();
+
+                frame.push(OperandStackEntry::Int(length as i32))?;
+            }
+            OpCodeType::iaload..=OpCodeType::saload => {
+                let index = frame.pop_int()?;
+                let Some(handle) = pop_array(frame)? else {
+                    return Ok(Step::Throw(self.allocate_builtin_exception("java/lang/NullPointerException")));
+                };
+
+                let elements = self.array_elements(handle);
+                let index = array_index(index, elements.len())?;
+
+                frame.push(elements[index].clone())?;
+            }
+            OpCodeType::iastore..=OpCodeType::sastore => {
+                let value = frame.pop()?;
+                let index = frame.pop_int()?;
+                let Some(handle) = pop_array(frame)? else {
+                    return Ok(Step::Throw(self.allocate_builtin_exception("java/lang/NullPointerException")));
+                };
+
+                let Some(HeapObject::Array { element_type, elements }) = self.heap.get_mut(handle)
+                else {
+                    panic!("Expected reference to be a valid array handle")
+                };
+
+                if !value.matches_type(element_type) {
+                    return Err(InterpreterError::OperandTypeMismatch {
+                        expected: match instruction {
+                            OpCodeType::lastore => "long",
+                            OpCodeType::fastore => "float",
+                            OpCodeType::dastore => "double",
+                            OpCodeType::aastore => "reference",
+                            _ => "int",
+                        },
+                        found: value,
+                    });
+                }
+
+                let index = array_index(index, elements.len())?;
+
+                // Narrow element types keep their values truncated, so loads don't have to
+                elements[index] = match (&*element_type, value) {
+                    (FieldType::Boolean, OperandStackEntry::Int(value)) => OperandStackEntry::Int(value & 1),
+                    (FieldType::Byte, OperandStackEntry::Int(value)) => OperandStackEntry::Int(value as i8 as i32),
+                    (FieldType::Char, OperandStackEntry::Int(value)) => OperandStackEntry::Int(value as u16 as i32),
+                    (FieldType::Short, OperandStackEntry::Int(value)) => OperandStackEntry::Int(value as i16 as i32),
+                    (_, value) => value,
+                };
+            }
+            OpCodeType::invokevirtual => {
+                let method_index = frame.read_u2()?;
+
+                let method_ref = class.constant_pool.get_value(method_index);
+
+                let ConstantPoolInfo::Methodref { class_index, name_and_type_index } = method_ref else {
+                    panic!("Expected method ref to be of type Methodref")
+                };
+
+                let method_class = class
+                    .constant_pool
+                    .get_class_name_from_index(*class_index)
+                    .expect("Expected method to have valid class index");
+
+                let (name, descriptor) = class
+                    .constant_pool
+                    .get_name_and_type(*name_and_type_index)
+                    .expect("Expected method to have valid name_and_type index");
+
+                let method_descriptor = parse_method_descriptor(descriptor)
+                    .expect("Expected method to have a valid descriptor");
+
+                let arguments = frame.operand_stack.pop_n(method_descriptor.params.len())?;
+
+                let receiver = frame.pop()?;
+
+                match receiver {
+                    OperandStackEntry::PrintStream(stream) => {
+                        next = self.invoke_print_stream(stream, name, descriptor, &arguments)?
+                    }
+                    OperandStackEntry::String(string) => {
+                        next = self.invoke_string(frame, &string, name, descriptor, &arguments)?
+                    }
+                    OperandStackEntry::Reference(handle) => {
+                        let receiver_class = match self.heap.get(handle) {
+                            Some(HeapObject::Object { class_name, .. }) => class_name.clone(),
+                            _ => {
+                                return Err(InterpreterError::UnsupportedIntrinsic {
+                                    method: format!("{method_class}.{name}{descriptor} on an array"),
+                                })
+                            }
+                        };
+
+                        let target = self.select_virtual_method(&receiver_class, name, descriptor)?;
+
+                        let arguments = [vec![receiver], arguments].concat();
+
+                        next = match target {
+                            MethodTarget::Native(native) => {
+                                let returns_value = matches!(method_descriptor.return_type, ReturnType::Value(_));
+
+                                match (native(&arguments), returns_value) {
+                                    (Some(value), true) => frame.push(value)?,
+                                    (None, false) => {}
+                                    _ => {
+                                        return Err(InterpreterError::NativeReturnMismatch {
+                                            method: format!("{method_class}.{name}{descriptor}"),
+                                        })
+                                    }
+                                }
+
+                                Step::Next
+                            }
+                            MethodTarget::Interpreted { class, method_index } => Step::Invoke(Frame::new(
+                                class.clone(),
+                                &class.methods[method_index],
+                                arguments,
+                                self.config.strict_stack,
+                            )?),
+                        };
+                    }
+                    OperandStackEntry::Null => {
+                        next = Step::Throw(self.allocate_builtin_exception("java/lang/NullPointerException"));
+                    }
+                    found => {
+                        return Err(InterpreterError::OperandTypeMismatch {
+                            expected: "object reference",
+                            found,
+                        })
+                    }
+                }
+            }
+            OpCodeType::ireturn..=OpCodeType::r#return => {
+                if self.config.strict_returns {
+                    check_return_type(&class, method, instruction)?;
+                }
+
+                // The value is popped with the type of the instruction, so an `ireturn` of a
+                // `long` fails here instead of surfacing in the caller
+                let value = match instruction {
+                    OpCodeType::ireturn => Some(OperandStackEntry::Int(frame.pop_int()?)),
+                    OpCodeType::lreturn => Some(OperandStackEntry::Long(frame.operand_stack.pop_long()?)),
+                    OpCodeType::dreturn => Some(OperandStackEntry::Double(frame.operand_stack.pop_double()?)),
+                    OpCodeType::r#return => None,
+                    _ => Some(frame.pop()?),
+                };
+
+                next = Step::Return(value);
+            }
+            OpCodeType::athrow => {
+                let exception = match frame.pop()? {
+                    OperandStackEntry::Reference(handle) => handle,
+                    // Throwing null throws a NullPointerException instead
+                    OperandStackEntry::Null => {
+                        self.allocate_builtin_exception("java/lang/NullPointerException")
+                    }
+                    found => {
+                        return Err(InterpreterError::OperandTypeMismatch {
+                            expected: "reference",
+                            found,
+                        })
+                    }
+                };
+
+                next = Step::Throw(exception);
+            }
+            _ => match self.config.on_unimplemented {
+                UnimplementedPolicy::Halt => {
+                    return Err(InterpreterError::UnimplementedOpcode {
+                        opcode: instruction,
+                        pc,
+                    })
+                }
+                UnimplementedPolicy::WarnAndSkip => {
+                    warn!("Skipping unimplemented instruction 0x{instruction:02x} at {pc}");
+
+                    let length = instruction_length(frame.code(), pc as usize)?;
+                    frame.pc = pc + length as u64;
+                }
+            },
+        }
+
+        trace!(
+            "{}.{}{} pc={pc} opcode=0x{instruction:02x} top={:?}",
+            class.get_this_class_name(),
+            method.name,
+            method.descriptor,
+            frame.operand_stack.peek(0).ok()
+        );
+
+        Ok(next)
+    }
+
+    /**
+     * Handles the `java/io/PrintStream` methods called on `System.out` and `System.err`.
+     * Printing a `null` `char[]` throws a `NullPointerException` like it does in Java.
+     */
+    fn invoke_print_stream(
+        &mut self,
+        stream: StreamKind,
+        name: &str,
+        descriptor: &str,
+        arguments: &[OperandStackEntry],
+    ) -> Result<Step, InterpreterError> {
+        let unsupported = || InterpreterError::UnsupportedIntrinsic {
+            method: format!("java/io/PrintStream.{name}{descriptor}"),
+        };
+
+        let text = match (descriptor, arguments) {
+            ("(Ljava/lang/String;)V" | "(Ljava/lang/Object;)V", [OperandStackEntry::Null]) => {
+                String::from("null")
+            }
+            ("(Ljava/lang/String;)V" | "(Ljava/lang/Object;)V", [OperandStackEntry::String(string)]) => {
+                string.clone()
+            }
+            ("(Ljava/lang/Object;)V", [OperandStackEntry::Reference(handle)]) => {
+                self.object_to_string(*handle)?
+            }
+            // booleans and chars are plain ints on the operand stack, only the descriptor says otherwise
+            ("(Z)V", [OperandStackEntry::Int(value)]) => (*value != 0).to_string(),
+            ("(C)V", [OperandStackEntry::Int(value)]) => char::from_u32(*value as u16 as u32)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                .to_string(),
+            ("(I)V", [OperandStackEntry::Int(value)]) => value.to_string(),
+            ("(J)V", [OperandStackEntry::Long(value)]) => value.to_string(),
+            ("(F)V", [OperandStackEntry::Float(value)]) => java_float_to_string(*value),
+            ("(D)V", [OperandStackEntry::Double(value)]) => java_double_to_string(*value),
+            ("([C)V", [OperandStackEntry::Reference(handle)]) => {
+                let units = self
+                    .array_elements(*handle)
+                    .iter()
+                    .map(|element| match element {
+                        OperandStackEntry::Int(unit) => *unit as u16,
+                        _ => panic!("Expected char array to hold ints"),
+                    })
+                    .collect::<Vec<_>>();
+
+                String::from_utf16_lossy(&units)
+            }
+            ("([C)V", [OperandStackEntry::Null]) => {
+                return Ok(Step::Throw(self.allocate_builtin_exception("java/lang/NullPointerException")))
+            }
+            // println() only prints the line separator
+            ("()V", []) if name == "println" => String::new(),
+            _ => return Err(unsupported()),
+        };
+
+        let sink = match stream {
+            StreamKind::Out => &mut self.stdout,
+            StreamKind::Err => &mut self.stderr,
+        };
+
+        match name {
+            "println" => {
+                sink.write_all(text.as_bytes())?;
+                sink.write_all(self.config.line_separator.as_bytes())?;
+            }
+            "print" => sink.write_all(text.as_bytes())?,
+            _ => return Err(unsupported()),
+        }
+
+        sink.flush()?;
+
+        Ok(Step::Next)
+    }
+
+    /**
+     * Calls `toString` on an object, running the closest override in its class or superclasses.
+     * Without an override this is `Object.toString`, which is the class name and a hash (here
+     * the heap handle, which is unique per object) like `com.example.Foo@1f`. Arrays always
+     * use `Object.toString` (ex. `[I@1f`).
+     */
+    fn object_to_string(&mut self, handle: usize) -> Result<String, InterpreterError> {
+        let class_name = self
+            .heap
+            .get(handle)
+            .expect("Expected reference to be a valid heap handle")
+            .class_name();
+
+        let mut current = Some(class_name.clone());
+
+        while let Some(name) =
+            current.filter(|name| name != "java/lang/Object" && !name.starts_with('['))
+        {
+            let class = self.class_loader.load_class(&name)?;
+
+            if let Some(method) = class
+                .get_method("toString", "()Ljava/lang/String;")
+                .filter(|method| !method.is_abstract())
+            {
+                return match self.execute(&class, method, vec![OperandStackEntry::Reference(handle)])? {
+                    Some(OperandStackEntry::String(string)) => Ok(string),
+                    Some(OperandStackEntry::Null) => Ok(String::from("null")),
+                    value => panic!("Expected {name}.toString to return a String, but got {value:?}"),
+                };
+            }
+
+            current = class.super_class_ref().map(|super_class| super_class.name);
+        }
+
+        Ok(format!("{}@{handle:x}", class_name.replace('/', ".")))
+    }
+}
+
+/**
+ * The superclass of the JDK exceptions the interpreter can throw without loading them
+ */
+fn builtin_exception_superclass(class_name: &str) -> Option<&'static str> {
+    match class_name {
+        "java/lang/NullPointerException"
+        | "java/lang/ArithmeticException"
+        | "java/lang/ArrayStoreException"
+        | "java/lang/IndexOutOfBoundsException" => Some("java/lang/RuntimeException"),
+        "java/lang/ArrayIndexOutOfBoundsException" | "java/lang/StringIndexOutOfBoundsException" => {
+            Some("java/lang/IndexOutOfBoundsException")
+        }
+        "java/lang/RuntimeException" => Some("java/lang/Exception"),
+        "java/lang/Exception" => Some("java/lang/Throwable"),
+        "java/lang/Throwable" => Some("java/lang/Object"),
+        _ => None,
+    }
+}
+
+/**
+ * Formats a `double` like `Double.toString`: plain decimals with at least one fractional digit
+ * between 10^-3 and 10^7 (ex. `1.0`, `0.25`), and computerized scientific notation outside of
+ * that range (ex. `1.0E7`, `2.5E-4`)
+ */
+fn java_double_to_string(value: f64) -> String {
+    java_decimal_to_string(value)
+}
+
+/**
+ * Formats a `float` like `Float.toString`, which follows the same rules as `Double.toString`
+ * but with only as many digits as it takes to tell the `float` apart (ex. `0.1`, not `0.10000000149011612`)
+ */
+fn java_float_to_string(value: f32) -> String {
+    java_decimal_to_string(value)
+}
+
+fn java_decimal_to_string<T>(value: T) -> String
+where
+    T: Copy + Into<f64> + fmt::Debug + fmt::LowerExp,
+{
+    let wide: f64 = value.into();
+
+    if wide.is_nan() {
+        return String::from("NaN");
+    }
+
+    if wide.is_infinite() {
+        return String::from(if wide > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+
+    if wide == 0.0 || (1e-3..1e7).contains(&wide.abs()) {
+        // Debug always keeps the fractional digit and never uses an exponent in this range
+        return format!("{value:?}");
+    }
+
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("Expected scientific notation to have an exponent");
+
+    if mantissa.contains('.') {
+        format!("{mantissa}E{exponent}")
+    } else {
+        format!("{mantissa}.0E{exponent}")
+    }
+}
+
+/**
+ * Makes sure an instruction that works on single slots doesn't split a `long` or `double`
+ */
+fn category_1(entry: &OperandStackEntry) -> Result<(), InterpreterError> {
+    match entry.category() {
+        1 => Ok(()),
+        _ => Err(InterpreterError::OperandTypeMismatch {
+            expected: "category 1 value",
+            found: entry.clone(),
+        }),
+    }
+}
+
+/**
+ * Pops the array reference of an array instruction, which is `None` for `null`
+ */
+fn pop_array(frame: &mut Frame) -> Result<Option<usize>, InterpreterError> {
+    match frame.pop()? {
+        OperandStackEntry::Reference(handle) => Ok(Some(handle)),
+        OperandStackEntry::Null => Ok(None),
+        found => Err(InterpreterError::OperandTypeMismatch {
+            expected: "array reference",
+            found,
+        }),
+    }
+}
+
+/**
+ * Checks an `int` index against the length of the array it indexes
+ */
+fn array_index(index: i32, length: usize) -> Result<usize, InterpreterError> {
+    match usize::try_from(index) {
+        Ok(checked) if checked < length => Ok(checked),
+        _ => Err(InterpreterError::ArrayIndexOutOfBounds { index, length }),
+    }
+}
+
+/**
+ * Makes sure a method returns with the instruction that matches the return type of its descriptor
+ */
+fn check_return_type(
+    class: &ClassFile,
+    method: &MethodInfo,
+    opcode: u8,
+) -> Result<(), InterpreterError> {
+    let return_type = parse_method_descriptor(&method.descriptor)
+        .expect("Expected method to have a valid descriptor")
+        .return_type;
+
+    let expected = match &return_type {
+        ReturnType::Void => OpCodeType::r#return,
+        ReturnType::Value(
+            FieldType::Boolean | FieldType::Byte | FieldType::Char | FieldType::Short | FieldType::Int,
+        ) => OpCodeType::ireturn,
+        ReturnType::Value(FieldType::Long) => OpCodeType::lreturn,
+        ReturnType::Value(FieldType::Float) => OpCodeType::freturn,
+        ReturnType::Value(FieldType::Double) => OpCodeType::dreturn,
+        ReturnType::Value(FieldType::Object(_) | FieldType::Array(_)) => OpCodeType::areturn,
+    };
+
+    if opcode != expected {
+        return Err(InterpreterError::ReturnTypeMismatch {
+            method: format!(
+                "{}.{}{}",
+                class.get_this_class_name(),
+                method.name,
+                method.descriptor
+            ),
+            return_type,
+            opcode,
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Returns which stream a `Fieldref` refers to if it is `System.out` or `System.err`
+ */
+fn system_stream(constant_pool: &dyn ConstantPool, field_ref_index: u16) -> Option<StreamKind> {
+    let ConstantPoolInfo::Fieldref { class_index, name_and_type_index } = constant_pool.get_value(field_ref_index) else {
+        return None;
+    };
+
+    if constant_pool.get_class_name_from_index(*class_index).ok()? != "java/lang/System" {
+        return None;
+    }
+
+    match constant_pool.get_name_and_type(*name_and_type_index).ok()?.0.as_str() {
+        "out" => Some(StreamKind::Out),
+        "err" => Some(StreamKind::Err),
+        _ => None,
+    }
+}
+
+/**
+ * Whether a `Methodref` refers to `System.arraycopy`, which the interpreter implements itself
+ */
+fn is_array_copy(constant_pool: &dyn ConstantPool, method_ref_index: u16) -> bool {
+    let Ok((class_name, name, descriptor)) = constant_pool.get_method_ref(method_ref_index) else {
+        return false;
+    };
+
+    class_name == "java/lang/System"
+        && name == "arraycopy"
+        && descriptor == "(Ljava/lang/Object;ILjava/lang/Object;II)V"
+}
+
+/**
+ * Branch offsets are relative to the pc of the branch instruction itself, not to the end of
+ * its operands
+ */
+fn branch_target(pc: u64, offset: i32) -> u64 {
+    u64::try_from(pc as i64 + offset as i64).expect("Expected branch target to be inside the method")
+}
+
+/**
+ * Makes sure a value moved by a field access opcode has the type of the field it is stored in,
+ * so a malformed access can't silently corrupt the operand stack. Returns the value narrowed to
+ * the field's type.
+ */
+fn check_field_type(
+    field_class: &str,
+    field_name: &str,
+    descriptor: &str,
+    value: OperandStackEntry,
+) -> Result<OperandStackEntry, InterpreterError> {
+    let field_type =
+        parse_field_descriptor(descriptor).expect("Expected field to have a valid descriptor");
+
+    if !value.matches_type(&field_type) {
+        return Err(InterpreterError::FieldTypeMismatch {
+            field: format!("{field_class}.{field_name}"),
+            descriptor: descriptor.to_string(),
+        });
+    }
+
+    Ok(narrow_boolean(&field_type, value))
+}
+
+/**
+ * An `int` stored into a `boolean` keeps only its lowest bit, like `putfield` and `bastore` do
+ * in the JVM, so a `boolean` never holds anything but 0 or 1
+ */
+fn narrow_boolean(target: &FieldType, value: OperandStackEntry) -> OperandStackEntry {
+    match (target, value) {
+        (FieldType::Boolean, OperandStackEntry::Int(value)) => OperandStackEntry::Int(value & 1),
+        (_, value) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        capture_logs, fixtures_dir, ClassBuilder, SharedBuffer, ACC_NATIVE, ACC_PUBLIC, ACC_STATIC,
+    };
+
+    /**
+     * An interpreter loading classes compiled from `tests/fixtures`, with its stdout captured
+     */
+    fn fixture_interpreter() -> (Interpreter, SharedBuffer) {
+        fixture_interpreter_with(InterpreterConfig::default())
+    }
+
+    fn fixture_interpreter_with(config: InterpreterConfig) -> (Interpreter, SharedBuffer) {
+        let mut interpreter = Interpreter::with_config(ClassLoader::new(vec![fixtures_dir()]), config);
+        let stdout = SharedBuffer::default();
+        interpreter.set_stdout(Box::new(stdout.clone()));
+
+        (interpreter, stdout)
+    }
+
+    /**
+     * Runs the `main` of a fixture class and returns what it printed
+     */
+    fn run_fixture(class_name: &str) -> (Result<(), InterpreterError>, String) {
+        let (mut interpreter, stdout) = fixture_interpreter();
+
+        let result = interpreter.run_main(class_name);
+
+        (result, stdout.contents())
+    }
+
+    /**
+     * An interpreter that can only load the given classes, with its stdout captured
+     */
+    fn interpreter_with(classes: Vec<ClassFile>, config: InterpreterConfig) -> (Interpreter, SharedBuffer) {
+        let mut class_loader = ClassLoader::new(Vec::new());
+
+        for class in classes {
+            class_loader.add_class(class);
+        }
+
+        let mut interpreter = Interpreter::with_config(class_loader, config);
+        let stdout = SharedBuffer::default();
+        interpreter.set_stdout(Box::new(stdout.clone()));
+
+        (interpreter, stdout)
+    }
+
+    /**
+     * Runs a static method of an already added class with the given arguments
+     */
+    fn call_static(
+        interpreter: &mut Interpreter,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        arguments: Vec<OperandStackEntry>,
+    ) -> Result<Option<OperandStackEntry>, InterpreterError> {
+        let class = interpreter.initialize_class(class_name)?;
+        let method = class
+            .get_method(name, descriptor)
+            .expect("Expected test method to exist");
+
+        interpreter.execute(&class, method, arguments)
+    }
+
+    #[test]
+    fn getstatic_initializes_and_reads_another_class() {
+        let (result, stdout) = run_fixture("ReadsConstant");
+
+        result.unwrap();
+        assert_eq!(stdout, "7\n");
+    }
+
+    #[test]
+    fn constant_value_of_the_wrong_kind_is_an_error() {
+        let mut builder = ClassBuilder::new("BadConstant");
+        let class_index = builder.class("BadConstant");
+        let constant_value = builder.attribute("ConstantValue", &class_index.to_be_bytes());
+        builder.field(ACC_PUBLIC | ACC_STATIC, "VALUE", "I", &[constant_value]);
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            interpreter.initialize_class("BadConstant"),
+            Err(InterpreterError::InvalidConstantValue { index, .. }) if index == class_index
+        ));
+    }
+
+    #[test]
+    fn field_access_of_the_wrong_width_is_an_error() {
+        let mut builder = ClassBuilder::new("Widths");
+        builder.field(ACC_PUBLIC | ACC_STATIC, "total", "J", &[]);
+        let total = builder.field_ref("Widths", "total", "J");
+        let [high, low] = total.to_be_bytes();
+        builder.static_method(
+            "storeInt",
+            "()V",
+            1,
+            0,
+            &[
+                0x04, // iconst_1
+                0xb3, high, low, // putstatic total:J
+                0xb1, // return
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Widths", "storeInt", "()V", Vec::new()),
+            Err(InterpreterError::FieldTypeMismatch { field, descriptor }) if field == "Widths.total" && descriptor == "J"
+        ));
+    }
+
+    #[test]
+    fn prints_boolean_fields_as_true_and_false() {
+        let (result, stdout) = run_fixture("Flags");
+
+        result.unwrap();
+        assert_eq!(stdout, "true\nfalse\n");
+    }
+
+    #[test]
+    fn bipush_sign_extends_its_operand() {
+        let mut builder = ClassBuilder::new("Bipush");
+        builder.static_method(
+            "minusOne",
+            "()I",
+            1,
+            0,
+            &[
+                0x10, 0xff, // bipush -1
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Bipush", "minusOne", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(-1)))
+        ));
+    }
+
+    #[test]
+    fn counting_loop_sums_one_to_five() {
+        let (result, stdout) = run_fixture("SumLoop");
+
+        result.unwrap();
+        assert_eq!(stdout, "15\n");
+    }
+
+    #[test]
+    fn wide_iinc_and_wide_local_indices() {
+        let mut builder = ClassBuilder::new("Wide");
+        builder.static_method(
+            "addThousand",
+            "(I)I",
+            1,
+            1,
+            &[
+                0xc4, 0x84, 0x00, 0x00, 0x03, 0xe8, // wide iinc 0 1000
+                0x1a, // iload_0
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method(
+            "highLocal",
+            "()I",
+            1,
+            301,
+            &[
+                0x10, 0x07, // bipush 7
+                0xc4, 0x36, 0x01, 0x2c, // wide istore 300
+                0xc4, 0x15, 0x01, 0x2c, // wide iload 300
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        let result = call_static(&mut interpreter, "Wide", "addThousand", "(I)I", vec![OperandStackEntry::Int(5)]);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(1005)))));
+
+        let result = call_static(&mut interpreter, "Wide", "highLocal", "()I", Vec::new());
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(7)))));
+    }
+
+    #[test]
+    fn adds_longs_and_doubles_through_locals() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        let arguments = vec![OperandStackEntry::Long(i64::MAX - 1), OperandStackEntry::Long(1)];
+        let result = call_static(&mut interpreter, "TwoSlotMath", "addLongs", "(JJ)J", arguments);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Long(i64::MAX)))));
+
+        let arguments = vec![OperandStackEntry::Double(1.5), OperandStackEntry::Double(2.25)];
+        let result = call_static(&mut interpreter, "TwoSlotMath", "addDoubles", "(DD)D", arguments);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Double(sum))) if sum == 3.75));
+
+        let arguments = vec![OperandStackEntry::Float(0.5)];
+        let result = call_static(&mut interpreter, "TwoSlotMath", "copyFloat", "(F)F", arguments);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Float(value))) if value == 0.5));
+    }
+
+    #[test]
+    fn category_2_locals_take_two_indices() {
+        let mut locals = LocalVariables::new(3);
+
+        locals.store(0, OperandStackEntry::Long(5)).unwrap();
+        assert!(matches!(locals.load(0), Ok(OperandStackEntry::Long(5))));
+        assert!(matches!(locals.load(1), Err(InterpreterError::UninitializedLocal { index: 1 })));
+
+        // Storing into the second index clobbers the long
+        locals.store(1, OperandStackEntry::Int(1)).unwrap();
+        assert!(matches!(locals.load(0), Err(InterpreterError::UninitializedLocal { index: 0 })));
+
+        // A double needs both of its indices to be in range
+        assert!(matches!(
+            locals.store(2, OperandStackEntry::Double(1.0)),
+            Err(InterpreterError::LocalOutOfRange { index: 2, max_locals: 3 })
+        ));
+    }
+
+    #[test]
+    fn recursion_runs_on_the_frame_stack() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        // 20! does not fit in an int, so it wraps around like in Java
+        let arguments = vec![OperandStackEntry::Int(20)];
+        let result = call_static(&mut interpreter, "Recursion", "factorial", "(I)I", arguments);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(-2102132736)))));
+
+        // Far deeper than the native stack would allow if every call recursed in Rust
+        let arguments = vec![OperandStackEntry::Int(100_000)];
+        let result = call_static(&mut interpreter, "Recursion", "depth", "(I)I", arguments);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(100_000)))));
+    }
+
+    #[test]
+    fn running_without_frames_is_an_error() {
+        let (mut interpreter, _) = interpreter_with(Vec::new(), InterpreterConfig::default());
+
+        assert!(matches!(
+            interpreter.run_frames(&mut Vec::new()),
+            Err(InterpreterError::EmptyCallStack)
+        ));
+    }
+
+    #[test]
+    fn int_arithmetic() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        let mut evaluate = |name: &str, value1: i32, value2: i32| {
+            let arguments = vec![OperandStackEntry::Int(value1), OperandStackEntry::Int(value2)];
+
+            match call_static(&mut interpreter, "IntMath", name, "(II)I", arguments) {
+                Ok(Some(OperandStackEntry::Int(result))) => result,
+                result => panic!("Expected IntMath.{name} to return an int, but got {result:?}"),
+            }
+        };
+
+        assert_eq!(evaluate("subtract", 3, 10), -7);
+        assert_eq!(evaluate("multiply", -6, 7), -42);
+        assert_eq!(evaluate("divide", -7, 2), -3);
+        assert_eq!(evaluate("divide", i32::MIN, -1), i32::MIN);
+        assert_eq!(evaluate("remainder", -7, 2), -1);
+        assert_eq!(evaluate("negate", i32::MIN, 0), i32::MIN);
+        assert_eq!(evaluate("shiftLeft", 1, 33), 2);
+        assert_eq!(evaluate("shiftRight", -8, 1), -4);
+        assert_eq!(evaluate("unsignedShiftRight", -1, 28), 15);
+        assert_eq!(evaluate("bits", 0b1100, 0b1010), (0b1100 & 0b1010) | (0b1100 ^ 0b1010));
+    }
+
+    #[test]
+    fn division_by_zero_throws_arithmetic_exception() {
+        let (result, stdout) = run_fixture("DivideByZero");
+
+        result.unwrap();
+        assert_eq!(stdout, "1\n");
+
+        let (mut interpreter, _) = fixture_interpreter();
+        let arguments = vec![OperandStackEntry::Int(1), OperandStackEntry::Int(0)];
+
+        assert!(matches!(
+            call_static(&mut interpreter, "IntMath", "remainder", "(II)I", arguments),
+            Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/ArithmeticException"
+        ));
+    }
+
+    #[test]
+    fn array_copy_copies_sub_ranges() {
+        let (result, stdout) = run_fixture("ArrayCopy");
+
+        result.unwrap();
+        assert_eq!(
+            stdout.lines().collect::<Vec<_>>(),
+            [
+                // A sub-range into another array
+                "0", "0", "2", "3", "4",
+                // An overlapping range within the same array
+                "1", "1", "2", "3", "4",
+                // Out of bounds, null and mismatched arrays
+                "-1", "-2", "-3",
+            ]
+        );
+    }
+
+    #[test]
+    fn natives_are_called_from_bytecode() {
+        let (mut interpreter, stdout) = fixture_interpreter();
+
+        interpreter.register_native(
+            "Natives",
+            "twice",
+            "(I)I",
+            Box::new(|arguments| match arguments {
+                [OperandStackEntry::Int(value)] => Some(OperandStackEntry::Int(value * 2)),
+                _ => None,
+            }),
+        );
+
+        interpreter.run_main("Natives").unwrap();
+        assert_eq!(stdout.contents(), "42\n");
+    }
+
+    #[test]
+    fn invocation_failures_are_errors() {
+        // Natives.twice is declared native, but nothing is registered for it
+        let (result, _) = run_fixture("Natives");
+        assert!(matches!(result, Err(InterpreterError::UnsatisfiedLink { method }) if method == "Natives.twice(I)I"));
+
+        let (mut interpreter, _) = fixture_interpreter();
+        interpreter.register_native("Natives", "twice", "(I)I", Box::new(|_| None));
+
+        assert!(matches!(
+            interpreter.run_main("Natives"),
+            Err(InterpreterError::NativeReturnMismatch { method }) if method == "Natives.twice(I)I"
+        ));
+
+        let mut builder = ClassBuilder::new("Calls");
+        let missing = builder.method_ref("Calls", "missing", "()V");
+        let instance = builder.method_ref("Calls", "instance", "()V");
+        builder.static_method("callMissing", "()V", 0, 0, &[0xb8, (missing >> 8) as u8, missing as u8, 0xb1]);
+        builder.static_method("callInstance", "()V", 0, 0, &[0xb8, (instance >> 8) as u8, instance as u8, 0xb1]);
+        builder.method(ACC_PUBLIC, "instance", "()V", 0, 1, &[0xb1]);
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Calls", "callMissing", "()V", Vec::new()),
+            Err(InterpreterError::NoSuchMethod { method }) if method == "Calls.missing()V"
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Calls", "callInstance", "()V", Vec::new()),
+            Err(InterpreterError::IncompatibleClassChange { expected_static: true, .. })
+        ));
+    }
+
+    #[test]
+    fn backward_branches_are_relative_to_the_branch() {
+        let offset = (&[0xFF, 0xFB][..]).parse_i2().unwrap();
+
+        assert_eq!(branch_target(10, offset as i32), 5);
+    }
+
+    #[test]
+    fn system_out_and_err_print_to_their_own_streams() {
+        let (mut interpreter, stdout) = fixture_interpreter();
+        let stderr = SharedBuffer::default();
+        interpreter.set_stderr(Box::new(stderr.clone()));
+
+        interpreter.run_main("PrintStreams").unwrap();
+
+        assert_eq!(stdout.contents(), "to out\n");
+        assert_eq!(stderr.contents(), "to err\n");
+    }
+
+    #[test]
+    fn records_opcode_counts_when_enabled() {
+        let (mut interpreter, _) = fixture_interpreter_with(InterpreterConfig {
+            record_opcode_counts: true,
+            ..InterpreterConfig::default()
+        });
+
+        interpreter.run_main("SumLoop").unwrap();
+
+        let counts = interpreter.opcode_counts();
+        // The loop body runs five times and its condition is checked a sixth time to exit
+        assert_eq!(counts.get(&OpCodeType::iinc), Some(&5));
+        assert_eq!(counts.get(&OpCodeType::goto), Some(&5));
+        assert_eq!(counts.get(&OpCodeType::if_icmpgt), Some(&6));
+        assert_eq!(counts.get(&OpCodeType::invokestatic), Some(&1));
+
+        let (mut interpreter, _) = fixture_interpreter();
+        interpreter.run_main("SumLoop").unwrap();
+
+        assert!(interpreter.opcode_counts().is_empty());
+    }
+
+    #[test]
+    fn dup2_duplicates_two_ints_or_one_long() {
+        let mut builder = ClassBuilder::new("Dup2");
+        builder.static_method(
+            "ints",
+            "()I",
+            4,
+            0,
+            &[
+                0x04, // iconst_1
+                0x05, // iconst_2
+                0x5c, // dup2
+                0x60, 0x60, 0x60, // iadd x3
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method(
+            "long",
+            "()J",
+            4,
+            0,
+            &[
+                0x0a, // lconst_1
+                0x5c, // dup2
+                0x61, // ladd
+                0xad, // lreturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        let result = call_static(&mut interpreter, "Dup2", "ints", "()I", Vec::new());
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(6)))));
+
+        let result = call_static(&mut interpreter, "Dup2", "long", "()J", Vec::new());
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Long(2)))));
+    }
+
+    #[test]
+    fn traces_every_executed_instruction() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        let arguments = vec![OperandStackEntry::Int(5), OperandStackEntry::Int(3)];
+        let (result, logs) =
+            capture_logs(|| call_static(&mut interpreter, "IntMath", "subtract", "(II)I", arguments));
+
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(2)))));
+        assert_eq!(
+            logs,
+            [
+                (log::Level::Trace, String::from("IntMath.subtract(II)I pc=0 opcode=0x1a top=Some(Int(5))")),
+                (log::Level::Trace, String::from("IntMath.subtract(II)I pc=1 opcode=0x1b top=Some(Int(3))")),
+                (log::Level::Trace, String::from("IntMath.subtract(II)I pc=2 opcode=0x64 top=Some(Int(2))")),
+                (log::Level::Trace, String::from("IntMath.subtract(II)I pc=3 opcode=0xac top=None")),
+            ]
+        );
+    }
+
+    #[test]
+    fn operand_stack_checks_its_depth() {
+        let mut stack = OperandStack::new(2, true);
+
+        stack.push(OperandStackEntry::Int(1)).unwrap();
+        // A long needs two slots, but only one is left
+        assert!(matches!(
+            stack.push(OperandStackEntry::Long(2)),
+            Err(InterpreterError::StackOverflow { max_stack: 2 })
+        ));
+        stack.push(OperandStackEntry::Int(2)).unwrap();
+        assert!(matches!(
+            stack.push(OperandStackEntry::Int(3)),
+            Err(InterpreterError::StackOverflow { max_stack: 2 })
+        ));
+
+        assert!(matches!(stack.pop(), Ok(OperandStackEntry::Int(2))));
+        assert!(matches!(stack.pop(), Ok(OperandStackEntry::Int(1))));
+        assert!(matches!(stack.pop(), Err(InterpreterError::StackUnderflow)));
+    }
+
+    #[test]
+    fn popping_an_empty_stack_is_an_error() {
+        let mut builder = ClassBuilder::new("Underflow");
+        builder.static_method("add", "()I", 2, 0, &[0x04, 0x60, 0xac]); // iconst_1, iadd, ireturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Underflow", "add", "()I", Vec::new()),
+            Err(InterpreterError::StackUnderflow)
+        ));
+    }
+
+    #[test]
+    fn unimplemented_opcodes_halt_or_are_skipped() {
+        let class = || {
+            let mut builder = ClassBuilder::new("Unimplemented");
+            let [high, low] = builder.class("java/lang/String").to_be_bytes();
+            builder.static_method(
+                "seven",
+                "()I",
+                1,
+                0,
+                &[
+                    0x01, // aconst_null
+                    0xc0, high, low, // checkcast java/lang/String
+                    0x57, // pop
+                    0x10, 0x07, // bipush 7
+                    0xac, // ireturn
+                ],
+            );
+
+            builder.parse()
+        };
+
+        let (mut interpreter, _) = interpreter_with(vec![class()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Unimplemented", "seven", "()I", Vec::new()),
+            Err(InterpreterError::UnimplementedOpcode { opcode: 0xc0, pc: 1 })
+        ));
+
+        let config = InterpreterConfig {
+            on_unimplemented: UnimplementedPolicy::WarnAndSkip,
+            ..Default::default()
+        };
+        let (mut interpreter, _) = interpreter_with(vec![class()], config);
+
+        let (result, logs) =
+            capture_logs(|| call_static(&mut interpreter, "Unimplemented", "seven", "()I", Vec::new()));
+
+        // `checkcast` leaves the stack as it was, so skipping it happens to be harmless here
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(7)))));
+        assert!(logs.contains(&(
+            log::Level::Warn,
+            String::from("Skipping unimplemented instruction 0xc0 at 1")
+        )));
+    }
+
+    #[test]
+    fn getfield_reads_a_field_declared_by_the_superclass() {
+        // The Fieldref names `Inherited`, but `value` is declared by `Base`
+        let (result, stdout) = run_fixture("Inherited");
+
+        result.unwrap();
+        assert_eq!(stdout, "5\n");
+    }
+
+    #[test]
+    fn main_receives_an_empty_string_array() {
+        let (result, stdout) = run_fixture("ArgsLength");
+
+        result.unwrap();
+        assert_eq!(stdout, "0\n");
+
+        // An instance `main` (JEP 445) is called on a new instance, still with an empty array
+        let (result, stdout) = run_fixture("InstanceMain");
+
+        result.unwrap();
+        assert_eq!(stdout, "0\n");
+
+        let (result, stdout) = run_fixture("NoArgsMain");
+
+        result.unwrap();
+        assert_eq!(stdout, "no arguments\n");
+    }
+
+    #[test]
+    fn stack_trace_names_the_line_of_every_frame() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        let result = interpreter.run_main("Thrower");
+
+        assert!(matches!(
+            result,
+            Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/ArithmeticException"
+        ));
+        assert_eq!(
+            interpreter
+                .current_stack_trace()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "Thrower.inner(Thrower.java:3)",
+                "Thrower.outer(Thrower.java:7)",
+                "Thrower.main(Thrower.java:11)",
+            ]
+        );
+
+        let without_lines = StackTraceElement {
+            class_name: String::from("com/example/Generated"),
+            method_name: String::from("run"),
+            source_file: None,
+            line_number: None,
+        };
+        assert_eq!(without_lines.to_string(), "com.example.Generated.run(Unknown Source)");
+    }
+
+    #[test]
+    fn unassigned_static_int_reads_as_zero() {
+        let (result, stdout) = run_fixture("UnsetStatic");
+
+        result.unwrap();
+        assert_eq!(stdout, "0\n");
+    }
+
+    #[test]
+    fn string_intrinsics_count_utf16_code_units() {
+        let (result, stdout) = run_fixture("Strings");
+
+        // The emoji is a surrogate pair, so it counts as 2 and `b` is at index 3
+        result.unwrap();
+        assert_eq!(stdout, "4\nb\ntrue\nfalse\nout of range\n");
+    }
+
+    #[test]
+    fn unsupported_string_method_is_an_error() {
+        let mut builder = ClassBuilder::new("StringMethods");
+        let [string_high, string_low] = builder.string("text").to_be_bytes();
+        let [method_high, method_low] = builder.method_ref("java/lang/String", "isEmpty", "()Z").to_be_bytes();
+        builder.static_method(
+            "isEmpty",
+            "()Z",
+            1,
+            0,
+            &[
+                0x13, string_high, string_low, // ldc_w "text"
+                0xb6, method_high, method_low, // invokevirtual String.isEmpty
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "StringMethods", "isEmpty", "()Z", Vec::new()),
+            Err(InterpreterError::UnsupportedIntrinsic { method }) if method == "java/lang/String.isEmpty()Z"
+        ));
+    }
+
+    #[test]
+    fn areturn_from_an_int_method_is_an_error_with_strict_returns() {
+        let class = || {
+            let mut builder = ClassBuilder::new("WrongReturn");
+            builder.static_method(
+                "number",
+                "()I",
+                1,
+                0,
+                &[
+                    0x01, // aconst_null
+                    0xb0, // areturn
+                ],
+            );
+
+            builder.parse()
+        };
+
+        let config = InterpreterConfig {
+            strict_returns: true,
+            ..Default::default()
+        };
+        let (mut interpreter, _) = interpreter_with(vec![class()], config);
+
+        assert!(matches!(
+            call_static(&mut interpreter, "WrongReturn", "number", "()I", Vec::new()),
+            Err(InterpreterError::ReturnTypeMismatch {
+                method,
+                return_type: ReturnType::Value(FieldType::Int),
+                opcode: 0xb0,
+            }) if method == "WrongReturn.number()I"
+        ));
+
+        // Unchecked, the null is returned as is
+        let (mut interpreter, _) = interpreter_with(vec![class()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "WrongReturn", "number", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Null))
+        ));
+    }
+
+    #[test]
+    fn println_ends_lines_with_the_configured_separator() {
+        let (mut interpreter, stdout) = fixture_interpreter_with(InterpreterConfig {
+            line_separator: String::from("\r\n"),
+            ..InterpreterConfig::default()
+        });
+        let stderr = SharedBuffer::default();
+        interpreter.set_stderr(Box::new(stderr.clone()));
+
+        interpreter.run_main("PrintStreams").unwrap();
+
+        assert_eq!(stdout.contents(), "to out\r\n");
+        assert_eq!(stderr.contents(), "to err\r\n");
+    }
+
+    #[test]
+    fn stores_a_computed_int_and_reloads_it() {
+        let mut builder = ClassBuilder::new("Locals");
+        builder.static_method(
+            "compute",
+            "(I)I",
+            2,
+            3,
+            &[
+                0x1a, // iload_0
+                0x06, // iconst_3
+                0x60, // iadd
+                0x3c, // istore_1
+                0x01, // aconst_null
+                0x3a, 0x02, // astore 2
+                0x2c, // aload_2
+                0x57, // pop
+                0x15, 0x01, // iload 1
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Locals", "compute", "(I)I", vec![OperandStackEntry::Int(4)]),
+            Ok(Some(OperandStackEntry::Int(7)))
+        ));
+    }
+
+    #[test]
+    fn getstatic_pushes_the_modeled_standard_streams() {
+        let mut builder = ClassBuilder::new("Streams");
+        let [out_high, out_low] = builder.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;").to_be_bytes();
+        let [err_high, err_low] = builder.field_ref("java/lang/System", "err", "Ljava/io/PrintStream;").to_be_bytes();
+        builder.static_method("out", "()Ljava/io/PrintStream;", 1, 0, &[0xb2, out_high, out_low, 0xb0]); // getstatic, areturn
+        builder.static_method("err", "()Ljava/io/PrintStream;", 1, 0, &[0xb2, err_high, err_low, 0xb0]); // getstatic, areturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Streams", "out", "()Ljava/io/PrintStream;", Vec::new()),
+            Ok(Some(OperandStackEntry::PrintStream(StreamKind::Out)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Streams", "err", "()Ljava/io/PrintStream;", Vec::new()),
+            Ok(Some(OperandStackEntry::PrintStream(StreamKind::Err)))
+        ));
+        // Nothing is allocated to stand in for the streams
+        assert_eq!(interpreter.heap().len(), 0);
+    }
+
+    #[test]
+    #[ignore = "prints 100k lines, run with --ignored when working on the print path"]
+    fn print_loop_allocates_no_java_objects() {
+        let (mut interpreter, stdout) = fixture_interpreter();
+
+        interpreter.run_main("PrintLoop").unwrap();
+
+        let output = stdout.contents();
+        assert_eq!(output.lines().count(), 100_000);
+        assert_eq!(output.lines().last(), Some("99999"));
+        // 100k `getstatic System.out` later only the `String[]` passed to `main` is on the Java heap
+        // (this counts interpreter heap objects, not Rust allocations)
+        assert_eq!(interpreter.heap().len(), 1);
+    }
+
+    #[test]
+    fn invokestatic_passes_arguments_and_pushes_the_result() {
+        let (result, stdout) = run_fixture("StaticAdd");
+
+        result.unwrap();
+        assert_eq!(stdout, "5\n");
+    }
+
+    #[test]
+    fn frame_holds_the_state_of_one_invocation() {
+        let mut builder = ClassBuilder::new("Frames");
+        builder.static_method(
+            "run",
+            "(I)V",
+            2,
+            2,
+            &[
+                0x10, 0x2a, // bipush 42
+                0xb1, // return
+            ],
+        );
+
+        let class = Rc::new(builder.parse());
+        let method = class.get_method("run", "(I)V").unwrap();
+        let mut frame = Frame::new(class.clone(), method, vec![OperandStackEntry::Int(9)], true).unwrap();
+
+        assert_eq!(frame.code(), [0x10, 0x2a, 0xb1]);
+        assert_eq!(frame.pc, 0);
+        assert_eq!(frame.read_u1().unwrap(), 0x10);
+        assert_eq!(frame.read_i1().unwrap(), 42);
+        assert_eq!(frame.pc, 2);
+
+        // The arguments are the first locals
+        assert!(matches!(frame.locals.load(0), Ok(&OperandStackEntry::Int(9))));
+
+        frame.push(OperandStackEntry::Int(1)).unwrap();
+        frame.push(OperandStackEntry::Null).unwrap();
+        assert!(matches!(frame.push(OperandStackEntry::Int(2)), Err(InterpreterError::StackOverflow { max_stack: 2 })));
+
+        assert!(matches!(frame.pop(), Ok(OperandStackEntry::Null)));
+        assert_eq!(frame.pop_int().unwrap(), 1);
+        assert!(matches!(frame.pop(), Err(InterpreterError::StackUnderflow)));
+    }
+
+    #[test]
+    fn ireturn_hands_its_value_to_the_caller() {
+        let mut builder = ClassBuilder::new("Answers");
+        builder.static_method("answer", "()I", 1, 0, &[0x10, 42, 0xac]); // bipush 42, ireturn
+        let answer = builder.method_ref("Answers", "answer", "()I");
+        let [high, low] = answer.to_be_bytes();
+        builder.static_method(
+            "ask",
+            "()I",
+            1,
+            0,
+            &[
+                0xb8, high, low, // invokestatic answer()I
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method("nothing", "()V", 0, 0, &[0xb1]); // return
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Answers", "ask", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(42)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Answers", "nothing", "()V", Vec::new()),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn last_instruction_runs_and_empty_code_is_an_error() {
+        let mut builder = ClassBuilder::new("Endings");
+        // The single-byte ireturn is the last byte of the code, and the value only reaches the
+        // caller if it runs
+        builder.static_method("five", "()I", 1, 0, &[0x08, 0xac]); // iconst_5, ireturn
+        builder.static_method("only", "()V", 0, 0, &[0xb1]); // return
+        builder.static_method("empty", "()V", 0, 0, &[]);
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Endings", "five", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(5)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Endings", "only", "()V", Vec::new()),
+            Ok(None)
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Endings", "empty", "()V", Vec::new()),
+            Err(InterpreterError::EmptyCode { method }) if method == "Endings.empty()V"
+        ));
+    }
+
+    #[test]
+    fn new_dup_and_init_leave_one_reference() {
+        let mut builder = ClassBuilder::new("Empty");
+        let object_init = builder.method_ref("java/lang/Object", "<init>", "()V");
+        let [high, low] = object_init.to_be_bytes();
+        builder.method(
+            ACC_PUBLIC,
+            "<init>",
+            "()V",
+            1,
+            1,
+            &[
+                0x2a, // aload_0
+                0xb7, high, low, // invokespecial java/lang/Object.<init>()V
+                0xb1, // return
+            ],
+        );
+        let class_index = builder.class("Empty");
+        let init = builder.method_ref("Empty", "<init>", "()V");
+        let [class_high, class_low] = class_index.to_be_bytes();
+        let [init_high, init_low] = init.to_be_bytes();
+        builder.static_method(
+            "make",
+            "()LEmpty;",
+            2,
+            0,
+            &[
+                0xbb, class_high, class_low, // new Empty
+                0x59, // dup
+                0xb7, init_high, init_low, // invokespecial Empty.<init>()V
+                0xb0, // areturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        // The constructor consumed the duplicate, so the single reference left is what is returned
+        let Ok(Some(OperandStackEntry::Reference(handle))) =
+            call_static(&mut interpreter, "Empty", "make", "()LEmpty;", Vec::new())
+        else {
+            panic!("Expected make to return a reference");
+        };
+
+        assert_eq!(interpreter.heap().len(), 1);
+        assert!(matches!(
+            interpreter.heap().get(handle),
+            Some(HeapObject::Object { class_name, fields }) if class_name == "Empty" && fields.is_empty()
+        ));
+    }
+
+    #[test]
+    fn constructor_reads_the_default_before_assigning() {
+        let (result, stdout) = run_fixture("Defaults");
+
+        result.unwrap();
+        assert_eq!(stdout, "0\n5\n");
+    }
+
+    #[test]
+    fn field_access_on_null_throws_null_pointer_exception() {
+        let mut builder = ClassBuilder::new("NullFields");
+        builder.field(0, "value", "I", &[]);
+        let value = builder.field_ref("NullFields", "value", "I");
+        let [high, low] = value.to_be_bytes();
+        builder.static_method("read", "()I", 1, 0, &[0x01, 0xb4, high, low, 0xac]); // aconst_null, getfield, ireturn
+        builder.static_method("write", "()V", 2, 0, &[0x01, 0x04, 0xb5, high, low, 0xb1]); // aconst_null, iconst_1, putfield, return
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        for (name, descriptor) in [("read", "()I"), ("write", "()V")] {
+            assert!(matches!(
+                call_static(&mut interpreter, "NullFields", name, descriptor, Vec::new()),
+                Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/NullPointerException"
+            ));
+        }
+    }
+
+    #[test]
+    fn putfield_stores_a_value_getfield_reads_back() {
+        let mut builder = ClassBuilder::new("Holder");
+        builder.field(0, "value", "I", &[]);
+        let [class_high, class_low] = builder.class("Holder").to_be_bytes();
+        let [high, low] = builder.field_ref("Holder", "value", "I").to_be_bytes();
+        builder.static_method(
+            "roundTrip",
+            "()I",
+            2,
+            1,
+            &[
+                0xbb, class_high, class_low, // new Holder
+                0x4b, // astore_0
+                0x2a, // aload_0
+                0x10, 7, // bipush 7
+                0xb5, high, low, // putfield value:I
+                0x2a, // aload_0
+                0xb4, high, low, // getfield value:I
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Holder", "roundTrip", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(7)))
+        ));
+    }
+
+    #[test]
+    fn duplicated_references_share_the_object() {
+        let mut builder = ClassBuilder::new("Shared");
+        builder.field(0, "value", "I", &[]);
+        let [class_high, class_low] = builder.class("Shared").to_be_bytes();
+        let [high, low] = builder.field_ref("Shared", "value", "I").to_be_bytes();
+        builder.static_method(
+            "mutate",
+            "()I",
+            3,
+            0,
+            &[
+                0xbb, class_high, class_low, // new Shared
+                0x59, // dup
+                0x10, 9, // bipush 9
+                0xb5, high, low, // putfield value:I through the copy
+                0xb4, high, low, // getfield value:I through the original
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Shared", "mutate", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(9)))
+        ));
+        assert_eq!(interpreter.heap().len(), 1);
+    }
+
+    #[test]
+    fn invokevirtual_dispatches_on_the_receiver_class() {
+        let (result, stdout) = run_fixture("Dispatch");
+
+        result.unwrap();
+        assert_eq!(stdout, "tweet\n2\nwoof\n4\nnull\n");
+    }
+
+    #[test]
+    fn invokevirtual_on_null_throws_null_pointer_exception() {
+        let mut builder = ClassBuilder::new("NullReceiver");
+        let [high, low] = builder.method_ref("NullReceiver", "run", "()V").to_be_bytes();
+        builder.static_method("call", "()V", 1, 0, &[0x01, 0xb6, high, low, 0xb1]); // aconst_null, invokevirtual run, return
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "NullReceiver", "call", "()V", Vec::new()),
+            Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/NullPointerException"
+        ));
+    }
+
+    #[test]
+    fn int_arrays_store_load_and_report_their_length() {
+        let mut builder = ClassBuilder::new("IntArrays");
+        builder.static_method(
+            "fill",
+            "()I",
+            4,
+            1,
+            &[
+                0x08, // iconst_5
+                0xbc, 10, // newarray int
+                0x4b, // astore_0
+                0x2a, 0x05, 0x10, 42, // aload_0, iconst_2, bipush 42
+                0x4f, // iastore
+                0x2a, 0x05, 0x2e, // aload_0, iconst_2, iaload
+                0x2a, 0xbe, // aload_0, arraylength
+                0x60, // iadd
+                0x2a, 0x03, 0x2e, // aload_0, iconst_0, iaload
+                0x60, // iadd
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method("outOfBounds", "()I", 2, 0, &[0x08, 0xbc, 10, 0x08, 0x2e, 0xac]); // iconst_5, newarray int, iconst_5, iaload
+        builder.static_method("lengthOfNull", "()I", 1, 0, &[0x01, 0xbe, 0xac]); // aconst_null, arraylength
+        builder.static_method("loadFromNull", "()I", 2, 0, &[0x01, 0x03, 0x2e, 0xac]); // aconst_null, iconst_0, iaload
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        // 42 stored at index 2, a length of 5 and an untouched element that is still 0
+        assert!(matches!(
+            call_static(&mut interpreter, "IntArrays", "fill", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(47)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "IntArrays", "outOfBounds", "()I", Vec::new()),
+            Err(InterpreterError::ArrayIndexOutOfBounds { index: 5, length: 5 })
+        ));
+
+        for name in ["lengthOfNull", "loadFromNull"] {
+            assert!(matches!(
+                call_static(&mut interpreter, "IntArrays", name, "()I", Vec::new()),
+                Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/NullPointerException"
+            ));
+        }
+    }
+
+    #[test]
+    fn print_stream_overloads_format_like_java() {
+        let (result, stdout) = run_fixture("PrintOverloads");
+
+        result.unwrap();
+        assert_eq!(stdout, "1.5\n1.0E10\n0.1\n123456789012\nhi\nxtrue\n2.5\n");
+    }
+
+    #[test]
+    fn unsupported_print_stream_methods_are_errors() {
+        let mut builder = ClassBuilder::new("Flushes");
+        let [out_high, out_low] = builder.field_ref("java/lang/System", "out", "Ljava/io/PrintStream;").to_be_bytes();
+        let [high, low] = builder.method_ref("java/io/PrintStream", "flush", "()V").to_be_bytes();
+        builder.static_method("flush", "()V", 1, 0, &[0xb2, out_high, out_low, 0xb6, high, low, 0xb1]); // System.out.flush()
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Flushes", "flush", "()V", Vec::new()),
+            Err(InterpreterError::UnsupportedIntrinsic { method }) if method == "java/io/PrintStream.flush()V"
+        ));
+    }
+
+    #[test]
+    fn throwing_null_throws_null_pointer_exception() {
+        let mut builder = ClassBuilder::new("ThrowsNull");
+        builder.static_method("run", "()V", 1, 0, &[0x01, 0xbf]); // aconst_null, athrow
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "ThrowsNull", "run", "()V", Vec::new()),
+            Err(InterpreterError::UncaughtException { class_name }) if class_name == "java/lang/NullPointerException"
+        ));
+    }
+
+    #[test]
+    fn ldc_pushes_int_float_and_class_constants() {
+        let mut builder = ClassBuilder::new("Loads");
+        let integer = builder.integer(100_000) as u8;
+        let float = builder.float(2.5) as u8;
+        let class_index = builder.class("java/lang/String") as u8;
+        builder.static_method("integer", "()I", 1, 0, &[0x12, integer, 0xac]); // ldc, ireturn
+        builder.static_method("wideInteger", "()I", 1, 0, &[0x13, 0, integer, 0xac]); // ldc_w, ireturn
+        builder.static_method("float", "()F", 1, 0, &[0x12, float, 0xae]); // ldc, freturn
+        builder.static_method("type", "()Ljava/lang/Class;", 1, 0, &[0x12, class_index, 0xb0]); // ldc, areturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        for name in ["integer", "wideInteger"] {
+            assert!(matches!(
+                call_static(&mut interpreter, "Loads", name, "()I", Vec::new()),
+                Ok(Some(OperandStackEntry::Int(100_000)))
+            ));
+        }
+        assert!(matches!(
+            call_static(&mut interpreter, "Loads", "float", "()F", Vec::new()),
+            Ok(Some(OperandStackEntry::Float(value))) if value == 2.5
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Loads", "type", "()Ljava/lang/Class;", Vec::new()),
+            Ok(Some(OperandStackEntry::Class(name))) if name == "java/lang/String"
+        ));
+    }
+
+    #[test]
+    fn ldc2_w_pushes_long_and_double_constants() {
+        let mut builder = ClassBuilder::new("WideLoads");
+        let [long_high, long_low] = builder.long(123_456_789_012).to_be_bytes();
+        let [double_high, double_low] = builder.double(0.125).to_be_bytes();
+        builder.static_method("long", "()J", 2, 0, &[0x14, long_high, long_low, 0xad]); // ldc2_w, lreturn
+        builder.static_method("double", "()D", 2, 0, &[0x14, double_high, double_low, 0xaf]); // ldc2_w, dreturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "WideLoads", "long", "()J", Vec::new()),
+            Ok(Some(OperandStackEntry::Long(123_456_789_012)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "WideLoads", "double", "()D", Vec::new()),
+            Ok(Some(OperandStackEntry::Double(value))) if value == 0.125
+        ));
+    }
+
+    #[test]
+    fn push_opcodes_push_their_constants() {
+        let mut builder = ClassBuilder::new("Pushes");
+        builder.static_method("bipush", "()I", 1, 0, &[0x10, 100, 0xac]); // bipush 100
+        builder.static_method("sipush", "()I", 1, 0, &[0x11, 0x03, 0xe8, 0xac]); // sipush 1000
+        builder.static_method("negativeSipush", "()I", 1, 0, &[0x11, 0xfc, 0x18, 0xac]); // sipush -1000
+        builder.static_method("iconst", "()I", 1, 0, &[0x06, 0xac]); // iconst_3
+        builder.static_method("iconstM1", "()I", 1, 0, &[0x02, 0xac]); // iconst_m1
+        builder.static_method("lconst", "()J", 2, 0, &[0x0a, 0xad]); // lconst_1
+        builder.static_method("fconst", "()F", 1, 0, &[0x0d, 0xae]); // fconst_2
+        builder.static_method("dconst", "()D", 2, 0, &[0x0f, 0xaf]); // dconst_1
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        let mut call = |name: &str, descriptor: &str| {
+            call_static(&mut interpreter, "Pushes", name, descriptor, Vec::new()).unwrap()
+        };
+
+        assert!(matches!(call("bipush", "()I"), Some(OperandStackEntry::Int(100))));
+        assert!(matches!(call("sipush", "()I"), Some(OperandStackEntry::Int(1000))));
+        assert!(matches!(call("negativeSipush", "()I"), Some(OperandStackEntry::Int(-1000))));
+        assert!(matches!(call("iconst", "()I"), Some(OperandStackEntry::Int(3))));
+        assert!(matches!(call("iconstM1", "()I"), Some(OperandStackEntry::Int(-1))));
+        assert!(matches!(call("lconst", "()J"), Some(OperandStackEntry::Long(1))));
+        assert!(matches!(call("fconst", "()F"), Some(OperandStackEntry::Float(value)) if value == 2.0));
+        assert!(matches!(call("dconst", "()D"), Some(OperandStackEntry::Double(value)) if value == 1.0));
+    }
+
+    #[test]
+    fn understated_max_stack_only_fails_with_strict_stack() {
+        let mut builder = ClassBuilder::new("Understated");
+        builder.static_method("add", "()I", 0, 0, &[0x04, 0x05, 0x60, 0xac]); // iconst_1, iconst_2, iadd, ireturn
+
+        // The stack grows past the declared max_stack of 0 unless it is enforced
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        assert!(matches!(
+            call_static(&mut interpreter, "Understated", "add", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(3)))
+        ));
+
+        let config = InterpreterConfig {
+            strict_stack: true,
+            ..InterpreterConfig::default()
+        };
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], config);
+        assert!(matches!(
+            call_static(&mut interpreter, "Understated", "add", "()I", Vec::new()),
+            Err(InterpreterError::StackOverflow { max_stack: 0 })
+        ));
+    }
+
+    #[test]
+    fn method_resolution_is_cached_per_call_site() {
+        let (mut interpreter, _) = fixture_interpreter();
+
+        // Every recursive call goes through the same Methodref, so it is resolved once
+        let result = call_static(&mut interpreter, "Recursion", "factorial", "(I)I", vec![OperandStackEntry::Int(5)]);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(120)))));
+        assert_eq!(interpreter.resolved_methods.len(), 1);
+
+        // A warm cache gives the same result
+        let result = call_static(&mut interpreter, "Recursion", "factorial", "(I)I", vec![OperandStackEntry::Int(5)]);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(120)))));
+
+        // A native registered later replaces what the recursive call site resolved to, so 5 * -1
+        interpreter.register_native("Recursion", "factorial", "(I)I", Box::new(|_| Some(OperandStackEntry::Int(-1))));
+        assert!(interpreter.resolved_methods.is_empty());
+        let result = call_static(&mut interpreter, "Recursion", "factorial", "(I)I", vec![OperandStackEntry::Int(5)]);
+        assert!(matches!(result, Ok(Some(OperandStackEntry::Int(-5)))));
+    }
+
+    #[test]
+    fn failed_resolution_is_not_cached() {
+        let mut builder = ClassBuilder::new("CallsMissing");
+        let [high, low] = builder.method_ref("CallsMissing", "missing", "()V").to_be_bytes();
+        builder.static_method("call", "()V", 0, 0, &[0xb8, high, low, 0xb1]); // invokestatic missing()V
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        for _ in 0..2 {
+            assert!(matches!(
+                call_static(&mut interpreter, "CallsMissing", "call", "()V", Vec::new()),
+                Err(InterpreterError::NoSuchMethod { method }) if method == "CallsMissing.missing()V"
+            ));
+        }
+        assert!(interpreter.resolved_methods.is_empty());
+    }
+
+    #[test]
+    fn unmodeled_system_fields_are_unsupported() {
+        let mut builder = ClassBuilder::new("ReadsStdin");
+        let [high, low] = builder.field_ref("java/lang/System", "in", "Ljava/io/InputStream;").to_be_bytes();
+        builder.static_method("stdin", "()Ljava/io/InputStream;", 1, 0, &[0xb2, high, low, 0xb0]); // getstatic System.in, areturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "ReadsStdin", "stdin", "()Ljava/io/InputStream;", Vec::new()),
+            Err(InterpreterError::UnsupportedIntrinsic { method }) if method == "java/lang/System.in"
+        ));
+    }
+
+    #[test]
+    fn slot_instructions_do_not_split_category_2_values() {
+        let mut builder = ClassBuilder::new("Splits");
+        builder.static_method("pop2Ints", "()I", 3, 0, &[0x04, 0x05, 0x06, 0x58, 0xac]); // iconst_1..3, pop2, ireturn
+        builder.static_method("pop2Long", "()I", 3, 0, &[0x04, 0x0a, 0x58, 0xac]); // iconst_1, lconst_1, pop2, ireturn
+        builder.static_method("popLong", "()V", 2, 0, &[0x0a, 0x57, 0xb1]); // lconst_1, pop
+        builder.static_method("dupLong", "()V", 4, 0, &[0x0a, 0x59, 0xb1]); // lconst_1, dup
+        builder.static_method("pop2IntOnLong", "()V", 3, 0, &[0x0a, 0x04, 0x58, 0xb1]); // lconst_1, iconst_1, pop2
+        builder.static_method("dup2IntOnLong", "()V", 6, 0, &[0x0a, 0x04, 0x5c, 0xb1]); // lconst_1, iconst_1, dup2
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "Splits", "pop2Ints", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(1)))
+        ));
+        assert!(matches!(
+            call_static(&mut interpreter, "Splits", "pop2Long", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(1)))
+        ));
+
+        for name in ["popLong", "dupLong", "pop2IntOnLong", "dup2IntOnLong"] {
+            assert!(matches!(
+                call_static(&mut interpreter, "Splits", name, "()V", Vec::new()),
+                Err(InterpreterError::OperandTypeMismatch { expected: "category 1 value", found: OperandStackEntry::Long(1) })
+            ));
+        }
+    }
+
+    #[test]
+    fn ints_stored_into_booleans_keep_their_lowest_bit() {
+        let mut builder = ClassBuilder::new("Narrowing");
+        builder.field(ACC_STATIC, "flag", "Z", &[]);
+        builder.field(0, "set", "Z", &[]);
+        let [class_high, class_low] = builder.class("Narrowing").to_be_bytes();
+        let [static_high, static_low] = builder.field_ref("Narrowing", "flag", "Z").to_be_bytes();
+        let [field_high, field_low] = builder.field_ref("Narrowing", "set", "Z").to_be_bytes();
+        let [identity_high, identity_low] = builder.method_ref("Narrowing", "identity", "(Z)Z").to_be_bytes();
+        builder.static_method(
+            "storeStatic",
+            "()Z",
+            1,
+            0,
+            &[
+                0x05, // iconst_2
+                0xb3, static_high, static_low, // putstatic flag:Z
+                0xb2, static_high, static_low, // getstatic flag:Z
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method(
+            "storeField",
+            "()Z",
+            3,
+            0,
+            &[
+                0xbb, class_high, class_low, // new Narrowing
+                0x59, // dup
+                0x06, // iconst_3
+                0xb5, field_high, field_low, // putfield set:Z
+                0xb4, field_high, field_low, // getfield set:Z
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method("identity", "(Z)Z", 1, 1, &[0x1a, 0xac]); // iload_0, ireturn
+        builder.static_method(
+            "passArgument",
+            "()Z",
+            1,
+            0,
+            &[
+                0x05, // iconst_2
+                0xb8, identity_high, identity_low, // invokestatic identity(Z)Z
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        let mut call = |name: &str| call_static(&mut interpreter, "Narrowing", name, "()Z", Vec::new()).unwrap();
+
+        // 2 is even, so the boolean is false, and 3 is odd, so it is true
+        assert!(matches!(call("storeStatic"), Some(OperandStackEntry::Int(0))));
+        assert!(matches!(call("storeField"), Some(OperandStackEntry::Int(1))));
+        assert!(matches!(call("passArgument"), Some(OperandStackEntry::Int(0))));
+    }
+
+    #[test]
+    fn methods_resolve_through_superclasses_of_the_referenced_class() {
+        let (mut interpreter, stdout) = fixture_interpreter();
+
+        interpreter.run_main("SuperCalls").unwrap();
+
+        // Child.base() is declared by Grandparent, so it doesn't initialize Child
+        assert_eq!(stdout.contents(), "1\nChild initialized\n15\n");
+
+        // The call sites remember the class that declares the method, not the one they name
+        let declaring_classes = interpreter
+            .resolved_methods
+            .values()
+            .filter_map(|resolved| match &resolved.target {
+                MethodTarget::Interpreted { class, method_index } => {
+                    Some((class.get_this_class_name().as_str(), class.methods[*method_index].name.as_str()))
+                }
+                MethodTarget::Native(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(declaring_classes.contains(&("Grandparent", "base")));
+        assert!(declaring_classes.contains(&("Grandparent", "describe")));
+    }
+
+    #[test]
+    fn falling_off_the_end_of_the_code_is_an_error() {
+        let mut builder = ClassBuilder::new("NoReturn");
+        builder.static_method("run", "()V", 1, 0, &[0x04, 0x57]); // iconst_1, pop
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+
+        assert!(matches!(
+            call_static(&mut interpreter, "NoReturn", "run", "()V", Vec::new()),
+            Err(InterpreterError::FallsOffEnd { method }) if method == "NoReturn.run()V"
+        ));
+    }
+
+    #[test]
+    fn bad_field_accesses_are_errors() {
+        let mut builder = ClassBuilder::new("FieldErrors");
+        builder.field(0, "value", "I", &[]);
+        let [class_high, class_low] = builder.class("FieldErrors").to_be_bytes();
+        let [value_high, value_low] = builder.field_ref("FieldErrors", "value", "I").to_be_bytes();
+        let [missing_high, missing_low] = builder.field_ref("FieldErrors", "missing", "I").to_be_bytes();
+        builder.static_method(
+            "getMissing",
+            "()I",
+            1,
+            0,
+            &[
+                0xbb, class_high, class_low, // new FieldErrors
+                0xb4, missing_high, missing_low, // getfield missing:I
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method(
+            "putMissing",
+            "()I",
+            2,
+            0,
+            &[
+                0xbb, class_high, class_low, // new FieldErrors
+                0x04, // iconst_1
+                0xb5, missing_high, missing_low, // putfield missing:I
+                0x03, // iconst_0
+                0xac, // ireturn
+            ],
+        );
+        builder.static_method(
+            "getOnArray",
+            "()I",
+            1,
+            0,
+            &[
+                0x04, // iconst_1
+                0xbc, 10, // newarray int
+                0xb4, value_high, value_low, // getfield value:I
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        let mut call = |name: &str| call_static(&mut interpreter, "FieldErrors", name, "()I", Vec::new());
+
+        assert!(matches!(
+            call("getMissing"),
+            Err(InterpreterError::NoSuchField { field }) if field == "FieldErrors.missing"
+        ));
+        assert!(matches!(
+            call("putMissing"),
+            Err(InterpreterError::NoSuchField { field }) if field == "FieldErrors.missing"
+        ));
+        assert!(matches!(
+            call("getOnArray"),
+            Err(InterpreterError::OperandTypeMismatch {
+                expected: "object reference",
+                found: OperandStackEntry::Reference(_),
+            })
+        ));
+    }
+
+    #[test]
+    fn virtual_natives_must_match_their_descriptor() {
+        let mut builder = ClassBuilder::new("VirtualNative");
+        builder.method_with_attributes(ACC_PUBLIC | ACC_NATIVE, "value", "()I", &[]);
+        let [class_high, class_low] = builder.class("VirtualNative").to_be_bytes();
+        let [value_high, value_low] = builder.method_ref("VirtualNative", "value", "()I").to_be_bytes();
+        builder.static_method(
+            "run",
+            "()I",
+            1,
+            0,
+            &[
+                0xbb, class_high, class_low, // new VirtualNative
+                0xb6, value_high, value_low, // invokevirtual value()I
+                0xac, // ireturn
+            ],
+        );
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        interpreter.register_native("VirtualNative", "value", "()I", Box::new(|_| Some(OperandStackEntry::Int(5))));
+        assert!(matches!(
+            call_static(&mut interpreter, "VirtualNative", "run", "()I", Vec::new()),
+            Ok(Some(OperandStackEntry::Int(5)))
+        ));
+
+        interpreter.register_native("VirtualNative", "value", "()I", Box::new(|_| None));
+        assert!(matches!(
+            call_static(&mut interpreter, "VirtualNative", "run", "()I", Vec::new()),
+            Err(InterpreterError::NativeReturnMismatch { method }) if method == "VirtualNative.value()I"
+        ));
+    }
+
+    #[test]
+    fn ldc_of_an_unloadable_constant_is_an_error() {
+        let mut builder = ClassBuilder::new("LoadConstants");
+        let method_type = builder.method_type("()V");
+        let name = builder.utf8("LoadConstants");
+        builder.static_method("loadMethodType", "()V", 1, 0, &[0x12, method_type as u8, 0xb1]); // ldc, return
+        builder.static_method("loadUtf8", "()V", 1, 0, &[0x12, name as u8, 0xb1]); // ldc, return
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        let mut call = |name: &str| call_static(&mut interpreter, "LoadConstants", name, "()V", Vec::new());
+
+        assert!(matches!(call("loadMethodType"), Err(InterpreterError::UnsupportedIntrinsic { .. })));
+        assert!(matches!(
+            call("loadUtf8"),
+            Err(InterpreterError::InvalidLoadConstant { index, opcode: 0x12 }) if index == name
+        ));
+    }
+
+    #[test]
+    fn ldc2_w_of_a_single_slot_constant_is_an_error() {
+        let mut builder = ClassBuilder::new("LoadWide");
+        let [int_high, int_low] = builder.integer(7).to_be_bytes();
+        let [long_high, long_low] = builder.long(7).to_be_bytes();
+        builder.static_method("loadInt", "()J", 2, 0, &[0x14, int_high, int_low, 0xad]); // ldc2_w, lreturn
+        builder.static_method("loadLong", "()J", 2, 0, &[0x14, long_high, long_low, 0xad]); // ldc2_w, lreturn
+
+        let (mut interpreter, _) = interpreter_with(vec![builder.parse()], InterpreterConfig::default());
+        let mut call = |name: &str| call_static(&mut interpreter, "LoadWide", name, "()J", Vec::new());
+
+        assert!(matches!(call("loadLong"), Ok(Some(OperandStackEntry::Long(7)))));
+        assert!(matches!(
+            call("loadInt"),
+            Err(InterpreterError::InvalidLoadConstant { opcode: 0x14, .. })
+        ));
+    }
 }
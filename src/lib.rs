@@ -1,3 +1,10 @@
 mod bytes;
 pub mod class;
-pub mod interpreter;
\ No newline at end of file
+pub mod class_loader;
+pub mod descriptor;
+pub mod heap;
+pub mod instruction;
+pub mod interpreter;
+pub mod verifier;
+#[cfg(test)]
+mod test_support;
@@ -1,25 +1,65 @@
-use std::{env, path::PathBuf};
+use std::{env, path::PathBuf, process};
 
-use rust_jvm::interpreter::run_main;
+use rust_jvm::{class_loader::ClassLoader, interpreter::Interpreter};
 
 fn main() {
     let args: Vec<_> = env::args().collect();
 
     let file_name = args.get(1).expect("Exected file name argument!");
 
-    let class = rust_jvm::class::parse_class_file(&PathBuf::from(file_name))
-        .expect("Could not parse class file");
+    let path = PathBuf::from(file_name);
+
+    let class = rust_jvm::class::parse_class_file(&path).unwrap_or_else(|err| {
+        eprintln!("Could not parse class file {}: {err}", path.display());
+        process::exit(1);
+    });
 
     // println!("{:#?}", class);
 
+    // Dump the parsed structure for tooling instead of running it
+    #[cfg(feature = "serde")]
+    if args.iter().any(|arg| arg == "--json") {
+        match serde_json::to_string_pretty(&class) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("Could not serialize class file {}: {err}", path.display());
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
     assert_eq!(
         &class.magic, b"\xCA\xFE\xBA\xBE",
         "Class magic should be [CA, FE, BA, BE], but got: {:02X?}",
         &class.magic
     );
 
-    assert_eq!(class.get_super_class_name(), "java/lang/Object");
+    assert_eq!(
+        class.get_super_class_name().map(|name| name.as_str()),
+        Some("java/lang/Object")
+    );
     assert_eq!(class.get_this_class_name(), "Main");
 
-    run_main(&class).expect("Error running main()");
+    // Other classes are looked up next to the class that was passed in
+    let class_path = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut class_loader = ClassLoader::new(vec![class_path]);
+    let class_name = class_loader.add_class(class).get_this_class_name().clone();
+
+    let mut interpreter = Interpreter::new(class_loader);
+
+    if let Err(err) = interpreter.run_main(&class_name) {
+        eprintln!("Exception in thread \"main\" {err}");
+
+        for element in interpreter.current_stack_trace() {
+            eprintln!("\tat {element}");
+        }
+
+        process::exit(1);
+    }
 }
@@ -0,0 +1,372 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::{self, Write},
+    path::PathBuf,
+    rc::Rc,
+    sync::Once,
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::class::{parse_class_bytes, ClassFile};
+
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_NATIVE: u16 = 0x0100;
+
+/**
+ * The directory holding the compiled Java programs the tests run (sources next to them in `tests/fixtures`)
+ */
+pub fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+/**
+ * A `Write` that can be handed to the interpreter while the test keeps a handle to read it
+ */
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).expect("Expected output to be UTF-8")
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+thread_local! {
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+    static CAPTURED: RefCell<Vec<(Level, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/**
+ * Records log messages of the threads that are inside `capture_logs`. Tests run on their own
+ * threads, so they only ever see their own messages.
+ */
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        CAPTURING.with(Cell::get)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            CAPTURED.with(|captured| captured.borrow_mut().push((record.level(), record.args().to_string())));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/**
+ * Runs `f` and returns what it logged, at every level
+ */
+pub fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<(Level, String)>) {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        log::set_logger(&CapturingLogger).expect("Expected no other logger to be installed");
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    CAPTURING.with(|capturing| capturing.set(true));
+    let result = f();
+    CAPTURING.with(|capturing| capturing.set(false));
+
+    (result, CAPTURED.with(|captured| captured.take()))
+}
+
+/**
+ * Assembles class files for tests that need bytecode `javac` would not produce (ex. an
+ * understated `max_stack`). Constants are added as they are needed and `Utf8` entries are
+ * shared, so the methods returning an index can be called freely.
+ */
+pub struct ClassBuilder {
+    constants: Vec<u8>,
+    next_index: u16,
+    utf8_indices: HashMap<String, u16>,
+    access_flags: u16,
+    this_class: u16,
+    super_class: u16,
+    fields: Vec<u8>,
+    field_count: u16,
+    methods: Vec<u8>,
+    method_count: u16,
+    attributes: Vec<u8>,
+    attribute_count: u16,
+    major_version: u16,
+}
+
+impl ClassBuilder {
+    /**
+     * A public class extending `java/lang/Object`
+     */
+    pub fn new(name: &str) -> ClassBuilder {
+        let mut builder = ClassBuilder {
+            constants: Vec::new(),
+            next_index: 1,
+            utf8_indices: HashMap::new(),
+            access_flags: ACC_PUBLIC | 0x0020,
+            this_class: 0,
+            super_class: 0,
+            fields: Vec::new(),
+            field_count: 0,
+            methods: Vec::new(),
+            method_count: 0,
+            attributes: Vec::new(),
+            attribute_count: 0,
+            major_version: 52,
+        };
+
+        builder.this_class = builder.class(name);
+        builder.super_class = builder.class("java/lang/Object");
+
+        builder
+    }
+
+    /**
+     * Sets `super_class` to a raw index, so 0 (no superclass) can be tested
+     */
+    pub fn super_class_index(&mut self, index: u16) -> &mut Self {
+        self.super_class = index;
+        self
+    }
+
+    fn push_constant(&mut self, bytes: &[u8], slots: u16) -> u16 {
+        let index = self.next_index;
+
+        self.constants.extend_from_slice(bytes);
+        self.next_index += slots;
+
+        index
+    }
+
+    pub fn utf8(&mut self, value: &str) -> u16 {
+        if let Some(index) = self.utf8_indices.get(value) {
+            return *index;
+        }
+
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+
+        let index = self.push_constant(&bytes, 1);
+        self.utf8_indices.insert(value.to_string(), index);
+
+        index
+    }
+
+    /**
+     * Adds a `Utf8` constant with raw bytes, so malformed modified UTF-8 can be tested
+     */
+    pub fn utf8_bytes(&mut self, value: &[u8]) -> u16 {
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+
+        self.push_constant(&bytes, 1)
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name_index = self.utf8(name);
+
+        self.push_constant(&[&[7][..], &name_index.to_be_bytes()].concat(), 1)
+    }
+
+    pub fn string(&mut self, value: &str) -> u16 {
+        let string_index = self.utf8(value);
+
+        self.push_constant(&[&[8][..], &string_index.to_be_bytes()].concat(), 1)
+    }
+
+    pub fn integer(&mut self, value: i32) -> u16 {
+        self.push_constant(&[&[3][..], &value.to_be_bytes()].concat(), 1)
+    }
+
+    pub fn float(&mut self, value: f32) -> u16 {
+        self.push_constant(&[&[4][..], &value.to_be_bytes()].concat(), 1)
+    }
+
+    pub fn long(&mut self, value: i64) -> u16 {
+        self.push_constant(&[&[5][..], &value.to_be_bytes()].concat(), 2)
+    }
+
+    pub fn double(&mut self, value: f64) -> u16 {
+        self.push_constant(&[&[6][..], &value.to_be_bytes()].concat(), 2)
+    }
+
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+
+        self.push_constant(
+            &[&[12][..], &name_index.to_be_bytes(), &descriptor_index.to_be_bytes()].concat(),
+            1,
+        )
+    }
+
+    fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class_index = self.class(class);
+        let name_and_type_index = self.name_and_type(name, descriptor);
+
+        self.push_constant(
+            &[&[tag][..], &class_index.to_be_bytes(), &name_and_type_index.to_be_bytes()].concat(),
+            1,
+        )
+    }
+
+    pub fn method_type(&mut self, descriptor: &str) -> u16 {
+        let descriptor_index = self.utf8(descriptor);
+
+        self.push_constant(&[&[16][..], &descriptor_index.to_be_bytes()].concat(), 1)
+    }
+
+    pub fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(9, class, name, descriptor)
+    }
+
+    pub fn method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(10, class, name, descriptor)
+    }
+
+    /**
+     * Encodes an attribute with its name and length header
+     */
+    pub fn attribute(&mut self, name: &str, body: &[u8]) -> Vec<u8> {
+        let name_index = self.utf8(name);
+
+        [&name_index.to_be_bytes()[..], &(body.len() as u32).to_be_bytes(), body].concat()
+    }
+
+    pub fn field(&mut self, access_flags: u16, name: &str, descriptor: &str, attributes: &[Vec<u8>]) -> &mut Self {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+
+        self.fields.extend_from_slice(&access_flags.to_be_bytes());
+        self.fields.extend_from_slice(&name_index.to_be_bytes());
+        self.fields.extend_from_slice(&descriptor_index.to_be_bytes());
+        self.fields.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+
+        for attribute in attributes {
+            self.fields.extend_from_slice(attribute);
+        }
+
+        self.field_count += 1;
+        self
+    }
+
+    /**
+     * Adds a method with the given attributes (ex. none for an `abstract` or `native` method)
+     */
+    pub fn method_with_attributes(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) -> &mut Self {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+
+        self.methods.extend_from_slice(&access_flags.to_be_bytes());
+        self.methods.extend_from_slice(&name_index.to_be_bytes());
+        self.methods.extend_from_slice(&descriptor_index.to_be_bytes());
+        self.methods.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+
+        for attribute in attributes {
+            self.methods.extend_from_slice(attribute);
+        }
+
+        self.method_count += 1;
+        self
+    }
+
+    /**
+     * Encodes a `Code` attribute
+     */
+    pub fn code_attribute(&mut self, max_stack: u16, max_locals: u16, code: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+
+        body.extend_from_slice(&max_stack.to_be_bytes());
+        body.extend_from_slice(&max_locals.to_be_bytes());
+        body.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        body.extend_from_slice(code);
+        // No exception table
+        body.extend_from_slice(&0u16.to_be_bytes());
+
+        // No nested attributes
+        body.extend_from_slice(&0u16.to_be_bytes());
+
+        self.attribute("Code", &body)
+    }
+
+    /**
+     * Adds a method with a `Code` attribute
+     */
+    pub fn method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+    ) -> &mut Self {
+        let code_attribute = self.code_attribute(max_stack, max_locals, code);
+
+        self.method_with_attributes(access_flags, name, descriptor, &[code_attribute])
+    }
+
+    /**
+     * Adds a `public static` method with a `Code` attribute
+     */
+    pub fn static_method(&mut self, name: &str, descriptor: &str, max_stack: u16, max_locals: u16, code: &[u8]) -> &mut Self {
+        self.method(ACC_PUBLIC | ACC_STATIC, name, descriptor, max_stack, max_locals, code)
+    }
+
+    pub fn class_attribute(&mut self, attribute: Vec<u8>) -> &mut Self {
+        self.attributes.extend_from_slice(&attribute);
+        self.attribute_count += 1;
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&[0xCA, 0xFE, 0xBA, 0xBE]);
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&self.major_version.to_be_bytes());
+        bytes.extend_from_slice(&self.next_index.to_be_bytes());
+        bytes.extend_from_slice(&self.constants);
+        bytes.extend_from_slice(&self.access_flags.to_be_bytes());
+        bytes.extend_from_slice(&self.this_class.to_be_bytes());
+        bytes.extend_from_slice(&self.super_class.to_be_bytes());
+        // No interfaces
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&self.field_count.to_be_bytes());
+        bytes.extend_from_slice(&self.fields);
+        bytes.extend_from_slice(&self.method_count.to_be_bytes());
+        bytes.extend_from_slice(&self.methods);
+        bytes.extend_from_slice(&self.attribute_count.to_be_bytes());
+        bytes.extend_from_slice(&self.attributes);
+
+        bytes
+    }
+
+    pub fn parse(&self) -> ClassFile {
+        parse_class_bytes(&self.build()).expect("Expected built class to parse")
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    class::{AttributeKind, Attributes, ClassFile},
+    instruction::{branch_targets, highest_local_slot, instruction_length, is_unconditional_transfer},
+};
+
+/**
+ * A problem found by `verify`. Every variant names the method it was found in.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    EmptyCode {
+        name: String,
+        descriptor: String,
+    },
+    /** An instruction is unknown or runs past the end of the code */
+    MalformedInstruction {
+        name: String,
+        descriptor: String,
+        pc: usize,
+        message: String,
+    },
+    /** The last instruction can continue past the end of the code */
+    FallsOffEnd {
+        name: String,
+        descriptor: String,
+        pc: usize,
+    },
+    /** A branch targets a pc outside the code or in the middle of an instruction */
+    InvalidBranchTarget {
+        name: String,
+        descriptor: String,
+        pc: usize,
+        target: i64,
+    },
+    /** An instruction uses a local variable slot at or above `max_locals` */
+    LocalOutOfRange {
+        name: String,
+        descriptor: String,
+        pc: usize,
+        slot: u16,
+        max_locals: u16,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::EmptyCode { name, descriptor } => {
+                write!(f, "{name}{descriptor}: Code is empty")
+            }
+            VerifyError::MalformedInstruction {
+                name,
+                descriptor,
+                pc,
+                message,
+            } => write!(f, "{name}{descriptor} @ {pc}: {message}"),
+            VerifyError::FallsOffEnd {
+                name,
+                descriptor,
+                pc,
+            } => write!(
+                f,
+                "{name}{descriptor} @ {pc}: Execution can fall off the end of the code"
+            ),
+            VerifyError::InvalidBranchTarget {
+                name,
+                descriptor,
+                pc,
+                target,
+            } => write!(
+                f,
+                "{name}{descriptor} @ {pc}: Branch target {target} is not the start of an instruction"
+            ),
+            VerifyError::LocalOutOfRange {
+                name,
+                descriptor,
+                pc,
+                slot,
+                max_locals,
+            } => write!(
+                f,
+                "{name}{descriptor} @ {pc}: Local variable {slot} is out of range (max_locals is {max_locals})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/**
+ * A lightweight subset of the JVM verifier meant to catch obviously broken bytecode before it
+ * reaches the interpreter. For every method with code it checks that:
+ *
+ * - the code is not empty and every instruction decodes
+ * - the last instruction is a return, `athrow`, `goto` or switch
+ * - branch and switch targets land on instruction boundaries
+ * - `max_locals` covers every local variable slot that is used
+ *
+ * Types and stack depths are not checked.
+ */
+pub fn verify(class: &ClassFile) -> Result<(), Vec<VerifyError>> {
+    let mut errors = Vec::new();
+
+    for method in &class.methods {
+        let Some(code_attribute) = method.attributes.get_by_name("Code") else {
+            continue;
+        };
+
+        let AttributeKind::Code { max_locals, code, .. } = &code_attribute.attribute else {
+            continue;
+        };
+
+        let code = code.code_bytes();
+        let name = || method.name.clone();
+        let descriptor = || method.descriptor.clone();
+
+        if code.is_empty() {
+            errors.push(VerifyError::EmptyCode {
+                name: name(),
+                descriptor: descriptor(),
+            });
+            continue;
+        }
+
+        let mut boundaries = HashSet::new();
+        let mut last_pc = 0;
+        let mut pc = 0;
+
+        while pc < code.len() {
+            match instruction_length(code, pc) {
+                Ok(length) => {
+                    boundaries.insert(pc);
+                    last_pc = pc;
+                    pc += length;
+                }
+                Err(err) => {
+                    errors.push(VerifyError::MalformedInstruction {
+                        name: name(),
+                        descriptor: descriptor(),
+                        pc,
+                        message: err.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        // The remaining checks need every instruction to be decodable
+        if pc < code.len() {
+            continue;
+        }
+
+        if !is_unconditional_transfer(code[last_pc]) {
+            errors.push(VerifyError::FallsOffEnd {
+                name: name(),
+                descriptor: descriptor(),
+                pc: last_pc,
+            });
+        }
+
+        let mut pcs = boundaries.iter().copied().collect::<Vec<_>>();
+        pcs.sort_unstable();
+
+        for pc in pcs {
+            for target in branch_targets(code, pc).unwrap_or_default() {
+                if target < 0 || !boundaries.contains(&(target as usize)) {
+                    errors.push(VerifyError::InvalidBranchTarget {
+                        name: name(),
+                        descriptor: descriptor(),
+                        pc,
+                        target,
+                    });
+                }
+            }
+
+            if let Ok(Some(slot)) = highest_local_slot(code, pc) {
+                if slot >= *max_locals {
+                    errors.push(VerifyError::LocalOutOfRange {
+                        name: name(),
+                        descriptor: descriptor(),
+                        pc,
+                        slot,
+                        max_locals: *max_locals,
+                    });
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        class::parse_class_file,
+        test_support::{fixtures_dir, ClassBuilder},
+    };
+
+    #[test]
+    fn branch_into_an_operand_is_rejected() {
+        let mut builder = ClassBuilder::new("MidInstruction");
+        builder.static_method(
+            "jump",
+            "()I",
+            1,
+            0,
+            &[
+                0xa7, 0x00, 0x04, // goto 4, the low byte of the sipush operand
+                0x11, 0x01, 0x02, // sipush 258
+                0xac, // ireturn
+            ],
+        );
+
+        assert_eq!(
+            verify(&builder.parse()),
+            Err(vec![VerifyError::InvalidBranchTarget {
+                name: String::from("jump"),
+                descriptor: String::from("()I"),
+                pc: 0,
+                target: 4,
+            }])
+        );
+    }
+
+    #[test]
+    fn missing_final_return_is_rejected() {
+        let mut builder = ClassBuilder::new("NoReturn");
+        builder.static_method(
+            "store",
+            "()V",
+            1,
+            1,
+            &[
+                0x04, // iconst_1
+                0x3b, // istore_0
+            ],
+        );
+
+        assert_eq!(
+            verify(&builder.parse()),
+            Err(vec![VerifyError::FallsOffEnd {
+                name: String::from("store"),
+                descriptor: String::from("()V"),
+                pc: 1,
+            }])
+        );
+    }
+
+    #[test]
+    fn compiled_classes_pass() {
+        let class = parse_class_file(&fixtures_dir().join("Switches.class")).unwrap();
+
+        assert_eq!(verify(&class), Ok(()));
+    }
+}
@@ -0,0 +1,16 @@
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Retention(RetentionPolicy.RUNTIME)
+@interface Marker {
+    String value();
+}
+
+@Marker("class")
+public class Annotated {
+    @Marker("field")
+    int count;
+
+    @Marker("method")
+    void run() {}
+}
@@ -0,0 +1,5 @@
+public class ArgsLength {
+    public static void main(String[] args) {
+        System.out.println(args.length);
+    }
+}
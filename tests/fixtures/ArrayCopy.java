@@ -0,0 +1,40 @@
+public class ArrayCopy {
+    public static void main(String[] args) {
+        int[] source = new int[5];
+
+        for (int i = 0; i < source.length; i++) {
+            source[i] = i + 1;
+        }
+
+        int[] destination = new int[5];
+        System.arraycopy(source, 1, destination, 2, 3);
+        print(destination);
+
+        System.arraycopy(source, 0, source, 1, 4);
+        print(source);
+
+        try {
+            System.arraycopy(source, 3, destination, 0, 3);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(-1);
+        }
+
+        try {
+            System.arraycopy(null, 0, destination, 0, 1);
+        } catch (NullPointerException e) {
+            System.out.println(-2);
+        }
+
+        try {
+            System.arraycopy(source, 0, new long[5], 0, 1);
+        } catch (ArrayStoreException e) {
+            System.out.println(-3);
+        }
+    }
+
+    static void print(int[] values) {
+        for (int i = 0; i < values.length; i++) {
+            System.out.println(values[i]);
+        }
+    }
+}
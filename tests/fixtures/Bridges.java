@@ -0,0 +1,7 @@
+import java.util.Comparator;
+
+public class Bridges implements Comparator<String> {
+    public int compare(String a, String b) {
+        return a.length() - b.length();
+    }
+}
@@ -0,0 +1,7 @@
+public enum Color {
+    RED,
+    GREEN,
+    BLUE;
+
+    public static final Color DEFAULT = RED;
+}
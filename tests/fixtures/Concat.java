@@ -0,0 +1,6 @@
+// Compiled with --release 11, so the concatenation is an invokedynamic of StringConcatFactory
+public class Concat {
+    static String greet(String name, int count) {
+        return "Hi " + name + " x" + count;
+    }
+}
@@ -0,0 +1,4 @@
+public class Constants {
+    // Not final, so javac reads it with getstatic instead of inlining it
+    public static int VERSION = 7;
+}
@@ -0,0 +1,17 @@
+public class Defaults {
+    int value;
+    int seen;
+
+    Defaults() {
+        // Runs on the uninitialized object, so value still holds its default
+        seen = value;
+        value = 5;
+    }
+
+    public static void main(String[] args) {
+        Defaults defaults = new Defaults();
+
+        System.out.println(defaults.seen);
+        System.out.println(defaults.value);
+    }
+}
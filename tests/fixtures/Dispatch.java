@@ -0,0 +1,44 @@
+class Animal {
+    String sound() {
+        return "...";
+    }
+
+    int legs() {
+        return 4;
+    }
+}
+
+class Bird extends Animal {
+    @Override
+    String sound() {
+        return "tweet";
+    }
+
+    @Override
+    int legs() {
+        return 2;
+    }
+}
+
+class Dog extends Animal {
+    @Override
+    String sound() {
+        return "woof";
+    }
+}
+
+public class Dispatch {
+    public static void main(String[] args) {
+        Animal bird = new Bird();
+        System.out.println(bird.sound());
+        System.out.println(bird.legs());
+
+        // legs() is inherited from Animal
+        Animal dog = new Dog();
+        System.out.println(dog.sound());
+        System.out.println(dog.legs());
+
+        Object nothing = null;
+        System.out.println(nothing);
+    }
+}
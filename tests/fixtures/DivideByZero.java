@@ -0,0 +1,11 @@
+public class DivideByZero {
+    public static void main(String[] args) {
+        int zero = 0;
+
+        try {
+            System.out.println(1 / zero);
+        } catch (ArithmeticException e) {
+            System.out.println(1);
+        }
+    }
+}
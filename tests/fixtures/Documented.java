@@ -0,0 +1,32 @@
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Retention(RetentionPolicy.RUNTIME)
+@interface Author {
+    String value();
+}
+
+@Retention(RetentionPolicy.RUNTIME)
+@interface Info {
+    int version();
+    String name();
+    long id();
+    char grade();
+    RetentionPolicy policy();
+    Class<?> type();
+    int[] sizes();
+    Author author();
+}
+
+@Info(
+    version = 3,
+    name = "x",
+    id = 7L,
+    grade = 'A',
+    policy = RetentionPolicy.CLASS,
+    type = String.class,
+    sizes = {1, 2},
+    author = @Author("me")
+)
+public class Documented {
+}
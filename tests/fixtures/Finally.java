@@ -0,0 +1,11 @@
+public class Finally {
+    static int count;
+
+    static void run() {
+        try {
+            count = 1;
+        } finally {
+            count = 2;
+        }
+    }
+}
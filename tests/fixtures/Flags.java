@@ -0,0 +1,9 @@
+public class Flags {
+    static boolean enabled = true;
+    static boolean disabled;
+
+    public static void main(String[] args) {
+        System.out.println(enabled);
+        System.out.println(disabled);
+    }
+}
@@ -0,0 +1,11 @@
+public interface Greeter {
+    String name();
+
+    default String greeting() {
+        return "Hello";
+    }
+
+    static Greeter anonymous() {
+        return null;
+    }
+}
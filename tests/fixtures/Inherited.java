@@ -0,0 +1,13 @@
+class Base {
+    protected int value = 5;
+}
+
+public class Inherited extends Base {
+    static int read(Inherited inherited) {
+        return inherited.value;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(read(new Inherited()));
+    }
+}
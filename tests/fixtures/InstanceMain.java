@@ -0,0 +1,5 @@
+public class InstanceMain {
+    void main(String[] args) {
+        System.out.println(args.length);
+    }
+}
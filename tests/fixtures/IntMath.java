@@ -0,0 +1,37 @@
+public class IntMath {
+    static int subtract(int a, int b) {
+        return a - b;
+    }
+
+    static int multiply(int a, int b) {
+        return a * b;
+    }
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static int remainder(int a, int b) {
+        return a % b;
+    }
+
+    static int negate(int a, int unused) {
+        return -a;
+    }
+
+    static int shiftLeft(int a, int b) {
+        return a << b;
+    }
+
+    static int shiftRight(int a, int b) {
+        return a >> b;
+    }
+
+    static int unsignedShiftRight(int a, int b) {
+        return a >>> b;
+    }
+
+    static int bits(int a, int b) {
+        return (a & b) | (a ^ b);
+    }
+}
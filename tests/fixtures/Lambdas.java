@@ -0,0 +1,5 @@
+public class Lambdas {
+    static Runnable greeter() {
+        return () -> System.out.println("Hello");
+    }
+}
@@ -0,0 +1,9 @@
+public class LongThenString {
+    static long big() {
+        return 123456789012L;
+    }
+
+    static String name() {
+        return "after";
+    }
+}
@@ -0,0 +1,7 @@
+public class Natives {
+    static native int twice(int value);
+
+    public static void main(String[] args) {
+        System.out.println(twice(21));
+    }
+}
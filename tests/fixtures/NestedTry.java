@@ -0,0 +1,13 @@
+public class NestedTry {
+    static int divide(int value) {
+        try {
+            try {
+                return 10 / value;
+            } catch (ArithmeticException e) {
+                return -1;
+            }
+        } catch (RuntimeException e) {
+            return -2;
+        }
+    }
+}
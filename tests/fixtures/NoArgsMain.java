@@ -0,0 +1,5 @@
+public class NoArgsMain {
+    void main() {
+        System.out.println("no arguments");
+    }
+}
@@ -0,0 +1,11 @@
+public class Outer {
+    private static class Nested {
+    }
+
+    Runnable anonymous() {
+        return new Runnable() {
+            public void run() {
+            }
+        };
+    }
+}
@@ -0,0 +1,11 @@
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Retention(RetentionPolicy.RUNTIME)
+@interface NotNull {
+}
+
+public class Parameters {
+    static void greet(int times, @NotNull String name) {
+    }
+}
@@ -0,0 +1,15 @@
+public class Point {
+    int x;
+    int y;
+
+    Point(int x, int y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    public static void main(String[] args) {
+        Point point = new Point(3, 4);
+
+        System.out.println(point.x + point.y);
+    }
+}
@@ -0,0 +1,7 @@
+public class PrintLoop {
+    public static void main(String[] args) {
+        for (int i = 0; i < 100000; i++) {
+            System.out.println(i);
+        }
+    }
+}
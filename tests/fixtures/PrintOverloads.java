@@ -0,0 +1,13 @@
+public class PrintOverloads {
+    public static void main(String[] args) {
+        System.out.println(1.5f);
+        System.out.println(1.0E10f);
+        System.out.println(0.1f);
+        System.out.println(123456789012L);
+        System.out.println(new char[] { 'h', 'i' });
+        System.out.print('x');
+        System.out.print(true);
+        System.out.println();
+        System.out.println(2.5);
+    }
+}
@@ -0,0 +1,6 @@
+public class PrintStreams {
+    public static void main(String[] args) {
+        System.out.println("to out");
+        System.err.println("to err");
+    }
+}
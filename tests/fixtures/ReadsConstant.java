@@ -0,0 +1,5 @@
+public class ReadsConstant {
+    public static void main(String[] args) {
+        System.out.println(Constants.VERSION);
+    }
+}
@@ -0,0 +1,17 @@
+public class Recursion {
+    static int factorial(int n) {
+        if (n <= 1) {
+            return 1;
+        }
+
+        return n * factorial(n - 1);
+    }
+
+    static int depth(int n) {
+        if (n == 0) {
+            return 0;
+        }
+
+        return depth(n - 1) + 1;
+    }
+}
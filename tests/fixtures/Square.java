@@ -0,0 +1,13 @@
+import java.io.Serializable;
+
+interface Shape {
+}
+
+interface Polygon extends Shape {
+}
+
+abstract class AbstractPolygon implements Polygon {
+}
+
+public class Square extends AbstractPolygon implements Serializable {
+}
@@ -0,0 +1,9 @@
+public class StaticAdd {
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(add(2, 3));
+    }
+}
@@ -0,0 +1,16 @@
+public class Strings {
+    public static void main(String[] args) {
+        String text = "a\uD83D\uDE00b";
+
+        System.out.println(text.length());
+        System.out.println(text.charAt(3));
+        System.out.println(text.equals("a\uD83D\uDE00b"));
+        System.out.println(text.equals("ab"));
+
+        try {
+            text.charAt(4);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println("out of range");
+        }
+    }
+}
@@ -0,0 +1,15 @@
+public class SumLoop {
+    static int sum() {
+        int total = 0;
+
+        for (int i = 1; i <= 5; i++) {
+            total += i;
+        }
+
+        return total;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(sum());
+    }
+}
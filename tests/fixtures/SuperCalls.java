@@ -0,0 +1,32 @@
+class Grandparent {
+    static int base() {
+        return 1;
+    }
+
+    int describe() {
+        return 10;
+    }
+}
+
+class Parent extends Grandparent {
+}
+
+class Child extends Parent {
+    static {
+        System.out.println("Child initialized");
+    }
+
+    @Override
+    int describe() {
+        // invokespecial Parent.describe, which Parent inherits from Grandparent
+        return super.describe() + 5;
+    }
+}
+
+public class SuperCalls {
+    public static void main(String[] args) {
+        // invokestatic Child.base, declared by Grandparent, so Child is not initialized yet
+        System.out.println(Child.base());
+        System.out.println(new Child().describe());
+    }
+}
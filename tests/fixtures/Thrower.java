@@ -0,0 +1,13 @@
+public class Thrower {
+    static int inner(int divisor) {
+        return 10 / divisor;
+    }
+
+    static int outer() {
+        return inner(0);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(outer());
+    }
+}
@@ -0,0 +1,19 @@
+public class TwoSlotMath {
+    static long addLongs(long a, long b) {
+        long sum = a + b;
+
+        return sum;
+    }
+
+    static double addDoubles(double a, double b) {
+        double sum = a + b;
+
+        return sum;
+    }
+
+    static float copyFloat(float value) {
+        float copy = value;
+
+        return copy;
+    }
+}
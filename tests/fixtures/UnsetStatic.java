@@ -0,0 +1,7 @@
+public class UnsetStatic {
+    static int count;
+
+    public static void main(String[] args) {
+        System.out.println(count);
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use rust_jvm::{
+    class_loader::ClassLoader,
+    heap::HeapObject,
+    interpreter::{Interpreter, OperandStackEntry},
+};
+
+/**
+ * A `Write` the interpreter can print to while the test keeps a handle to read it
+ */
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn constructs_a_point_and_prints_the_sum_of_its_fields() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+
+    let mut interpreter = Interpreter::new(ClassLoader::new(vec![fixtures]));
+    let output = Output::default();
+    interpreter.set_stdout(Box::new(output.clone()));
+
+    // `new Point(3, 4)` is `new`, `dup`, the arguments and `invokespecial <init>`, which assigns
+    // the fields through `putfield` before `main` reads them back with `getfield`
+    interpreter.run_main("Point").unwrap();
+
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "7\n");
+
+    // The `dup`'d reference the constructor ran on is the one `main` kept, so there is a single
+    // Point on the heap (next to the empty `String[]` passed to `main`)
+    let points = (0..interpreter.heap().len())
+        .filter_map(|handle| match interpreter.heap().get(handle) {
+            Some(HeapObject::Object { class_name, fields }) if class_name == "Point" => Some(fields),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(points.len(), 1);
+    assert!(matches!(points[0].get("x"), Some(OperandStackEntry::Int(3))));
+    assert!(matches!(points[0].get("y"), Some(OperandStackEntry::Int(4))));
+}